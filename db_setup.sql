@@ -25,4 +25,161 @@ create table if not exists privilege_level (
 
 INSERT INTO privilege_level (role, privelege_level) VALUES ('admin', 999);
 
-SELECT * FROM privilege_level
+
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    token_hash VARCHAR(64) PRIMARY KEY,
+    family_id VARCHAR(32) NOT NULL,
+    username VARCHAR(50) NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+    expires_at BIGINT NOT NULL,
+    used BOOLEAN NOT NULL DEFAULT FALSE,
+    revoked BOOLEAN NOT NULL DEFAULT FALSE
+    );
+
+CREATE INDEX IF NOT EXISTS refresh_tokens_family_idx ON refresh_tokens (family_id);
+
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    jti VARCHAR(64) PRIMARY KEY,
+    expires_at BIGINT NOT NULL
+    );
+
+-- Deliberately no foreign key: the cutoff has to outlive a deleted user,
+-- otherwise their tokens would become valid again
+CREATE TABLE IF NOT EXISTS user_token_cutoffs (
+    username VARCHAR(50) PRIMARY KEY,
+    issued_before BIGINT NOT NULL
+    );
+
+CREATE TABLE IF NOT EXISTS server_settings (
+    key VARCHAR(50) PRIMARY KEY,
+    value VARCHAR(255) NOT NULL
+    );
+
+-- Registration is closed until an admin opens it via /api/admin/registration
+INSERT INTO server_settings (key, value) VALUES ('registration_enabled', 'false')
+ON CONFLICT (key) DO NOTHING;
+
+-- Default role of self-registered users
+INSERT INTO privilege_level (role, privelege_level) VALUES ('user', 1)
+ON CONFLICT (role) DO NOTHING;
+
+-- Disabled users can neither log in nor use their existing tokens
+ALTER TABLE users ADD COLUMN IF NOT EXISTS disabled BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Accounts created by an LDAP login are 'ldap', only those are updated by later LDAP logins
+ALTER TABLE users ADD COLUMN IF NOT EXISTS origin VARCHAR(10) NOT NULL DEFAULT 'local'
+    CHECK (origin IN ('local', 'ldap'));
+
+-- TOTP two-factor authentication
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_secret VARCHAR(64);
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_last_step BIGINT;
+
+CREATE TABLE IF NOT EXISTS mfa_recovery_codes (
+    code_hash VARCHAR(64) PRIMARY KEY,
+    username VARCHAR(50) NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+    used BOOLEAN NOT NULL DEFAULT FALSE
+    );
+
+CREATE TABLE IF NOT EXISTS api_keys (
+    id VARCHAR(32) PRIMARY KEY,
+    key_hash VARCHAR(64) NOT NULL UNIQUE,
+    key_prefix VARCHAR(16) NOT NULL,
+    username VARCHAR(50) NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    read_only BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at BIGINT NOT NULL,
+    expires_at BIGINT,
+    last_used_at BIGINT,
+    revoked BOOLEAN NOT NULL DEFAULT FALSE
+    );
+
+CREATE INDEX IF NOT EXISTS api_keys_username_idx ON api_keys (username);
+
+-- Accounts of an OpenID Connect provider, identified by the issuer and the subject of its ID tokens
+CREATE TABLE IF NOT EXISTS oidc_identities (
+    issuer VARCHAR(255) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    username VARCHAR(50) NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+    linked_at BIGINT NOT NULL,
+    PRIMARY KEY (issuer, subject)
+    );
+
+-- Groups share a folder under root_dir/_groups/<name>
+CREATE TABLE IF NOT EXISTS groups (
+    name VARCHAR(50) PRIMARY KEY,
+    created_at BIGINT NOT NULL
+    );
+
+CREATE TABLE IF NOT EXISTS group_members (
+    group_name VARCHAR(50) NOT NULL REFERENCES groups(name) ON DELETE CASCADE,
+    username VARCHAR(50) NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+    access_level VARCHAR(10) NOT NULL CHECK (access_level IN ('read', 'write', 'manage')),
+    PRIMARY KEY (group_name, username)
+    );
+
+CREATE INDEX IF NOT EXISTS group_members_username_idx ON group_members (username);
+
+-- LDAP accounts created before the origin column have no usable password and no OpenID Connect identity
+UPDATE users SET origin = 'ldap'
+WHERE origin = 'local' AND password_hash = '!'
+  AND username NOT IN (SELECT username FROM oidc_identities);
+
+-- Access control entries on paths of a user or group tree, tree is the username or "_groups/<group>".
+-- The principal is a user or a group, so it has no foreign key. Entries are removed with their principal.
+CREATE TABLE IF NOT EXISTS acl_entries (
+    tree VARCHAR(100) NOT NULL,
+    path TEXT NOT NULL,
+    principal_type VARCHAR(10) NOT NULL CHECK (principal_type IN ('user', 'group')),
+    principal VARCHAR(50) NOT NULL,
+    can_read BOOLEAN NOT NULL DEFAULT FALSE,
+    can_write BOOLEAN NOT NULL DEFAULT FALSE,
+    can_delete BOOLEAN NOT NULL DEFAULT FALSE,
+    can_share BOOLEAN NOT NULL DEFAULT FALSE,
+    inherit BOOLEAN NOT NULL DEFAULT TRUE,
+    PRIMARY KEY (tree, path, principal_type, principal)
+    );
+
+CREATE INDEX IF NOT EXISTS acl_entries_principal_idx ON acl_entries (principal_type, principal);
+
+-- Files and directories shared with another user. A share is tied to the identity of its source, so
+-- nothing else created at the same path is shared. Renaming the source moves the path along, deleting
+-- it deletes the share.
+CREATE TABLE IF NOT EXISTS shares (
+    id BIGSERIAL PRIMARY KEY,
+    owner VARCHAR(50) NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+    recipient VARCHAR(50) NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    path TEXT NOT NULL,
+    file_id TEXT NOT NULL DEFAULT '',
+    is_dir BOOLEAN NOT NULL,
+    allow_upload BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at BIGINT NOT NULL,
+    UNIQUE (recipient, name)
+    );
+
+CREATE INDEX IF NOT EXISTS shares_owner_idx ON shares (owner);
+
+-- Public links to a file or directory, found by the hash of their token. Like shares, they are moved
+-- along when the source is renamed, and revoked when it is deleted.
+CREATE TABLE IF NOT EXISTS share_links (
+    id VARCHAR(32) PRIMARY KEY,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    owner VARCHAR(50) NOT NULL REFERENCES users(username) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+    is_dir BOOLEAN NOT NULL,
+    expires_at BIGINT,
+    max_downloads INTEGER,
+    download_count INTEGER NOT NULL DEFAULT 0,
+    password_hash VARCHAR(100),
+    created_at BIGINT NOT NULL,
+    revoked BOOLEAN NOT NULL DEFAULT FALSE
+    );
+
+CREATE INDEX IF NOT EXISTS share_links_owner_idx ON share_links (owner);
+
+-- File drops are links that only accept uploads into a directory
+ALTER TABLE share_links ADD COLUMN IF NOT EXISTS kind VARCHAR(10) NOT NULL DEFAULT 'download' CHECK (kind IN ('download', 'drop'));
+ALTER TABLE share_links ADD COLUMN IF NOT EXISTS max_bytes BIGINT;
+ALTER TABLE share_links ADD COLUMN IF NOT EXISTS max_files INTEGER;
+ALTER TABLE share_links ADD COLUMN IF NOT EXISTS uploaded_bytes BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE share_links ADD COLUMN IF NOT EXISTS upload_count INTEGER NOT NULL DEFAULT 0;
@@ -1,8 +1,29 @@
 use std::sync::Arc;
+use crate::dao::acl_store::AclStore;
+use crate::dao::credential_store::CredentialStore;
+use crate::dao::share_link_store::ShareLinkStore;
+use crate::dao::share_store::ShareStore;
+use crate::services::authentication::authentication_service::AuthenticationService;
+use crate::services::authentication::login_throttle::LoginThrottle;
+use crate::services::authentication::mfa_service::MfaPolicy;
+use crate::services::authentication::oidc_client::OidcClient;
+use crate::services::file_structure::archive_service::ArchiveLimits;
+use crate::services::file_structure::tus_service::TusSettings;
 use crate::services::locking::directory_locking_manager::DirectoryLockManager;
 
 #[derive(Clone)]
 pub struct AppConfig {
     pub root_dir: Arc<String>,
-    pub directory_lock_manager: DirectoryLockManager
-}
\ No newline at end of file
+    pub directory_lock_manager: DirectoryLockManager,
+    pub authentication_service: AuthenticationService,
+    pub credential_store: Arc<dyn CredentialStore>,
+    pub acl_store: Arc<dyn AclStore>,
+    pub share_store: Arc<dyn ShareStore>,
+    pub share_link_store: Arc<dyn ShareLinkStore>,
+    pub login_throttle: LoginThrottle,
+    pub mfa_policy: MfaPolicy,
+    pub tus_settings: TusSettings,
+    pub archive_limits: ArchiveLimits,
+    /// `None` unless an OpenID Connect provider is configured
+    pub oidc_client: Option<OidcClient>
+}
@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use crate::models::acl::acl_entry::{AclEntry, AclPrincipal};
+
+/// ACL entries are stored per tree, i.e. per user or group folder, and path
+#[async_trait]
+pub trait AclStore: Send + Sync {
+    /// The entries on any of the paths
+    async fn get_entries(&self, tree: &str, paths: &[String]) -> Result<Vec<AclEntry>, String>;
+
+    /// The groups the user is a member of
+    async fn get_user_groups(&self, username: &str) -> Result<Vec<String>, String>;
+
+    /// Adds the entry or replaces the one of the same principal on the path.
+    /// Fails with "Principal not found" for unknown users and groups.
+    async fn set_entry(&self, tree: &str, entry: &AclEntry) -> Result<(), String>;
+
+    /// Returns `false` if there was no such entry
+    async fn remove_entry(&self, tree: &str, path: &str, principal: &AclPrincipal) -> Result<bool, String>;
+
+    /// Moves the entries of the path and everything below it to the new path
+    async fn move_entries(&self, tree: &str, old_path: &str, new_path: &str) -> Result<(), String>;
+
+    /// Removes the entries of the path and everything below it
+    async fn delete_entries(&self, tree: &str, path: &str) -> Result<(), String>;
+}
@@ -0,0 +1,19 @@
+use async_trait::async_trait;
+use crate::models::authentication::api_key::{ApiKeyIdentity, ApiKeyRecord};
+
+#[async_trait]
+pub trait ApiKeyStore: Send + Sync {
+    async fn insert_api_key(&self, record: &ApiKeyRecord, key_hash: &str) -> Result<(), String>;
+
+    async fn list_api_keys(&self, username: &str) -> Result<Vec<ApiKeyRecord>, String>;
+
+    /// Returns `false` if the user has no key with that id
+    async fn revoke_api_key(&self, username: &str, id: &str) -> Result<bool, String>;
+
+    /// Revokes every key of the user
+    async fn revoke_all_api_keys(&self, username: &str) -> Result<(), String>;
+
+    async fn find_api_key(&self, key_hash: &str) -> Result<Option<ApiKeyIdentity>, String>;
+
+    async fn touch_api_key(&self, id: &str, used_at: i64) -> Result<(), String>;
+}
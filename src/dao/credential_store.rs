@@ -0,0 +1,7 @@
+use async_trait::async_trait;
+use crate::models::authentication::verified_user::VerifiedUser;
+
+#[async_trait]
+pub trait CredentialStore: Send + Sync {
+    async fn verify_credentials(&self, username: &str, password: &str) -> Result<VerifiedUser, String>;
+}
@@ -0,0 +1,135 @@
+use async_trait::async_trait;
+use tokio_postgres::Row;
+use crate::dao::acl_store::AclStore;
+use crate::dao::db_pool::get_client;
+use crate::models::acl::acl_entry::{AclEntry, AclPermission, AclPrincipal};
+
+pub struct DbAclStore;
+
+#[async_trait]
+impl AclStore for DbAclStore {
+    async fn get_entries(&self, tree: &str, paths: &[String]) -> Result<Vec<AclEntry>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                "SELECT path, principal_type, principal, can_read, can_write, can_delete, can_share, inherit \
+                 FROM acl_entries WHERE tree = $1 AND path = ANY($2) \
+                 ORDER BY path, principal_type, principal",
+                &[&tree, &paths],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.iter().filter_map(entry_from_row).collect())
+    }
+
+    async fn get_user_groups(&self, username: &str) -> Result<Vec<String>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query("SELECT group_name FROM group_members WHERE username = $1", &[&username])
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.iter().map(|row| row.get("group_name")).collect())
+    }
+
+    async fn set_entry(&self, tree: &str, entry: &AclEntry) -> Result<(), String> {
+        let client = get_client().await?;
+
+        let updated = client
+            .execute(
+                "INSERT INTO acl_entries \
+                 (tree, path, principal_type, principal, can_read, can_write, can_delete, can_share, inherit) \
+                 SELECT $1, $2, $3, $4, $5, $6, $7, $8, $9 \
+                 WHERE ($3 = 'user' AND EXISTS (SELECT 1 FROM users WHERE username = $4)) \
+                    OR ($3 = 'group' AND EXISTS (SELECT 1 FROM groups WHERE name = $4)) \
+                 ON CONFLICT (tree, path, principal_type, principal) DO UPDATE SET \
+                 can_read = EXCLUDED.can_read, can_write = EXCLUDED.can_write, \
+                 can_delete = EXCLUDED.can_delete, can_share = EXCLUDED.can_share, inherit = EXCLUDED.inherit",
+                &[
+                    &tree,
+                    &entry.path,
+                    &entry.principal.kind(),
+                    &entry.principal.name(),
+                    &entry.allows(AclPermission::Read),
+                    &entry.allows(AclPermission::Write),
+                    &entry.allows(AclPermission::Delete),
+                    &entry.allows(AclPermission::Share),
+                    &entry.inherit,
+                ],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if updated == 0 {
+            return Err("Principal not found".to_string());
+        }
+        Ok(())
+    }
+
+    async fn remove_entry(&self, tree: &str, path: &str, principal: &AclPrincipal) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        let deleted = client
+            .execute(
+                "DELETE FROM acl_entries WHERE tree = $1 AND path = $2 AND principal_type = $3 AND principal = $4",
+                &[&tree, &path, &principal.kind(), &principal.name()],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(deleted == 1)
+    }
+
+    async fn move_entries(&self, tree: &str, old_path: &str, new_path: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "UPDATE acl_entries SET path = $3 || substr(path, length($2) + 1) \
+                 WHERE tree = $1 AND (path = $2 OR left(path, length($2) + 1) = $2 || '/')",
+                &[&tree, &old_path, &new_path],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn delete_entries(&self, tree: &str, path: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "DELETE FROM acl_entries WHERE tree = $1 AND (path = $2 OR left(path, length($2) + 1) = $2 || '/')",
+                &[&tree, &path],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
+
+fn entry_from_row(row: &Row) -> Option<AclEntry> {
+    let principal = AclPrincipal::parse(row.get("principal_type"), row.get("principal"))?;
+    let permissions = [
+        ("can_read", AclPermission::Read),
+        ("can_write", AclPermission::Write),
+        ("can_delete", AclPermission::Delete),
+        ("can_share", AclPermission::Share),
+    ]
+        .into_iter()
+        .filter(|(column, _)| row.get::<_, bool>(*column))
+        .map(|(_, permission)| permission)
+        .collect();
+
+    Some(AclEntry {
+        path: row.get("path"),
+        principal,
+        permissions,
+        inherit: row.get("inherit"),
+    })
+}
@@ -0,0 +1,127 @@
+use async_trait::async_trait;
+use tokio_postgres::Row;
+use crate::dao::api_key_store::ApiKeyStore;
+use crate::dao::db_pool::get_client;
+use crate::models::authentication::api_key::{ApiKeyIdentity, ApiKeyRecord};
+
+pub struct DbApiKeyStore;
+
+#[async_trait]
+impl ApiKeyStore for DbApiKeyStore {
+    async fn insert_api_key(&self, record: &ApiKeyRecord, key_hash: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "INSERT INTO api_keys (id, key_hash, key_prefix, username, name, read_only, created_at, expires_at) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                &[
+                    &record.id,
+                    &key_hash,
+                    &record.key_prefix,
+                    &record.username,
+                    &record.name,
+                    &record.read_only,
+                    &record.created_at,
+                    &record.expires_at,
+                ],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn list_api_keys(&self, username: &str) -> Result<Vec<ApiKeyRecord>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                "SELECT id, username, name, key_prefix, read_only, created_at, expires_at, last_used_at, revoked \
+                 FROM api_keys WHERE username = $1 ORDER BY created_at",
+                &[&username],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.iter().map(to_record).collect())
+    }
+
+    async fn revoke_api_key(&self, username: &str, id: &str) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        let updated = client
+            .execute(
+                "UPDATE api_keys SET revoked = TRUE WHERE username = $1 AND id = $2",
+                &[&username, &id],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(updated == 1)
+    }
+
+    async fn revoke_all_api_keys(&self, username: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "UPDATE api_keys SET revoked = TRUE WHERE username = $1 AND NOT revoked",
+                &[&username],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn find_api_key(&self, key_hash: &str) -> Result<Option<ApiKeyIdentity>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                "SELECT k.id, k.username, k.name, k.key_prefix, k.read_only, k.created_at, k.expires_at, \
+                        k.last_used_at, k.revoked, u.role, u.disabled, p.privilege_level \
+                 FROM api_keys k \
+                 JOIN users u ON u.username = k.username \
+                 LEFT JOIN privilege_level p ON p.role = u.role \
+                 WHERE k.key_hash = $1",
+                &[&key_hash],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.first().map(|row| ApiKeyIdentity {
+            key: to_record(row),
+            role: row.get("role"),
+            // Roles without an entry in the privilege table get no privileges
+            privilege: row.get::<_, Option<i16>>("privilege_level").map(i32::from).unwrap_or(0),
+            user_disabled: row.get("disabled"),
+        }))
+    }
+
+    async fn touch_api_key(&self, id: &str, used_at: i64) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute("UPDATE api_keys SET last_used_at = $2 WHERE id = $1", &[&id, &used_at])
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
+
+fn to_record(row: &Row) -> ApiKeyRecord {
+    ApiKeyRecord {
+        id: row.get("id"),
+        username: row.get("username"),
+        name: row.get("name"),
+        key_prefix: row.get("key_prefix"),
+        read_only: row.get("read_only"),
+        created_at: row.get("created_at"),
+        expires_at: row.get("expires_at"),
+        last_used_at: row.get("last_used_at"),
+        revoked: row.get("revoked"),
+    }
+}
@@ -0,0 +1,14 @@
+use async_trait::async_trait;
+use crate::dao::credential_store::CredentialStore;
+use crate::dao::login_verification::verify_user_credentials;
+use crate::models::authentication::verified_user::VerifiedUser;
+
+pub struct DbCredentialStore;
+
+#[async_trait]
+impl CredentialStore for DbCredentialStore {
+    async fn verify_credentials(&self, username: &str, password: &str) -> Result<VerifiedUser, String> {
+        let username = verify_user_credentials(username, password).await?;
+        Ok(VerifiedUser { username, role: None })
+    }
+}
@@ -0,0 +1,124 @@
+use async_trait::async_trait;
+use crate::dao::db_pool::get_client;
+use crate::dao::group_store::GroupStore;
+use crate::models::groups::group_access::GroupAccess;
+use crate::models::groups::group_requests::{GroupMember, GroupSummary};
+use crate::services::file_structure::group_service::group_tree;
+
+pub struct DbGroupStore;
+
+#[async_trait]
+impl GroupStore for DbGroupStore {
+    async fn create_group(&self, name: &str, created_at: i64) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        let inserted = client
+            .execute(
+                "INSERT INTO groups (name, created_at) VALUES ($1, $2) ON CONFLICT (name) DO NOTHING",
+                &[&name, &created_at],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(inserted == 1)
+    }
+
+    async fn delete_group(&self, name: &str) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        let deleted = client
+            .execute("DELETE FROM groups WHERE name = $1", &[&name])
+            .await
+            .map_err(|e| e.to_string())?;
+
+        client
+            .execute(
+                "DELETE FROM acl_entries WHERE tree = $1 OR (principal_type = 'group' AND principal = $2)",
+                &[&group_tree(name), &name],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(deleted == 1)
+    }
+
+    async fn list_groups(&self, username: &str, include_all: bool) -> Result<Vec<GroupSummary>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                "SELECT g.name, m.access_level FROM groups g \
+                 LEFT JOIN group_members m ON m.group_name = g.name AND m.username = $1 \
+                 WHERE $2 OR m.username IS NOT NULL \
+                 ORDER BY g.name",
+                &[&username, &include_all],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows
+            .iter()
+            .map(|row| GroupSummary {
+                name: row.get("name"),
+                access: row
+                    .get::<_, Option<String>>("access_level")
+                    .and_then(|access| GroupAccess::parse(&access)),
+            })
+            .collect())
+    }
+
+    async fn list_members(&self, group: &str) -> Result<Vec<GroupMember>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                "SELECT username, access_level FROM group_members WHERE group_name = $1 ORDER BY username",
+                &[&group],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                GroupAccess::parse(row.get("access_level")).map(|access| GroupMember {
+                    username: row.get("username"),
+                    access,
+                })
+            })
+            .collect())
+    }
+
+    async fn set_member(&self, group: &str, username: &str, access: GroupAccess) -> Result<(), String> {
+        let client = get_client().await?;
+
+        let updated = client
+            .execute(
+                "INSERT INTO group_members (group_name, username, access_level) \
+                 SELECT $1, $2, $3 WHERE EXISTS (SELECT 1 FROM users WHERE username = $2) \
+                 ON CONFLICT (group_name, username) DO UPDATE SET access_level = EXCLUDED.access_level",
+                &[&group, &username, &access.as_str()],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if updated == 0 {
+            return Err("User not found".to_string());
+        }
+        Ok(())
+    }
+
+    async fn remove_member(&self, group: &str, username: &str) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        let deleted = client
+            .execute(
+                "DELETE FROM group_members WHERE group_name = $1 AND username = $2",
+                &[&group, &username],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(deleted == 1)
+    }
+}
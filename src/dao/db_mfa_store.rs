@@ -0,0 +1,118 @@
+use async_trait::async_trait;
+use crate::dao::db_pool::get_client;
+use crate::dao::mfa_store::MfaStore;
+use crate::models::authentication::mfa::MfaState;
+
+pub struct DbMfaStore;
+
+#[async_trait]
+impl MfaStore for DbMfaStore {
+    async fn get_mfa_state(&self, username: &str) -> Result<Option<MfaState>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                "SELECT totp_secret, totp_enabled, totp_last_step FROM users WHERE username = $1",
+                &[&username],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.first().map(|row| MfaState {
+            totp_secret: row.get("totp_secret"),
+            enabled: row.get("totp_enabled"),
+            last_used_step: row.get("totp_last_step"),
+        }))
+    }
+
+    async fn set_pending_secret(&self, username: &str, secret: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "UPDATE users SET totp_secret = $2, totp_enabled = FALSE, totp_last_step = NULL \
+                 WHERE username = $1",
+                &[&username, &secret],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn enable_mfa(&self, username: &str, recovery_code_hashes: &[String]) -> Result<(), String> {
+        let mut client = get_client().await?;
+        let transaction = client.transaction().await.map_err(|e| e.to_string())?;
+
+        transaction
+            .execute("UPDATE users SET totp_enabled = TRUE WHERE username = $1", &[&username])
+            .await
+            .map_err(|e| e.to_string())?;
+        transaction
+            .execute("DELETE FROM mfa_recovery_codes WHERE username = $1", &[&username])
+            .await
+            .map_err(|e| e.to_string())?;
+        for code_hash in recovery_code_hashes {
+            transaction
+                .execute(
+                    "INSERT INTO mfa_recovery_codes (code_hash, username) VALUES ($1, $2)",
+                    &[code_hash, &username],
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        transaction.commit().await.map_err(|e| e.to_string())
+    }
+
+    async fn mark_step_used(&self, username: &str, step: i64) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        // Compare-and-swap, so the same code can't be used by two concurrent logins
+        let updated = client
+            .execute(
+                "UPDATE users SET totp_last_step = $2 \
+                 WHERE username = $1 AND (totp_last_step IS NULL OR totp_last_step < $2)",
+                &[&username, &step],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(updated == 1)
+    }
+
+    async fn consume_recovery_code(&self, username: &str, code_hash: &str) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        let updated = client
+            .execute(
+                "UPDATE mfa_recovery_codes SET used = TRUE \
+                 WHERE username = $1 AND code_hash = $2 AND used = FALSE",
+                &[&username, &code_hash],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(updated == 1)
+    }
+
+    async fn disable_mfa(&self, username: &str) -> Result<(), String> {
+        let mut client = get_client().await?;
+        let transaction = client.transaction().await.map_err(|e| e.to_string())?;
+
+        transaction
+            .execute(
+                "UPDATE users SET totp_secret = NULL, totp_enabled = FALSE, totp_last_step = NULL \
+                 WHERE username = $1",
+                &[&username],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        transaction
+            .execute("DELETE FROM mfa_recovery_codes WHERE username = $1", &[&username])
+            .await
+            .map_err(|e| e.to_string())?;
+
+        transaction.commit().await.map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,84 @@
+use async_trait::async_trait;
+use crate::dao::db_pool::get_client;
+use crate::dao::oidc_identity_store::OidcIdentityStore;
+
+pub struct DbOidcIdentityStore;
+
+#[async_trait]
+impl OidcIdentityStore for DbOidcIdentityStore {
+    async fn find_linked_user(&self, issuer: &str, subject: &str) -> Result<Option<(String, bool)>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                "SELECT u.username, u.disabled FROM oidc_identities i \
+                 JOIN users u ON u.username = i.username \
+                 WHERE i.issuer = $1 AND i.subject = $2",
+                &[&issuer, &subject],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.first().map(|row| (row.get("username"), row.get("disabled"))))
+    }
+
+    async fn get_user_disabled(&self, username: &str) -> Result<Option<bool>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query("SELECT disabled FROM users WHERE username = $1", &[&username])
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.first().map(|row| row.get("disabled")))
+    }
+
+    async fn link_identity(&self, issuer: &str, subject: &str, username: &str, linked_at: i64) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "INSERT INTO oidc_identities (issuer, subject, username, linked_at) VALUES ($1, $2, $3, $4)",
+                &[&issuer, &subject, &username, &linked_at],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn create_linked_user(
+        &self,
+        issuer: &str,
+        subject: &str,
+        username: &str,
+        password_hash: &str,
+        role: &str,
+        linked_at: i64
+    ) -> Result<bool, String> {
+        let mut client = get_client().await?;
+        let transaction = client.transaction().await.map_err(|e| e.to_string())?;
+
+        let inserted = transaction
+            .execute(
+                "INSERT INTO users (username, password_hash, role) VALUES ($1, $2, $3) \
+                 ON CONFLICT (username) DO NOTHING",
+                &[&username, &password_hash, &role],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+        if inserted == 0 {
+            return Ok(false);
+        }
+        transaction
+            .execute(
+                "INSERT INTO oidc_identities (issuer, subject, username, linked_at) VALUES ($1, $2, $3, $4)",
+                &[&issuer, &subject, &username, &linked_at],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        transaction.commit().await.map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+}
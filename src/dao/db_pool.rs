@@ -0,0 +1,37 @@
+use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
+use once_cell::sync::Lazy;
+use std::env;
+use tokio_postgres::{Config, NoTls};
+
+pub(crate) static DB_POOL: Lazy<Pool> = Lazy::new(|| {
+    let host = env::var("POSTGRESQL_HOST").expect("POSTGRESQL_HOST must be set");
+    let user = env::var("POSTGRESQL_USER").expect("POSTGRESQL_USER must be set");
+    let pass = env::var("POSTGRESQL_PASSWORD").expect("POSTGRESQL_PASSWORD must be set");
+    let port = env::var("POSTGRESQL_PORT").expect("POSTGRESQL_PORT must be set");
+    let db   = env::var("POSTGRESQL_DATABASE").expect("POSTGRESQL_DATABASE must be set");
+
+    let mut cfg = Config::new();
+    cfg.host(&host);
+    cfg.user(&user);
+    cfg.password(&pass);
+    cfg.dbname(&db);
+    cfg.port(port.parse().expect("POSTGRESQL_PORT must be a valid integer"));
+
+    let mgr_config = ManagerConfig {
+        recycling_method: RecyclingMethod::Fast
+    };
+
+    let mgr = Manager::from_config(cfg, NoTls, mgr_config);
+
+    Pool::builder(mgr)
+        .max_size(16) // set max connections
+        .build()
+        .expect("Failed to create Deadpool Postgres pool")
+});
+
+pub(crate) async fn get_client() -> Result<deadpool_postgres::Object, String> {
+    DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))
+}
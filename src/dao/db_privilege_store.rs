@@ -1,12 +1,57 @@
 use async_trait::async_trait;
-use crate::dao::login_verification::{check_privileges};
+use crate::dao::db_pool::get_client;
+use crate::dao::login_verification::{check_privileges, get_user_role};
 use crate::dao::privilege_store::PrivilegeStore;
+use crate::models::groups::group_access::GroupAccess;
 
 pub struct DbPrivilegeStore;
 
 #[async_trait]
 impl PrivilegeStore for DbPrivilegeStore {
-    async fn get_privilege_level(&self, role: &str) -> Result<i32, String> {
+    async fn get_privilege_level(&self, role: &str) -> Result<Option<i32>, String> {
         check_privileges(role).await
     }
+
+    async fn get_user_role(&self, username: &str) -> Result<String, String> {
+        get_user_role(username).await
+    }
+
+    async fn is_user_disabled(&self, username: &str) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query("SELECT disabled FROM users WHERE username = $1", &[&username])
+            .await
+            .map_err(|e| e.to_string())?;
+
+        match rows.first() {
+            Some(row) => Ok(row.get("disabled")),
+            None => Err("User not found".to_string()),
+        }
+    }
+
+    async fn group_exists(&self, group: &str) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query("SELECT 1 FROM groups WHERE name = $1", &[&group])
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(!rows.is_empty())
+    }
+
+    async fn get_group_access(&self, group: &str, username: &str) -> Result<Option<GroupAccess>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                "SELECT access_level FROM group_members WHERE group_name = $1 AND username = $2",
+                &[&group, &username],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.first().and_then(|row| GroupAccess::parse(row.get("access_level"))))
+    }
 }
\ No newline at end of file
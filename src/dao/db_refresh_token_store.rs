@@ -0,0 +1,98 @@
+use async_trait::async_trait;
+use crate::dao::db_pool::get_client;
+use crate::dao::refresh_token_store::RefreshTokenStore;
+use crate::models::authentication::refresh_token::RefreshTokenRecord;
+
+pub struct DbRefreshTokenStore;
+
+#[async_trait]
+impl RefreshTokenStore for DbRefreshTokenStore {
+    async fn insert_refresh_token(&self, record: &RefreshTokenRecord) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "INSERT INTO refresh_tokens (token_hash, family_id, username, expires_at, used, revoked) \
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+                &[
+                    &record.token_hash,
+                    &record.family_id,
+                    &record.username,
+                    &record.expires_at,
+                    &record.used,
+                    &record.revoked,
+                ],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn find_refresh_token(&self, token_hash: &str) -> Result<Option<RefreshTokenRecord>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                "SELECT token_hash, family_id, username, expires_at, used, revoked \
+                 FROM refresh_tokens WHERE token_hash = $1",
+                &[&token_hash],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.first().map(|row| RefreshTokenRecord {
+            token_hash: row.get("token_hash"),
+            family_id: row.get("family_id"),
+            username: row.get("username"),
+            expires_at: row.get("expires_at"),
+            used: row.get("used"),
+            revoked: row.get("revoked"),
+        }))
+    }
+
+    async fn mark_refresh_token_used(&self, token_hash: &str) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        // The WHERE clause makes this a compare-and-swap, so two concurrent
+        // refreshes with the same token can't both succeed
+        let updated = client
+            .execute(
+                "UPDATE refresh_tokens SET used = TRUE \
+                 WHERE token_hash = $1 AND used = FALSE AND revoked = FALSE",
+                &[&token_hash],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(updated == 1)
+    }
+
+    async fn revoke_token_family(&self, family_id: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "UPDATE refresh_tokens SET revoked = TRUE WHERE family_id = $1",
+                &[&family_id],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn revoke_user_tokens(&self, username: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "UPDATE refresh_tokens SET revoked = TRUE WHERE username = $1",
+                &[&username],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+}
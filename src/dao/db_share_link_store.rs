@@ -0,0 +1,192 @@
+use async_trait::async_trait;
+use tokio_postgres::Row;
+use crate::dao::db_pool::get_client;
+use crate::dao::share_link_store::ShareLinkStore;
+use crate::models::shares::share_link::{ShareLink, ShareLinkKind};
+
+pub struct DbShareLinkStore;
+
+const LINK_COLUMNS: &str = "id, kind, owner, path, is_dir, expires_at, max_downloads, download_count, \
+                            max_bytes, max_files, uploaded_bytes, upload_count, password_hash, created_at, revoked";
+
+#[async_trait]
+impl ShareLinkStore for DbShareLinkStore {
+    async fn insert_link(&self, link: &ShareLink, token_hash: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "INSERT INTO share_links (id, token_hash, kind, owner, path, is_dir, expires_at, max_downloads, \
+                 max_bytes, max_files, password_hash, created_at) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
+                &[
+                    &link.id,
+                    &token_hash,
+                    &link.kind.as_str(),
+                    &link.owner,
+                    &link.path,
+                    &link.is_dir,
+                    &link.expires_at,
+                    &link.max_downloads,
+                    &link.max_bytes,
+                    &link.max_files,
+                    &link.password_hash,
+                    &link.created_at,
+                ],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn find_link(&self, token_hash: &str) -> Result<Option<ShareLink>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                &format!("SELECT {} FROM share_links WHERE token_hash = $1", LINK_COLUMNS),
+                &[&token_hash],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        rows.first().map(link_from_row).transpose()
+    }
+
+    async fn list_links(&self, owner: &str) -> Result<Vec<ShareLink>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                &format!("SELECT {} FROM share_links WHERE owner = $1 ORDER BY created_at", LINK_COLUMNS),
+                &[&owner],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        rows.iter().map(link_from_row).collect()
+    }
+
+    async fn revoke_link(&self, owner: &str, id: &str) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        let updated = client
+            .execute(
+                "UPDATE share_links SET revoked = TRUE WHERE owner = $1 AND id = $2",
+                &[&owner, &id],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(updated == 1)
+    }
+
+    async fn move_sources(&self, owner: &str, old_path: &str, new_path: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "UPDATE share_links SET path = $3 || substr(path, length($2) + 1) \
+                 WHERE owner = $1 AND (path = $2 OR left(path, length($2) + 1) = $2 || '/')",
+                &[&owner, &old_path, &new_path],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn revoke_sources(&self, owner: &str, path: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "UPDATE share_links SET revoked = TRUE \
+                 WHERE owner = $1 AND (path = $2 OR left(path, length($2) + 1) = $2 || '/')",
+                &[&owner, &path],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn record_upload(&self, id: &str, bytes: i64, now: i64) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        // Like downloads, the caps are checked by the update itself
+        let updated = client
+            .execute(
+                "UPDATE share_links SET upload_count = upload_count + 1, uploaded_bytes = uploaded_bytes + $2 \
+                 WHERE id = $1 AND kind = 'drop' AND NOT revoked \
+                 AND (expires_at IS NULL OR expires_at > $3) \
+                 AND (max_files IS NULL OR upload_count < max_files) \
+                 AND (max_bytes IS NULL OR uploaded_bytes + $2 <= max_bytes)",
+                &[&id, &bytes, &now],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(updated == 1)
+    }
+
+    async fn undo_upload(&self, id: &str, bytes: i64) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "UPDATE share_links SET upload_count = upload_count - 1, uploaded_bytes = uploaded_bytes - $2 \
+                 WHERE id = $1",
+                &[&id, &bytes],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn record_download(&self, id: &str, now: i64) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        // A single statement, so concurrent downloads can't exceed the maximum
+        let updated = client
+            .execute(
+                "UPDATE share_links SET download_count = download_count + 1 \
+                 WHERE id = $1 AND kind = 'download' AND NOT revoked \
+                 AND (expires_at IS NULL OR expires_at > $2) \
+                 AND (max_downloads IS NULL OR download_count < max_downloads)",
+                &[&id, &now],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(updated == 1)
+    }
+}
+
+/// Fails for a kind this version doesn't know, rather than serving the link as something it isn't
+fn link_from_row(row: &Row) -> Result<ShareLink, String> {
+    let password_hash: Option<String> = row.get("password_hash");
+    let kind: String = row.get("kind");
+    let id: String = row.get("id");
+    let kind = ShareLinkKind::parse(&kind).ok_or_else(|| format!("Link {} has the unknown kind '{}'", id, kind))?;
+    Ok(ShareLink {
+        id,
+        kind,
+        owner: row.get("owner"),
+        path: row.get("path"),
+        is_dir: row.get("is_dir"),
+        expires_at: row.get("expires_at"),
+        max_downloads: row.get("max_downloads"),
+        download_count: row.get("download_count"),
+        max_bytes: row.get("max_bytes"),
+        max_files: row.get("max_files"),
+        uploaded_bytes: row.get("uploaded_bytes"),
+        upload_count: row.get("upload_count"),
+        has_password: password_hash.is_some(),
+        password_hash,
+        created_at: row.get("created_at"),
+        revoked: row.get("revoked"),
+    })
+}
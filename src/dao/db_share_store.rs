@@ -0,0 +1,150 @@
+use async_trait::async_trait;
+use tokio_postgres::Row;
+use crate::dao::db_pool::get_client;
+use crate::dao::share_store::ShareStore;
+use crate::models::shares::share::Share;
+
+pub struct DbShareStore;
+
+const SHARE_COLUMNS: &str = "id, owner, recipient, name, path, file_id, is_dir, allow_upload, created_at";
+
+#[async_trait]
+impl ShareStore for DbShareStore {
+    async fn create_share(&self, share: &Share) -> Result<Option<i64>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                "INSERT INTO shares (owner, recipient, name, path, file_id, is_dir, allow_upload, created_at) \
+                 SELECT $1, $2, $3, $4, $5, $6, $7, $8 WHERE EXISTS (SELECT 1 FROM users WHERE username = $2) \
+                 ON CONFLICT (recipient, name) DO NOTHING \
+                 RETURNING id",
+                &[
+                    &share.owner,
+                    &share.recipient,
+                    &share.name,
+                    &share.path,
+                    &share.file_id,
+                    &share.is_dir,
+                    &share.allow_upload,
+                    &share.created_at,
+                ],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if let Some(row) = rows.first() {
+            return Ok(Some(row.get("id")));
+        }
+
+        // Nothing was inserted, either because of the name or the recipient
+        let recipient_exists = client
+            .query("SELECT 1 FROM users WHERE username = $1", &[&share.recipient])
+            .await
+            .map_err(|e| e.to_string())?;
+        if recipient_exists.is_empty() {
+            return Err("User not found".to_string());
+        }
+        Ok(None)
+    }
+
+    async fn list_shares(&self, username: &str, incoming: bool) -> Result<Vec<Share>, String> {
+        let client = get_client().await?;
+
+        let column = if incoming { "recipient" } else { "owner" };
+        let rows = client
+            .query(
+                &format!("SELECT {} FROM shares WHERE {} = $1 ORDER BY name", SHARE_COLUMNS, column),
+                &[&username],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.iter().map(share_from_row).collect())
+    }
+
+    async fn find_share(&self, recipient: &str, name: &str) -> Result<Option<Share>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query(
+                &format!("SELECT {} FROM shares WHERE recipient = $1 AND name = $2", SHARE_COLUMNS),
+                &[&recipient, &name],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.first().map(share_from_row))
+    }
+
+    async fn move_sources(&self, owner: &str, old_path: &str, new_path: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "UPDATE shares SET path = $3 || substr(path, length($2) + 1) \
+                 WHERE owner = $1 AND (path = $2 OR left(path, length($2) + 1) = $2 || '/')",
+                &[&owner, &old_path, &new_path],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn refresh_sources(&self, owner: &str, path: &str, file_id: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "UPDATE shares SET file_id = $3 WHERE owner = $1 AND path = $2 AND NOT is_dir",
+                &[&owner, &path, &file_id],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn delete_sources(&self, owner: &str, path: &str) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "DELETE FROM shares WHERE owner = $1 AND (path = $2 OR left(path, length($2) + 1) = $2 || '/')",
+                &[&owner, &path],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn delete_share(&self, id: i64, username: &str) -> Result<bool, String> {
+        let client = get_client().await?;
+
+        let deleted = client
+            .execute(
+                "DELETE FROM shares WHERE id = $1 AND (owner = $2 OR recipient = $2)",
+                &[&id, &username],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(deleted == 1)
+    }
+}
+
+fn share_from_row(row: &Row) -> Share {
+    Share {
+        id: row.get("id"),
+        owner: row.get("owner"),
+        recipient: row.get("recipient"),
+        name: row.get("name"),
+        path: row.get("path"),
+        file_id: row.get("file_id"),
+        is_dir: row.get("is_dir"),
+        allow_upload: row.get("allow_upload"),
+        created_at: row.get("created_at"),
+    }
+}
@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use crate::dao::db_pool::get_client;
+use crate::dao::token_revocation_store::TokenRevocationStore;
+
+pub struct DbTokenRevocationStore;
+
+#[async_trait]
+impl TokenRevocationStore for DbTokenRevocationStore {
+    async fn revoke_token(&self, jti: &str, expires_at: i64) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "INSERT INTO revoked_tokens (jti, expires_at) VALUES ($1, $2) ON CONFLICT (jti) DO NOTHING",
+                &[&jti, &expires_at],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn revoke_all_user_tokens(&self, username: &str, issued_before: i64) -> Result<(), String> {
+        let client = get_client().await?;
+
+        client
+            .execute(
+                "INSERT INTO user_token_cutoffs (username, issued_before) VALUES ($1, $2) \
+                 ON CONFLICT (username) DO UPDATE SET issued_before = EXCLUDED.issued_before",
+                &[&username, &issued_before],
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(())
+    }
+
+    async fn load_revoked_tokens(&self, now: i64) -> Result<Vec<(String, i64)>, String> {
+        let client = get_client().await?;
+
+        // Expired tokens are rejected anyway, no need to keep them around
+        client
+            .execute("DELETE FROM revoked_tokens WHERE expires_at <= $1", &[&now])
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let rows = client
+            .query("SELECT jti, expires_at FROM revoked_tokens", &[])
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.iter().map(|row| (row.get("jti"), row.get("expires_at"))).collect())
+    }
+
+    async fn load_user_cutoffs(&self) -> Result<Vec<(String, i64)>, String> {
+        let client = get_client().await?;
+
+        let rows = client
+            .query("SELECT username, issued_before FROM user_token_cutoffs", &[])
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows.iter().map(|row| (row.get("username"), row.get("issued_before"))).collect())
+    }
+}
@@ -0,0 +1,53 @@
+use async_trait::async_trait;
+use crate::dao::{user_administration, user_management};
+use crate::dao::user_store::UserStore;
+use crate::models::authentication::user_administration::UserSummary;
+
+pub struct DbUserStore;
+
+#[async_trait]
+impl UserStore for DbUserStore {
+    async fn create_user(&self, username: &str, password_hash: &str, role: &str) -> Result<bool, String> {
+        user_management::create_user(username, password_hash, role).await
+    }
+
+    async fn upsert_external_user(&self, username: &str, password_hash: &str, role: &str) -> Result<Option<bool>, String> {
+        user_management::upsert_external_user(username, password_hash, role).await
+    }
+
+    async fn get_password_hash(&self, username: &str) -> Result<Option<String>, String> {
+        user_management::get_password_hash(username).await
+    }
+
+    async fn update_password_hash(&self, username: &str, password_hash: &str) -> Result<(), String> {
+        user_management::update_password_hash(username, password_hash).await
+    }
+
+    async fn delete_user(&self, username: &str) -> Result<(), String> {
+        user_management::delete_user(username).await
+    }
+
+    async fn is_registration_enabled(&self) -> Result<bool, String> {
+        user_management::is_registration_enabled().await
+    }
+
+    async fn set_registration_enabled(&self, enabled: bool) -> Result<(), String> {
+        user_management::set_registration_enabled(enabled).await
+    }
+
+    async fn list_users(&self) -> Result<Vec<UserSummary>, String> {
+        user_administration::list_users().await
+    }
+
+    async fn set_user_role(&self, username: &str, role: &str) -> Result<(), String> {
+        user_administration::set_user_role(username, role).await
+    }
+
+    async fn set_user_disabled(&self, username: &str, disabled: bool) -> Result<(), String> {
+        user_administration::set_user_disabled(username, disabled).await
+    }
+
+    async fn role_exists(&self, role: &str) -> Result<bool, String> {
+        user_administration::role_exists(role).await
+    }
+}
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use crate::models::groups::group_access::GroupAccess;
+use crate::models::groups::group_requests::{GroupMember, GroupSummary};
+
+#[async_trait]
+pub trait GroupStore: Send + Sync {
+    /// Returns `false` if a group with that name already exists
+    async fn create_group(&self, name: &str, created_at: i64) -> Result<bool, String>;
+
+    /// Returns `false` if there is no such group
+    async fn delete_group(&self, name: &str) -> Result<bool, String>;
+
+    /// The groups of the user with their access. With `include_all` groups the user
+    /// is not a member of are listed as well.
+    async fn list_groups(&self, username: &str, include_all: bool) -> Result<Vec<GroupSummary>, String>;
+
+    async fn list_members(&self, group: &str) -> Result<Vec<GroupMember>, String>;
+
+    /// Adds the user or changes their access
+    async fn set_member(&self, group: &str, username: &str, access: GroupAccess) -> Result<(), String>;
+
+    /// Returns `false` if the user was not a member
+    async fn remove_member(&self, group: &str, username: &str) -> Result<bool, String>;
+}
@@ -0,0 +1,109 @@
+use async_trait::async_trait;
+use ldap3::{drive, Ldap, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
+use crate::dao::credential_store::CredentialStore;
+use crate::models::authentication::verified_user::VerifiedUser;
+use crate::services::authentication::ldap_settings::LdapSettings;
+
+/// Checks passwords by binding to an LDAP or Active Directory server as the user.
+/// The role comes from the user's group memberships.
+pub struct LdapCredentialStore {
+    settings: LdapSettings,
+}
+
+impl LdapCredentialStore {
+    pub fn new(settings: LdapSettings) -> Self {
+        Self { settings }
+    }
+
+    async fn connect(&self) -> Result<Ldap, String> {
+        let conn_settings = LdapConnSettings::new()
+            .set_conn_timeout(self.settings.timeout)
+            .set_starttls(self.settings.starttls);
+        let (conn, ldap) = LdapConnAsync::with_settings(conn_settings, &self.settings.url)
+            .await
+            .map_err(|e| format!("Could not connect to the LDAP server: {}", e))?;
+        drive!(conn);
+        Ok(ldap)
+    }
+
+    /// Binds as the user and returns their entry, which holds the login name and the groups
+    async fn find_user(&self, ldap: &mut Ldap, username: &str, password: &str) -> Result<SearchEntry, String> {
+        let attributes = vec![self.settings.user_attribute.as_str(), self.settings.group_attribute.as_str()];
+
+        match (&self.settings.bind_dn, &self.settings.bind_password) {
+            (Some(bind_dn), Some(bind_password)) => {
+                ldap.simple_bind(bind_dn, bind_password)
+                    .await
+                    .and_then(|result| result.success())
+                    .map_err(|e| format!("Could not bind with the service account: {}", e))?;
+
+                let (entries, _) = ldap
+                    .search(&self.settings.base_dn, Scope::Subtree, &self.settings.user_filter(username), attributes)
+                    .await
+                    .and_then(|result| result.success())
+                    .map_err(|e| format!("Could not search for the user: {}", e))?;
+                // An ambiguous name is treated like an unknown one
+                if entries.len() != 1 {
+                    return Err("User not found".to_string());
+                }
+                let entry = SearchEntry::construct(entries.into_iter().next().unwrap());
+
+                ldap.simple_bind(&entry.dn, password)
+                    .await
+                    .and_then(|result| result.success())
+                    .map_err(|_| "Invalid credentials".to_string())?;
+                Ok(entry)
+            }
+            _ => {
+                let user_dn = self.settings.user_dn(username);
+                ldap.simple_bind(&user_dn, password)
+                    .await
+                    .and_then(|result| result.success())
+                    .map_err(|_| "Invalid credentials".to_string())?;
+
+                let (entries, _) = ldap
+                    .search(&user_dn, Scope::Base, "(objectClass=*)", attributes)
+                    .await
+                    .and_then(|result| result.success())
+                    .map_err(|e| format!("Could not read the user entry: {}", e))?;
+                entries
+                    .into_iter()
+                    .next()
+                    .map(SearchEntry::construct)
+                    .ok_or_else(|| "User not found".to_string())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl CredentialStore for LdapCredentialStore {
+    async fn verify_credentials(&self, username: &str, password: &str) -> Result<VerifiedUser, String> {
+        // Servers accept a bind with an empty password as an anonymous bind
+        if username.is_empty() || password.is_empty() {
+            return Err("Invalid credentials".to_string());
+        }
+
+        let mut ldap = self.connect().await?;
+        let entry = self.find_user(&mut ldap, username, password).await;
+        let _ = ldap.unbind().await;
+        let entry = entry?;
+
+        // Use the spelling stored in the directory, as names are matched case-insensitively
+        let username = entry.attrs
+            .get(&self.settings.user_attribute)
+            .and_then(|values| values.first())
+            .cloned()
+            .unwrap_or_else(|| username.to_string());
+        let groups = entry.attrs
+            .get(&self.settings.group_attribute)
+            .cloned()
+            .unwrap_or_default();
+
+        let role = self.settings
+            .role_for_groups(&groups)
+            .ok_or_else(|| format!("{} is not in any group mapped to a role", username))?;
+
+        Ok(VerifiedUser { username, role: Some(role) })
+    }
+}
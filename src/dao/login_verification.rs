@@ -1,33 +1,11 @@
-use bcrypt::verify;
-use deadpool_postgres::{Manager, ManagerConfig, Pool, RecyclingMethod};
+use bcrypt::{hash, verify, DEFAULT_COST};
 use once_cell::sync::Lazy;
-use std::env;
-use tokio_postgres::{Config, NoTls};
+use crate::dao::db_pool::DB_POOL;
 
-static DB_POOL: Lazy<Pool> = Lazy::new(|| {
-    let host = env::var("POSTGRESQL_HOST").expect("POSTGRESQL_HOST must be set");
-    let user = env::var("POSTGRESQL_USER").expect("POSTGRESQL_USER must be set");
-    let pass = env::var("POSTGRESQL_PASSWORD").expect("POSTGRESQL_PASSWORD must be set");
-    let port = env::var("POSTGRESQL_PORT").expect("POSTGRESQL_PORT must be set");
-    let db   = env::var("POSTGRESQL_DATABASE").expect("POSTGRESQL_DATABASE must be set");
-
-    let mut cfg = Config::new();
-    cfg.host(&host);
-    cfg.user(&user);
-    cfg.password(&pass);
-    cfg.dbname(&db);
-    cfg.port(port.parse().expect("POSTGRESQL_PORT must be a valid integer"));
-
-    let mgr_config = ManagerConfig {
-        recycling_method: RecyclingMethod::Fast
-    };
-
-    let mgr = Manager::from_config(cfg, NoTls, mgr_config);
-
-    Pool::builder(mgr)
-        .max_size(16) // set max connections
-        .build()
-        .expect("Failed to create Deadpool Postgres pool")
+// Checked against when the user does not exist, so that unknown usernames
+// take as long to reject as wrong passwords
+static DUMMY_PASSWORD_HASH: Lazy<String> = Lazy::new(|| {
+    hash("dummy password", DEFAULT_COST).expect("Failed to hash the dummy password")
 });
 
 // 2) An async function to verify user credentials
@@ -41,31 +19,55 @@ pub async fn verify_user_credentials(username: &str, password: &str) -> Result<S
     // Query the stored password hash
     let rows = client
         .query(
-            "SELECT password_hash FROM users WHERE username = $1",
+            "SELECT password_hash, disabled FROM users WHERE username = $1",
             &[&username],
         )
         .await
         .map_err(|e| e.to_string())?;
 
     if rows.is_empty() {
+        let _ = verify(password, &DUMMY_PASSWORD_HASH);
         return Err("User not found".to_string());
     }
 
     // Extract the password hash from the first row
     let row = &rows[0];
     let hash: String = row.get("password_hash");
+    let disabled: bool = row.get("disabled");
 
     // Compare the provided password with the stored hash
     let valid = verify(password, &hash).map_err(|e| e.to_string())?;
 
-    if valid {
+    if valid && disabled {
+        Err("Account is disabled".to_string())
+    } else if valid {
         Ok(username.to_string())
     } else {
         Err("Invalid credentials".to_string())
     }
 }
 
-pub async fn check_privileges(user_role: &str) -> Result<i32, String> {
+pub async fn get_user_role(username: &str) -> Result<String, String> {
+    let client = DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))?;
+
+    let rows = client
+        .query(
+            "SELECT role FROM users WHERE username = $1",
+            &[&username],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match rows.first() {
+        Some(row) => Ok(row.get("role")),
+        None => Err("User not found".to_string()),
+    }
+}
+
+pub async fn check_privileges(user_role: &str) -> Result<Option<i32>, String> {
 
     // Acquire a client from the pool (async)
     let client = DB_POOL
@@ -82,13 +84,8 @@ pub async fn check_privileges(user_role: &str) -> Result<i32, String> {
         .await
         .map_err(|e| e.to_string())?;
 
-    if rows.is_empty() {
-        return Err("User not found".to_string());
-    }
-
-    // Extract the password hash from the first row
-    let row = &rows[0];
-    let privilege: i16 = row.get("privilege_level");
-    let privilege: i32 = privilege.into();
-    Ok(privilege)
+    Ok(rows.first().map(|row| {
+        let privilege: i16 = row.get("privilege_level");
+        privilege.into()
+    }))
 }
\ No newline at end of file
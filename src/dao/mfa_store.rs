@@ -0,0 +1,23 @@
+use async_trait::async_trait;
+use crate::models::authentication::mfa::MfaState;
+
+#[async_trait]
+pub trait MfaStore: Send + Sync {
+    /// Returns `None` if the user does not exist
+    async fn get_mfa_state(&self, username: &str) -> Result<Option<MfaState>, String>;
+
+    /// Stores a new secret that is not enabled yet
+    async fn set_pending_secret(&self, username: &str, secret: &str) -> Result<(), String>;
+
+    /// Enables the stored secret and replaces the recovery codes of the user
+    async fn enable_mfa(&self, username: &str, recovery_code_hashes: &[String]) -> Result<(), String>;
+
+    /// Records that a code of this step was used. Returns `false` if the step,
+    /// or a later one, was already used.
+    async fn mark_step_used(&self, username: &str, step: i64) -> Result<bool, String>;
+
+    /// Returns `false` if there is no unused recovery code with that hash
+    async fn consume_recovery_code(&self, username: &str, code_hash: &str) -> Result<bool, String>;
+
+    async fn disable_mfa(&self, username: &str) -> Result<(), String>;
+}
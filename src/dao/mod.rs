@@ -1,3 +1,30 @@
+pub mod db_pool;
 pub mod login_verification;
+pub mod user_administration;
 pub mod privilege_store;
-pub mod db_privilege_store;
\ No newline at end of file
+pub mod db_privilege_store;
+pub mod refresh_token_store;
+pub mod db_refresh_token_store;
+pub mod token_revocation_store;
+pub mod db_token_revocation_store;
+pub mod user_management;
+pub mod user_store;
+pub mod db_user_store;
+pub mod mfa_store;
+pub mod db_mfa_store;
+pub mod api_key_store;
+pub mod db_api_key_store;
+pub mod credential_store;
+pub mod db_credential_store;
+pub mod ldap_credential_store;
+pub mod oidc_identity_store;
+pub mod db_oidc_identity_store;
+pub mod group_store;
+pub mod db_group_store;
+
+pub mod acl_store;
+pub mod db_acl_store;
+pub mod share_store;
+pub mod db_share_store;
+pub mod share_link_store;
+pub mod db_share_link_store;
\ No newline at end of file
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait OidcIdentityStore: Send + Sync {
+    /// Returns the linked user and whether they are disabled
+    async fn find_linked_user(&self, issuer: &str, subject: &str) -> Result<Option<(String, bool)>, String>;
+
+    /// Returns whether the user is disabled, or `None` if there is no such user
+    async fn get_user_disabled(&self, username: &str) -> Result<Option<bool>, String>;
+
+    async fn link_identity(&self, issuer: &str, subject: &str, username: &str, linked_at: i64) -> Result<(), String>;
+
+    /// Creates the user and links the identity to it in one go.
+    /// Returns `false` if a user with that name already exists.
+    async fn create_linked_user(
+        &self,
+        issuer: &str,
+        subject: &str,
+        username: &str,
+        password_hash: &str,
+        role: &str,
+        linked_at: i64
+    ) -> Result<bool, String>;
+}
@@ -1,6 +1,18 @@
 use async_trait::async_trait;
+use crate::models::groups::group_access::GroupAccess;
 
 #[async_trait]
 pub trait PrivilegeStore: Send + Sync {
-    async fn get_privilege_level(&self, role: &str) -> Result<i32, String>;
+    /// The privilege level of the role, `None` if the role is not in the `privilege_level` table
+    async fn get_privilege_level(&self, role: &str) -> Result<Option<i32>, String>;
+
+    async fn get_user_role(&self, username: &str) -> Result<String, String>;
+
+    /// Fails with `User not found` like `get_user_role`
+    async fn is_user_disabled(&self, username: &str) -> Result<bool, String>;
+
+    async fn group_exists(&self, group: &str) -> Result<bool, String>;
+
+    /// The access of a member to the group, `None` if the user is not a member
+    async fn get_group_access(&self, group: &str, username: &str) -> Result<Option<GroupAccess>, String>;
 }
\ No newline at end of file
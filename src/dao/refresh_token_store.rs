@@ -0,0 +1,17 @@
+use async_trait::async_trait;
+use crate::models::authentication::refresh_token::RefreshTokenRecord;
+
+#[async_trait]
+pub trait RefreshTokenStore: Send + Sync {
+    async fn insert_refresh_token(&self, record: &RefreshTokenRecord) -> Result<(), String>;
+
+    async fn find_refresh_token(&self, token_hash: &str) -> Result<Option<RefreshTokenRecord>, String>;
+
+    /// Marks the token as used. Returns `false` if it was already used or revoked,
+    /// which means somebody else presented the same token first.
+    async fn mark_refresh_token_used(&self, token_hash: &str) -> Result<bool, String>;
+
+    async fn revoke_token_family(&self, family_id: &str) -> Result<(), String>;
+
+    async fn revoke_user_tokens(&self, username: &str) -> Result<(), String>;
+}
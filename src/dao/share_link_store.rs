@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+use crate::models::shares::share_link::ShareLink;
+
+#[async_trait]
+pub trait ShareLinkStore: Send + Sync {
+    async fn insert_link(&self, link: &ShareLink, token_hash: &str) -> Result<(), String>;
+
+    async fn find_link(&self, token_hash: &str) -> Result<Option<ShareLink>, String>;
+
+    async fn list_links(&self, owner: &str) -> Result<Vec<ShareLink>, String>;
+
+    /// Returns `false` if the owner has no link with that id
+    async fn revoke_link(&self, owner: &str, id: &str) -> Result<bool, String>;
+
+    /// Moves the links of a renamed file or directory and of everything below it
+    async fn move_sources(&self, owner: &str, old_path: &str, new_path: &str) -> Result<(), String>;
+
+    /// Revokes the links of a deleted file or directory and of everything below it
+    async fn revoke_sources(&self, owner: &str, path: &str) -> Result<(), String>;
+
+    /// Counts an upload of `bytes` into a file drop unless the link was revoked or expired in the meantime,
+    /// or the upload would exceed its caps. Returns whether the upload was counted.
+    async fn record_upload(&self, id: &str, bytes: i64, now: i64) -> Result<bool, String>;
+
+    /// Takes back an upload counted by `record_upload` whose file could not be stored
+    async fn undo_upload(&self, id: &str, bytes: i64) -> Result<(), String>;
+
+    /// Counts a download unless the link was revoked, expired or used up in the meantime.
+    /// Returns whether the download was counted.
+    async fn record_download(&self, id: &str, now: i64) -> Result<bool, String>;
+}
@@ -0,0 +1,26 @@
+use async_trait::async_trait;
+use crate::models::shares::share::Share;
+
+#[async_trait]
+pub trait ShareStore: Send + Sync {
+    /// Returns the id of the new share, `None` if the recipient already has a share of that name.
+    /// Fails with "User not found" for unknown recipients.
+    async fn create_share(&self, share: &Share) -> Result<Option<i64>, String>;
+
+    /// The shares the user received, or with `incoming` unset the ones they created
+    async fn list_shares(&self, username: &str, incoming: bool) -> Result<Vec<Share>, String>;
+
+    async fn find_share(&self, recipient: &str, name: &str) -> Result<Option<Share>, String>;
+
+    /// Moves the shares of a renamed file or directory and of everything below it
+    async fn move_sources(&self, owner: &str, old_path: &str, new_path: &str) -> Result<(), String>;
+
+    /// Records the new identity of a shared file that was replaced at its path, e.g. by an upload
+    async fn refresh_sources(&self, owner: &str, path: &str, file_id: &str) -> Result<(), String>;
+
+    /// Deletes the shares of a deleted file or directory and of everything below it
+    async fn delete_sources(&self, owner: &str, path: &str) -> Result<(), String>;
+
+    /// Deletes the share if the user is its owner or recipient. Returns `false` otherwise.
+    async fn delete_share(&self, id: i64, username: &str) -> Result<bool, String>;
+}
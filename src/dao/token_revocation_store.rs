@@ -0,0 +1,16 @@
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait TokenRevocationStore: Send + Sync {
+    /// Persists a single revoked token id until its expiration
+    async fn revoke_token(&self, jti: &str, expires_at: i64) -> Result<(), String>;
+
+    /// Revokes every token of the user issued up to and including the given unix timestamp
+    async fn revoke_all_user_tokens(&self, username: &str, issued_before: i64) -> Result<(), String>;
+
+    /// Returns all token ids that are revoked and not yet expired, with their expiration
+    async fn load_revoked_tokens(&self, now: i64) -> Result<Vec<(String, i64)>, String>;
+
+    /// Returns the per-user cutoffs set by `revoke_all_user_tokens`
+    async fn load_user_cutoffs(&self) -> Result<Vec<(String, i64)>, String>;
+}
@@ -0,0 +1,83 @@
+use crate::dao::db_pool::DB_POOL;
+use crate::models::authentication::user_administration::UserSummary;
+
+pub async fn list_users() -> Result<Vec<UserSummary>, String> {
+    let client = DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))?;
+
+    let rows = client
+        .query(
+            "SELECT username, role, disabled FROM users ORDER BY username",
+            &[],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .iter()
+        .map(|row| UserSummary {
+            username: row.get("username"),
+            role: row.get("role"),
+            disabled: row.get("disabled"),
+        })
+        .collect())
+}
+
+pub async fn set_user_role(username: &str, role: &str) -> Result<(), String> {
+    let client = DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))?;
+
+    let updated = client
+        .execute(
+            "UPDATE users SET role = $2 WHERE username = $1",
+            &[&username, &role],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if updated == 0 {
+        return Err("User not found".to_string());
+    }
+    Ok(())
+}
+
+pub async fn set_user_disabled(username: &str, disabled: bool) -> Result<(), String> {
+    let client = DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))?;
+
+    let updated = client
+        .execute(
+            "UPDATE users SET disabled = $2 WHERE username = $1",
+            &[&username, &disabled],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if updated == 0 {
+        return Err("User not found".to_string());
+    }
+    Ok(())
+}
+
+pub async fn role_exists(role: &str) -> Result<bool, String> {
+    let client = DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))?;
+
+    let rows = client
+        .query(
+            "SELECT 1 FROM privilege_level WHERE role = $1",
+            &[&role],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(!rows.is_empty())
+}
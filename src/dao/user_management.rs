@@ -0,0 +1,138 @@
+use crate::dao::db_pool::DB_POOL;
+
+const REGISTRATION_SETTING: &str = "registration_enabled";
+
+pub async fn create_user(username: &str, password_hash: &str, role: &str) -> Result<bool, String> {
+    let client = DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))?;
+
+    let inserted = client
+        .execute(
+            "INSERT INTO users (username, password_hash, role) VALUES ($1, $2, $3) \
+             ON CONFLICT (username) DO NOTHING",
+            &[&username, &password_hash, &role],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(inserted == 1)
+}
+
+pub async fn upsert_external_user(username: &str, password_hash: &str, role: &str) -> Result<Option<bool>, String> {
+    let client = DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))?;
+
+    // A local account of the same name is left alone and no row is returned
+    let rows = client
+        .query(
+            "INSERT INTO users (username, password_hash, role, origin) VALUES ($1, $2, $3, 'ldap') \
+             ON CONFLICT (username) DO UPDATE SET password_hash = EXCLUDED.password_hash, role = EXCLUDED.role \
+             WHERE users.origin = 'ldap' \
+             RETURNING disabled",
+            &[&username, &password_hash, &role],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.first().map(|row| row.get("disabled")))
+}
+
+pub async fn get_password_hash(username: &str) -> Result<Option<String>, String> {
+    let client = DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))?;
+
+    let rows = client
+        .query(
+            "SELECT password_hash FROM users WHERE username = $1",
+            &[&username],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows.first().map(|row| row.get("password_hash")))
+}
+
+pub async fn update_password_hash(username: &str, password_hash: &str) -> Result<(), String> {
+    let client = DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))?;
+
+    let updated = client
+        .execute(
+            "UPDATE users SET password_hash = $2 WHERE username = $1",
+            &[&username, &password_hash],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if updated == 0 {
+        return Err("User not found".to_string());
+    }
+    Ok(())
+}
+
+pub async fn delete_user(username: &str) -> Result<(), String> {
+    let client = DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))?;
+
+    client
+        .execute("DELETE FROM users WHERE username = $1", &[&username])
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // The entries would otherwise apply to a new user of the same name
+    client
+        .execute(
+            "DELETE FROM acl_entries WHERE tree = $1 OR (principal_type = 'user' AND principal = $1)",
+            &[&username],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+pub async fn is_registration_enabled() -> Result<bool, String> {
+    let client = DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))?;
+
+    let rows = client
+        .query(
+            "SELECT value FROM server_settings WHERE key = $1",
+            &[&REGISTRATION_SETTING],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Registration is closed unless an admin opened it
+    Ok(rows.first().is_some_and(|row| row.get::<_, String>("value") == "true"))
+}
+
+pub async fn set_registration_enabled(enabled: bool) -> Result<(), String> {
+    let client = DB_POOL
+        .get()
+        .await
+        .map_err(|e| format!("Failed to get client from pool: {}", e))?;
+
+    client
+        .execute(
+            "INSERT INTO server_settings (key, value) VALUES ($1, $2) \
+             ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value",
+            &[&REGISTRATION_SETTING, &enabled.to_string()],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
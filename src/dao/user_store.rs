@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+use crate::models::authentication::user_administration::UserSummary;
+
+#[async_trait]
+pub trait UserStore: Send + Sync {
+    /// Returns `false` if a user with that name already exists
+    async fn create_user(&self, username: &str, password_hash: &str, role: &str) -> Result<bool, String>;
+
+    /// Creates or updates a user managed by an external directory. Returns whether the user is disabled,
+    /// or `None` if the name belongs to a local account, which is not touched.
+    async fn upsert_external_user(&self, username: &str, password_hash: &str, role: &str) -> Result<Option<bool>, String>;
+
+    async fn get_password_hash(&self, username: &str) -> Result<Option<String>, String>;
+
+    async fn update_password_hash(&self, username: &str, password_hash: &str) -> Result<(), String>;
+
+    async fn delete_user(&self, username: &str) -> Result<(), String>;
+
+    async fn is_registration_enabled(&self) -> Result<bool, String>;
+
+    async fn set_registration_enabled(&self, enabled: bool) -> Result<(), String>;
+
+    async fn list_users(&self) -> Result<Vec<UserSummary>, String>;
+
+    async fn set_user_role(&self, username: &str, role: &str) -> Result<(), String>;
+
+    async fn set_user_disabled(&self, username: &str, disabled: bool) -> Result<(), String>;
+
+    /// Whether the role has an entry in the privilege table
+    async fn role_exists(&self, role: &str) -> Result<bool, String>;
+}
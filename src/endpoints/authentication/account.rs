@@ -0,0 +1,99 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::http::StatusCode;
+use log::{error, info};
+use crate::app_config::AppConfig;
+use crate::dao::db_user_store::DbUserStore;
+use crate::endpoints::authentication::authentication::revoke_all_sessions;
+use crate::models::authentication::account_requests::{
+    ChangePasswordRequest, DeleteAccountRequest, RegisterRequest, RegistrationSettingRequest
+};
+use crate::models::authentication::auth_user::{AdminUser, ScopedUser};
+use crate::models::authentication::scope::Account;
+use crate::services::authentication::account_service::AccountService;
+
+#[post("/register")]
+pub async fn register_handler(
+    payload: web::Json<RegisterRequest>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let account_service = AccountService::new(
+        DbUserStore,
+        config.root_dir.as_ref().clone(),
+        config.directory_lock_manager.clone()
+    );
+
+    match account_service.register(&payload.username, &payload.password).await {
+        Ok(msg) => {
+            info!("Registered user {}", payload.username);
+            HttpResponse::Created().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/account/password")]
+pub async fn change_password_handler(
+    payload: web::Json<ChangePasswordRequest>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let username = authenticated_user.username();
+    let account_service = AccountService::new(
+        DbUserStore,
+        config.root_dir.as_ref().clone(),
+        config.directory_lock_manager.clone()
+    );
+
+    match account_service.change_password(username, &payload.old_password, &payload.new_password).await {
+        Ok(msg) => {
+            // Whoever knew the old password must not stay logged in
+            if let Err(e) = revoke_all_sessions(&config.authentication_service, username).await {
+                error!("Could not revoke tokens of {}: {}", username, e);
+            }
+            HttpResponse::Ok().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/account/delete")]
+pub async fn delete_account_handler(
+    payload: web::Json<DeleteAccountRequest>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let username = authenticated_user.username();
+    let account_service = AccountService::new(
+        DbUserStore,
+        config.root_dir.as_ref().clone(),
+        config.directory_lock_manager.clone()
+    );
+
+    match account_service.delete_account(username, &payload.password).await {
+        Ok(msg) => {
+            if let Err(e) = config.authentication_service.revoke_all_user_tokens(username).await {
+                error!("Could not revoke tokens of {}: {}", username, e);
+            }
+            HttpResponse::Ok().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/admin/registration")]
+pub async fn set_registration_handler(
+    payload: web::Json<RegistrationSettingRequest>,
+    _admin: AdminUser,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let account_service = AccountService::new(
+        DbUserStore,
+        config.root_dir.as_ref().clone(),
+        config.directory_lock_manager.clone()
+    );
+
+    match account_service.set_registration_enabled(payload.enabled).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::http::StatusCode;
+use log::info;
+use crate::dao::db_api_key_store::DbApiKeyStore;
+use crate::models::authentication::api_key::CreateApiKeyRequest;
+use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::scope::Account;
+use crate::services::authentication::api_key_service::ApiKeyService;
+
+#[post("/account/api-keys")]
+pub async fn create_api_key_handler(
+    payload: web::Json<CreateApiKeyRequest>,
+    authenticated_user: ScopedUser<Account>
+) -> impl Responder {
+    // A key outlives the token it was created with, so a restricted token must not be able to
+    // turn itself into one
+    if !authenticated_user.0.0.is_session() {
+        return HttpResponse::Forbidden().body("API keys can only be created with the token of a login session");
+    }
+    let username = authenticated_user.username();
+    let api_key_service = ApiKeyService::new(Arc::new(DbApiKeyStore));
+    match api_key_service.create(username, &payload.name, payload.expires_at, payload.read_only).await {
+        Ok((key, record)) => {
+            info!("Created API key {} for {}", record.id, username);
+            // The key is only shown this one time
+            HttpResponse::Created().json(serde_json::json!({
+                "key": key,
+                "api_key": record
+            }))
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[get("/account/api-keys")]
+pub async fn list_api_keys_handler(authenticated_user: ScopedUser<Account>) -> impl Responder {
+    let api_key_service = ApiKeyService::new(Arc::new(DbApiKeyStore));
+    match api_key_service.list(authenticated_user.username()).await {
+        Ok(keys) => HttpResponse::Ok().json(keys),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/account/api-keys/{id}/revoke")]
+pub async fn revoke_api_key_handler(
+    path: web::Path<String>,
+    authenticated_user: ScopedUser<Account>
+) -> impl Responder {
+    let id = path.into_inner();
+    let username = authenticated_user.username();
+    let api_key_service = ApiKeyService::new(Arc::new(DbApiKeyStore));
+    match api_key_service.revoke(username, &id).await {
+        Ok(msg) => {
+            info!("Revoked API key {} of {}", id, username);
+            HttpResponse::Ok().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
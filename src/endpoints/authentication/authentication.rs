@@ -1,12 +1,28 @@
-use std::path::Path;
-use crate::dao::login_verification::verify_user_credentials;
+use crate::dao::db_api_key_store::DbApiKeyStore;
+use crate::dao::db_mfa_store::DbMfaStore;
+use crate::dao::db_privilege_store::DbPrivilegeStore;
+use crate::dao::db_refresh_token_store::DbRefreshTokenStore;
+use crate::dao::db_user_store::DbUserStore;
+use crate::models::authentication::auth_user::{AuthenticatedUser, ScopedUser};
+use crate::models::authentication::logout_request::LogoutRequest;
+use crate::models::authentication::refresh_token::RefreshTokenRequest;
+use crate::models::authentication::scope::Account;
+use crate::models::authentication::scoped_token_request::ScopedTokenRequest;
 use crate::services::authentication::authentication_service::Claims;
-use crate::services::authentication::authentication_service::generate_jwt;
+use crate::services::authentication::account_service::AccountService;
+use crate::services::authentication::api_key_service::ApiKeyService;
+use crate::services::authentication::authentication_service::AuthenticationService;
+use crate::services::authentication::mfa_service::MfaService;
+use crate::services::authentication::refresh_token_service::RefreshTokenService;
 use actix_web::{post, web, HttpMessage, HttpRequest, HttpResponse, Responder};
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::http::StatusCode;
+use log::{error, info};
 use serde::Deserialize;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use crate::app_config::AppConfig;
-use crate::services::file_structure::directory_service::DirectoryService;
-use crate::services::file_structure::path_service::PathService;
+use crate::services::file_structure::privilege_service::PrivilegeService;
 
 #[derive(Debug, Deserialize)]
 pub struct UserLogin {
@@ -16,42 +32,223 @@ pub struct UserLogin {
 
 #[post("/login")]
 pub async fn login_handler(
+    req: HttpRequest,
     user_info: web::Json<UserLogin>,
     config: web::Data<AppConfig>
 ) -> impl Responder {
-    let user_id = match verify_user_credentials(
-        user_info.username.as_str(), user_info.password.as_str()
-    ).await {
-        Ok(id) => id,
-        Err(e) => return HttpResponse::NotFound().body(format!("{:?}", e))
+    let username = user_info.username.as_str();
+    // The peer address can't be spoofed through headers like X-Forwarded-For
+    let ip = req.peer_addr().map(|addr| addr.ip().to_string());
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+
+    if let Err(retry_after) = config.login_throttle.check(username, ip.as_deref(), now) {
+        return HttpResponse::TooManyRequests()
+            .insert_header((RETRY_AFTER, retry_after.to_string()))
+            .body("Too many failed login attempts, try again later");
+    }
+
+    let verified_user = match config.credential_store.verify_credentials(username, user_info.password.as_str()).await {
+        Ok(verified_user) => verified_user,
+        Err(e) => {
+            // Whether the user exists, is disabled or mistyped the password is only logged,
+            // the client always gets the same answer
+            info!("Failed login for {}: {}", username, e);
+            config.login_throttle.record_failure(username, ip.as_deref(), now);
+            return HttpResponse::Unauthorized().body("Invalid username or password");
+        }
+    };
+    let user_id = verified_user.username;
+
+    // Users of an external directory get a local account, kept in sync with their groups
+    if let Some(role) = verified_user.role {
+        let account_service = AccountService::new(
+            DbUserStore,
+            config.root_dir.to_string(),
+            config.directory_lock_manager.clone()
+        );
+        match account_service.provision_external_account(&user_id, &role).await {
+            Ok(_) => {}
+            Err((400, e)) | Err((403, e)) => {
+                info!("Failed login for {}: {}", user_id, e);
+                config.login_throttle.record_failure(username, ip.as_deref(), now);
+                return HttpResponse::Unauthorized().body("Invalid username or password");
+            }
+            Err((_, e)) => {
+                error!("Could not provision {}: {}", user_id, e);
+                return HttpResponse::InternalServerError().body("Could not generate token");
+            }
+        }
+    }
+
+    let privilege_service = PrivilegeService::new(DbPrivilegeStore);
+    let mfa_service = MfaService::new(DbMfaStore);
+    let mfa_enabled = match mfa_service.is_enabled(&user_id).await {
+        Ok(enabled) => enabled,
+        Err((_, e)) => {
+            error!("Could not load MFA settings of {}: {}", user_id, e);
+            return HttpResponse::InternalServerError().body("Could not generate token");
+        }
+    };
+    let mfa_forced = match privilege_service.resolve_user_privilege(&user_id).await {
+        Ok((_, privilege)) => config.mfa_policy.requires_mfa(privilege),
+        Err(e) => {
+            error!("Could not resolve privilege of {}: {}", user_id, e);
+            return HttpResponse::InternalServerError().body("Could not generate token");
+        }
     };
 
-    let path_service = PathService::new();
-    let directory_service = DirectoryService::new(
-      config.root_dir.as_ref().clone(),
-      config.directory_lock_manager.clone()
-    );
-    let user_path = Path::new(config.root_dir.as_ref()).join(&user_info.username);
-    match path_service.check_if_entity_is_dir(
-        &user_path
-    ).await {
-        Ok(_) => {}
-        Err(_) => {
-            directory_service.create_directory_path(&user_path).await.unwrap();
+    // The password alone is not enough, the client has to continue at /login/mfa.
+    // The throttle is only reset once the second factor was checked as well.
+    if mfa_enabled || mfa_forced {
+        return match config.authentication_service.generate_mfa_pending_token(user_id) {
+            Ok(mfa_token) => HttpResponse::Ok().json(serde_json::json!({
+                "mfa_required": true,
+                "mfa_enrollment_required": !mfa_enabled,
+                "mfa_token": mfa_token
+            })),
+            Err(_) => HttpResponse::InternalServerError().body("Could not generate token"),
+        };
+    }
+    config.login_throttle.record_success(username);
+
+    match issue_session_tokens(&config.authentication_service, user_id).await {
+        Ok((token, refresh_token)) => HttpResponse::Ok().json(serde_json::json!({
+            "token": token,
+            "refresh_token": refresh_token
+        })),
+        Err(e) => {
+            error!("Could not issue tokens: {}", e);
+            HttpResponse::InternalServerError().body("Could not generate token")
         }
+    }
+}
+
+#[post("/token/refresh")]
+pub async fn refresh_token_handler(
+    payload: web::Json<RefreshTokenRequest>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let refresh_token_service = RefreshTokenService::new(DbRefreshTokenStore);
+    let (username, refresh_token) = match refresh_token_service.rotate(&payload.refresh_token).await {
+        Ok(res) => res,
+        Err((code, msg)) => return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
     };
 
-    match generate_jwt(user_id) {
-        Ok(token) => HttpResponse::Ok().json(serde_json::json!({ "token": token })),
+    match generate_jwt_for_user(&config.authentication_service, username).await {
+        Ok(token) => HttpResponse::Ok().json(serde_json::json!({
+            "token": token,
+            "refresh_token": refresh_token
+        })),
         Err(_) => HttpResponse::InternalServerError().body("Could not generate token"),
     }
 }
 
+#[post("/logout")]
+pub async fn logout_handler(
+    payload: Option<web::Json<LogoutRequest>>,
+    authenticated_user: AuthenticatedUser,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let claims = authenticated_user.0;
+    if claims.api_key_id.is_some() {
+        return HttpResponse::BadRequest().body("API keys are revoked at /api/account/api-keys/<id>/revoke");
+    }
+    let request = payload.map(|p| p.into_inner()).unwrap_or_default();
+    let refresh_token_service = RefreshTokenService::new(DbRefreshTokenStore);
+    let authentication_service = &config.authentication_service;
+
+    if let Err(e) = authentication_service.revoke_token(&claims).await {
+        error!("Could not revoke token of {}: {}", claims.sub, e);
+        return HttpResponse::InternalServerError().body("Could not log out");
+    }
+
+    if let Some(refresh_token) = &request.refresh_token {
+        if let Err((code, msg)) = refresh_token_service.revoke(refresh_token).await {
+            error!("Could not revoke refresh token of {}: {}", claims.sub, msg);
+            return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body("Could not log out");
+        }
+    }
+
+    if request.all_sessions.unwrap_or(false) {
+        if let Err(e) = revoke_all_sessions(authentication_service, &claims.sub).await {
+            error!("Could not revoke tokens of {}: {}", claims.sub, e);
+            return HttpResponse::InternalServerError().body("Could not log out");
+        }
+    }
+
+    HttpResponse::Ok().body("Logged out successfully")
+}
+
+#[post("/account/tokens")]
+pub async fn scoped_token_handler(
+    payload: web::Json<ScopedTokenRequest>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let request = payload.into_inner();
+    let claims = &authenticated_user.0.0;
+
+    match config.authentication_service.restrict_token(claims, request.scopes.clone(), request.ttl_seconds) {
+        Ok(token) => {
+            info!("{} created a token with the scopes {:?}", claims.sub, request.scopes);
+            HttpResponse::Ok().json(serde_json::json!({
+                "token": token,
+                "scopes": request.scopes
+            }))
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+/// Starts a new session, returning an access token and a refresh token
+pub(crate) async fn issue_session_tokens(
+    authentication_service: &AuthenticationService,
+    username: String
+) -> Result<(String, String), String> {
+    let refresh_token = RefreshTokenService::new(DbRefreshTokenStore)
+        .issue(&username)
+        .await
+        .map_err(|(_, msg)| msg)?;
+    let token = generate_jwt_for_user(authentication_service, username).await?;
+
+    Ok((token, refresh_token))
+}
+
+/// Revokes every access token, refresh token and API key of the user
+pub(crate) async fn revoke_all_sessions(
+    authentication_service: &AuthenticationService,
+    username: &str
+) -> Result<(), String> {
+    authentication_service.revoke_all_user_tokens(username).await?;
+    RefreshTokenService::new(DbRefreshTokenStore)
+        .revoke_all(username)
+        .await
+        .map_err(|(_, msg)| msg)?;
+    ApiKeyService::new(Arc::new(DbApiKeyStore))
+        .revoke_all(username)
+        .await
+        .map_err(|(_, msg)| msg)
+}
+
+/// Embeds the current role and privilege level of the user in a fresh token,
+/// so that requests can be authorized without hitting the database.
+async fn generate_jwt_for_user(
+    authentication_service: &AuthenticationService,
+    username: String
+) -> Result<String, String> {
+    let privilege_service = PrivilegeService::new(DbPrivilegeStore);
+    let (role, privilege) = privilege_service.resolve_user_privilege(&username).await?;
+
+    authentication_service.generate_token(username, role, privilege)
+}
+
 pub async fn protected_resource_handler(req: HttpRequest) -> impl Responder {
     if let Some(claims) = req.extensions().get::<Claims>() {
         HttpResponse::Ok().json(serde_json::json!({
             "msg": "You have accessed a protected resource!",
             "user_id": claims.sub,
+            "role": claims.role,
+            "privilege": claims.privilege,
         }))
     } else {
         HttpResponse::Unauthorized().body("Unauthorized")
@@ -0,0 +1,8 @@
+use actix_web::{get, web, HttpResponse, Responder};
+use crate::app_config::AppConfig;
+
+/// Publishes the public signing keys, so other services can verify our tokens
+#[get("/.well-known/jwks.json")]
+pub async fn jwks_handler(config: web::Data<AppConfig>) -> impl Responder {
+    HttpResponse::Ok().json(config.authentication_service.jwks())
+}
@@ -0,0 +1,149 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
+use actix_web::http::header::RETRY_AFTER;
+use actix_web::http::StatusCode;
+use log::{error, info};
+use crate::app_config::AppConfig;
+use crate::dao::db_mfa_store::DbMfaStore;
+use crate::endpoints::authentication::authentication::issue_session_tokens;
+use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::mfa::{MfaCodeRequest, MfaEnrollRequest, MfaLoginRequest};
+use crate::models::authentication::scope::Account;
+use crate::services::authentication::mfa_service::MfaService;
+
+/// Second step of the login, exchanging the MFA token from `/login` and a code for a session.
+/// Users who are forced into MFA but haven't enrolled yet confirm their enrollment here.
+#[post("/login/mfa")]
+pub async fn mfa_login_handler(
+    req: HttpRequest,
+    payload: web::Json<MfaLoginRequest>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let claims = match config.authentication_service.validate_mfa_pending_token(&payload.mfa_token) {
+        Ok(claims) => claims,
+        Err(e) => return HttpResponse::Unauthorized().body(e)
+    };
+    let username = claims.sub.as_str();
+    let ip = req.peer_addr().map(|addr| addr.ip().to_string());
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+
+    if let Err(retry_after) = config.login_throttle.check(username, ip.as_deref(), now) {
+        return HttpResponse::TooManyRequests()
+            .insert_header((RETRY_AFTER, retry_after.to_string()))
+            .body("Too many failed login attempts, try again later");
+    }
+
+    let mfa_service = MfaService::new(DbMfaStore);
+    let result = match mfa_service.is_enabled(username).await {
+        Ok(true) => mfa_service
+            .verify(username, payload.code.as_deref(), payload.recovery_code.as_deref())
+            .await
+            .map(|_| None),
+        Ok(false) => match &payload.code {
+            Some(code) => mfa_service.confirm_enrollment(username, code).await.map(Some),
+            None => Err((400, "A code is required to finish the MFA enrollment".to_string())),
+        },
+        Err(e) => Err(e),
+    };
+
+    let recovery_codes = match result {
+        Ok(recovery_codes) => recovery_codes,
+        Err((code, msg)) => {
+            if code == 401 {
+                config.login_throttle.record_failure(username, ip.as_deref(), now);
+            }
+            return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg);
+        }
+    };
+    config.login_throttle.record_success(username);
+
+    // The MFA token is single use
+    if let Err(e) = config.authentication_service.revoke_token(&claims).await {
+        error!("Could not revoke MFA token of {}: {}", username, e);
+        return HttpResponse::InternalServerError().body("Could not generate token");
+    }
+
+    match issue_session_tokens(&config.authentication_service, claims.sub.clone()).await {
+        Ok((token, refresh_token)) => {
+            let mut body = serde_json::json!({
+                "token": token,
+                "refresh_token": refresh_token
+            });
+            if let Some(recovery_codes) = recovery_codes {
+                body["recovery_codes"] = serde_json::json!(recovery_codes);
+            }
+            HttpResponse::Ok().json(body)
+        },
+        Err(e) => {
+            error!("Could not issue tokens: {}", e);
+            HttpResponse::InternalServerError().body("Could not generate token")
+        }
+    }
+}
+
+/// Lets a user who is forced into MFA enroll before they have a session
+#[post("/login/mfa/enroll")]
+pub async fn mfa_login_enroll_handler(
+    payload: web::Json<MfaEnrollRequest>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let claims = match config.authentication_service.validate_mfa_pending_token(&payload.mfa_token) {
+        Ok(claims) => claims,
+        Err(e) => return HttpResponse::Unauthorized().body(e)
+    };
+
+    let mfa_service = MfaService::new(DbMfaStore);
+    match mfa_service.start_enrollment(&claims.sub, &config.mfa_policy.issuer).await {
+        Ok(enrollment) => HttpResponse::Ok().json(enrollment),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/account/mfa/enroll")]
+pub async fn mfa_enroll_handler(
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let mfa_service = MfaService::new(DbMfaStore);
+    match mfa_service.start_enrollment(authenticated_user.username(), &config.mfa_policy.issuer).await {
+        Ok(enrollment) => HttpResponse::Ok().json(enrollment),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/account/mfa/confirm")]
+pub async fn mfa_confirm_handler(
+    payload: web::Json<MfaCodeRequest>,
+    authenticated_user: ScopedUser<Account>
+) -> impl Responder {
+    let username = authenticated_user.username();
+    let mfa_service = MfaService::new(DbMfaStore);
+    match mfa_service.confirm_enrollment(username, &payload.code).await {
+        Ok(recovery_codes) => {
+            info!("Enabled MFA for {}", username);
+            HttpResponse::Ok().json(serde_json::json!({ "recovery_codes": recovery_codes }))
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/account/mfa/disable")]
+pub async fn mfa_disable_handler(
+    payload: web::Json<MfaCodeRequest>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    if config.mfa_policy.requires_mfa(authenticated_user.0.privilege()) {
+        return HttpResponse::Forbidden().body("MFA is required for your role");
+    }
+
+    let username = authenticated_user.username();
+    let mfa_service = MfaService::new(DbMfaStore);
+    match mfa_service.disable(username, &payload.code).await {
+        Ok(msg) => {
+            info!("Disabled MFA for {}", username);
+            HttpResponse::Ok().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
@@ -1 +1,7 @@
-pub mod authentication;
\ No newline at end of file
+pub mod authentication;
+pub mod jwks;
+pub mod account;
+pub mod user_administration;
+pub mod mfa;
+pub mod api_keys;
+pub mod oidc;
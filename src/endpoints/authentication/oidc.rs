@@ -0,0 +1,144 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use actix_web::cookie::{time, Cookie, SameSite};
+use actix_web::http::header::LOCATION;
+use actix_web::http::StatusCode;
+use log::{error, info};
+use crate::app_config::AppConfig;
+use crate::dao::db_mfa_store::DbMfaStore;
+use crate::dao::db_oidc_identity_store::DbOidcIdentityStore;
+use crate::dao::db_privilege_store::DbPrivilegeStore;
+use crate::endpoints::authentication::authentication::issue_session_tokens;
+use crate::models::authentication::oidc_callback::OidcCallbackQuery;
+use crate::services::authentication::mfa_service::MfaService;
+use crate::services::authentication::oidc_account_service::OidcAccountService;
+use crate::services::authentication::oidc_client::PENDING_LOGIN_TTL_SECONDS;
+use crate::services::file_structure::privilege_service::PrivilegeService;
+
+/// Binds a login to the browser that started it, so nobody can make a victim's browser
+/// finish a login the attacker started
+const STATE_COOKIE: &str = "oidc_state";
+
+/// Redirects the browser to the login page of the OpenID Connect provider
+#[get("/login/oidc/start")]
+pub async fn oidc_start_handler(config: web::Data<AppConfig>) -> impl Responder {
+    let Some(oidc_client) = &config.oidc_client else {
+        return HttpResponse::NotFound().body("OIDC login is not configured");
+    };
+
+    match oidc_client.start(now()).await {
+        Ok((url, state)) => {
+            let cookie = state_cookie(state, oidc_client.settings().redirect_uri.starts_with("https://"))
+                .max_age(time::Duration::seconds(PENDING_LOGIN_TTL_SECONDS))
+                .finish();
+            HttpResponse::Found().insert_header((LOCATION, url)).cookie(cookie).finish()
+        }
+        Err((code, msg)) => {
+            error!("Could not start OIDC login: {}", msg);
+            HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+        }
+    }
+}
+
+/// Finishes the login with the code from the provider and starts a session,
+/// like a successful `/login` does. Only the browser that started the login can finish it.
+#[get("/login/oidc/callback")]
+pub async fn oidc_callback_handler(
+    req: HttpRequest,
+    query: web::Query<OidcCallbackQuery>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let Some(oidc_client) = &config.oidc_client else {
+        return HttpResponse::NotFound().body("OIDC login is not configured");
+    };
+    if let Some(e) = &query.error {
+        info!("OIDC login refused by the provider: {} {:?}", e, query.error_description);
+        return HttpResponse::Unauthorized().body(format!("The provider refused the login: {}", e));
+    }
+    let (Some(code), Some(state)) = (&query.code, &query.state) else {
+        return HttpResponse::BadRequest().body("Both code and state are required");
+    };
+    if req.cookie(STATE_COOKIE).is_none_or(|cookie| cookie.value() != state) {
+        info!("OIDC callback without the state cookie of the login");
+        return HttpResponse::BadRequest().body("The login was started in another browser, start again");
+    }
+
+    let identity = match oidc_client.complete(code, state, now()).await {
+        Ok(identity) => identity,
+        Err((code, msg)) => {
+            info!("Failed OIDC login: {}", msg);
+            return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg);
+        }
+    };
+
+    let account_service = OidcAccountService::new(DbOidcIdentityStore, config.root_dir.to_string());
+    let username = match account_service.resolve_user(&identity, oidc_client.settings()).await {
+        Ok(username) => username,
+        Err((code, msg)) => {
+            info!("Failed OIDC login for subject {}: {}", identity.subject, msg);
+            return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg);
+        }
+    };
+
+    let mut response = session_response(&config, username).await;
+    // The state can only be used once, so the cookie is of no use anymore
+    let secure = oidc_client.settings().redirect_uri.starts_with("https://");
+    let _ = response.add_removal_cookie(&state_cookie(String::new(), secure).finish());
+    response
+}
+
+/// Answers like `/login` does: with the tokens of a session, or with a token to continue
+/// at `/login/mfa` if the user has to give a second factor
+async fn session_response(config: &AppConfig, username: String) -> HttpResponse {
+    let privilege_service = PrivilegeService::new(DbPrivilegeStore);
+    let mfa_service = MfaService::new(DbMfaStore);
+    let mfa_enabled = match mfa_service.is_enabled(&username).await {
+        Ok(enabled) => enabled,
+        Err((_, e)) => {
+            error!("Could not load MFA settings of {}: {}", username, e);
+            return HttpResponse::InternalServerError().body("Could not generate token");
+        }
+    };
+    let mfa_forced = match privilege_service.resolve_user_privilege(&username).await {
+        Ok((_, privilege)) => config.mfa_policy.requires_mfa(privilege),
+        Err(e) => {
+            error!("Could not resolve privilege of {}: {}", username, e);
+            return HttpResponse::InternalServerError().body("Could not generate token");
+        }
+    };
+
+    if mfa_enabled || mfa_forced {
+        return match config.authentication_service.generate_mfa_pending_token(username) {
+            Ok(mfa_token) => HttpResponse::Ok().json(serde_json::json!({
+                "mfa_required": true,
+                "mfa_enrollment_required": !mfa_enabled,
+                "mfa_token": mfa_token
+            })),
+            Err(_) => HttpResponse::InternalServerError().body("Could not generate token"),
+        };
+    }
+
+    match issue_session_tokens(&config.authentication_service, username).await {
+        Ok((token, refresh_token)) => HttpResponse::Ok().json(serde_json::json!({
+            "token": token,
+            "refresh_token": refresh_token
+        })),
+        Err(e) => {
+            error!("Could not issue tokens: {}", e);
+            HttpResponse::InternalServerError().body("Could not generate token")
+        }
+    }
+}
+
+/// Lax, so the cookie is sent along when the provider redirects the browser back
+fn state_cookie(state: String, secure: bool) -> actix_web::cookie::CookieBuilder<'static> {
+    Cookie::build(STATE_COOKIE, state)
+        .path("/login/oidc")
+        .http_only(true)
+        .secure(secure)
+        .same_site(SameSite::Lax)
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+}
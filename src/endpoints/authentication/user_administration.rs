@@ -0,0 +1,125 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::http::StatusCode;
+use log::{error, info};
+use crate::app_config::AppConfig;
+use crate::dao::db_user_store::DbUserStore;
+use crate::endpoints::authentication::authentication::revoke_all_sessions;
+use crate::models::authentication::auth_user::AdminUser;
+use crate::models::authentication::user_administration::{
+    ChangeRoleRequest, CreateUserRequest, ResetPasswordRequest, SetDisabledRequest
+};
+use crate::services::authentication::account_service::AccountService;
+
+// All handlers are mounted under the `/api/admin/users` scope
+
+#[get("")]
+pub async fn list_users_handler(
+    _admin: AdminUser,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    match account_service(&config).list_users().await {
+        Ok(users) => HttpResponse::Ok().json(users),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("")]
+pub async fn create_user_handler(
+    payload: web::Json<CreateUserRequest>,
+    admin: AdminUser,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    match account_service(&config)
+        .create_account_with_role(&payload.username, &payload.password, &payload.role)
+        .await
+    {
+        Ok(msg) => {
+            info!("{} created user {} with role {}", admin.0.username(), payload.username, payload.role);
+            HttpResponse::Created().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/{username}/password")]
+pub async fn reset_password_handler(
+    path: web::Path<String>,
+    payload: web::Json<ResetPasswordRequest>,
+    admin: AdminUser,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let username = path.into_inner();
+
+    match account_service(&config).reset_password(&username, &payload.new_password).await {
+        Ok(msg) => {
+            info!("{} reset the password of {}", admin.0.username(), username);
+            if let Err(e) = revoke_all_sessions(&config.authentication_service, &username).await {
+                error!("Could not revoke tokens of {}: {}", username, e);
+            }
+            HttpResponse::Ok().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/{username}/role")]
+pub async fn change_role_handler(
+    path: web::Path<String>,
+    payload: web::Json<ChangeRoleRequest>,
+    admin: AdminUser,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let username = path.into_inner();
+    if username == admin.0.username() {
+        return HttpResponse::BadRequest().body("You cannot change your own role");
+    }
+
+    match account_service(&config).change_role(&username, &payload.role).await {
+        Ok(msg) => {
+            info!("{} changed the role of {} to {}", admin.0.username(), username, payload.role);
+            // The role is embedded in the access tokens, so force the user to refresh them
+            if let Err(e) = config.authentication_service.revoke_all_user_tokens(&username).await {
+                error!("Could not revoke tokens of {}: {}", username, e);
+            }
+            HttpResponse::Ok().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/{username}/disabled")]
+pub async fn set_disabled_handler(
+    path: web::Path<String>,
+    payload: web::Json<SetDisabledRequest>,
+    admin: AdminUser,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let username = path.into_inner();
+    if username == admin.0.username() {
+        return HttpResponse::BadRequest().body("You cannot disable your own account");
+    }
+
+    let msg = match account_service(&config).set_disabled(&username, payload.disabled).await {
+        Ok(msg) => msg,
+        Err((code, msg)) => return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    };
+    info!("{}: {}", admin.0.username(), msg);
+
+    if payload.disabled {
+        if let Err(e) = revoke_all_sessions(&config.authentication_service, &username).await {
+            error!("Could not revoke tokens of {}: {}", username, e);
+            return HttpResponse::InternalServerError()
+                .body("User disabled, but their sessions could not be revoked");
+        }
+    }
+
+    HttpResponse::Ok().body(msg)
+}
+
+fn account_service(config: &AppConfig) -> AccountService<DbUserStore> {
+    AccountService::new(
+        DbUserStore,
+        config.root_dir.as_ref().clone(),
+        config.directory_lock_manager.clone()
+    )
+}
@@ -0,0 +1,113 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::http::StatusCode;
+use log::info;
+use crate::app_config::AppConfig;
+use crate::endpoints::system_operations::tree_owner::resolve_tree_owner;
+use crate::models::acl::acl_entry::{AclEntry, AclPermission};
+use crate::models::acl::acl_requests::{AclPathRequest, RemoveAclEntryRequest, SetAclEntryRequest};
+use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::scope::{Account, FilesRead};
+use crate::services::file_structure::acl_service::AclService;
+
+// All handlers are mounted under the `/api` scope. Owners of a tree, users allowed into it
+// through their privilege and group managers edit its ACLs, anyone else needs share permission.
+
+/// Shows the entries on the path and the ones it inherits
+#[post("/acl")]
+pub async fn view_acl_handler(
+    payload: web::Json<AclPathRequest>,
+    authenticated_user: ScopedUser<FilesRead>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let username = authenticated_user.username();
+    let (tree, _) = match resolve_acl_tree(&config, username, &payload.path, payload.owner.as_deref(), payload.group.as_deref()).await {
+        Ok(resolved) => resolved,
+        Err(response) => return response
+    };
+
+    match acl_service(&config).view(&tree, &payload.path).await {
+        Ok(view) => HttpResponse::Ok().json(view),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+/// Adds an entry or replaces the one of the same principal
+#[post("/acl/set")]
+pub async fn set_acl_entry_handler(
+    payload: web::Json<SetAclEntryRequest>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let username = authenticated_user.username();
+    let (tree, via_acl) = match resolve_acl_tree(&config, username, &payload.path, payload.owner.as_deref(), payload.group.as_deref()).await {
+        Ok(resolved) => resolved,
+        Err(response) => return response
+    };
+
+    // Users who only share a path can't hand out more than they have themselves
+    if via_acl {
+        let own = match acl_service(&config).effective_permissions(username, &tree, &payload.path).await {
+            Ok(permissions) => permissions,
+            Err((code, msg)) => return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+        };
+        if let Some(missing) = payload.permissions.iter().find(|permission| !own.contains(permission)) {
+            return HttpResponse::Forbidden().body(format!("You can't grant {} permission you don't have", missing.as_str()));
+        }
+    }
+
+    let entry = AclEntry {
+        path: payload.path.clone(),
+        principal: payload.principal.clone(),
+        permissions: payload.permissions.clone(),
+        inherit: payload.inherit,
+    };
+    match acl_service(&config).set_entry(&tree, entry).await {
+        Ok(msg) => {
+            info!("{} changed the ACL of {} in {}", username, payload.path, tree);
+            HttpResponse::Ok().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/acl/remove")]
+pub async fn remove_acl_entry_handler(
+    payload: web::Json<RemoveAclEntryRequest>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let username = authenticated_user.username();
+    let (tree, _) = match resolve_acl_tree(&config, username, &payload.path, payload.owner.as_deref(), payload.group.as_deref()).await {
+        Ok(resolved) => resolved,
+        Err(response) => return response
+    };
+
+    match acl_service(&config).remove_entry(&tree, &payload.path, &payload.principal).await {
+        Ok(msg) => {
+            info!("{} changed the ACL of {} in {}", username, payload.path, tree);
+            HttpResponse::Ok().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+/// The tree whose ACL is edited and whether the user only gets to it through share permission
+async fn resolve_acl_tree(
+    config: &AppConfig,
+    username: &str,
+    path: &str,
+    owner: Option<&str>,
+    group: Option<&str>
+) -> Result<(String, bool), HttpResponse> {
+    let access = resolve_tree_owner(config, username, owner, group, AclPermission::Share).await?;
+    if let Some(acl) = &access.acl {
+        acl.check(&access.tree, path, AclPermission::Share)
+            .await
+            .map_err(|(code, msg)| HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg))?;
+    }
+    Ok((access.tree, access.acl.is_some()))
+}
+
+fn acl_service(config: &AppConfig) -> AclService {
+    AclService::new(config.acl_store.clone())
+}
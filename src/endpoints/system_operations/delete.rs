@@ -2,29 +2,47 @@ use actix_web::{post, web, HttpResponse, Responder};
 use actix_web::http::StatusCode;
 use log::{debug, error};
 use crate::app_config::AppConfig;
-use crate::models::authentication::auth_user::AuthenticatedUser;
+use crate::endpoints::system_operations::tree_owner::{forget_acl_path, forget_shared_path, resolve_target};
+use crate::models::acl::acl_entry::AclPermission;
+use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::scope::FilesDelete;
 use crate::models::system_operations::delete_file_request::DeleteEntityRequest;
 use crate::services::file_structure::delete_service::DeleteService;
 
 #[post("/directory/delete")]
 pub async fn delete_user_directory(
     payload: web::Json<DeleteEntityRequest>,
-    authenticated_user: AuthenticatedUser,
+    authenticated_user: ScopedUser<FilesDelete>,
     config: web::Data<AppConfig>,
 ) -> impl Responder {
     debug!("Received payload: {:?}\n On Route /api/directory/delete", payload);
-    let username = authenticated_user.0.sub;
-    let dir_name = &payload.name;
-    let path = &payload.path;
+    let target = match resolve_target(
+        &config,
+        authenticated_user.username(),
+        payload.owner.as_deref(),
+        payload.group.as_deref(),
+        &payload.path,
+        &payload.name,
+        AclPermission::Delete
+    ).await {
+        Ok(target) => target,
+        Err(response) => return response
+    };
+    let username = target.tree;
+    let dir_name = &target.name;
+    let path = &target.path;
 
     let delete_service = DeleteService::new(
         config.root_dir.as_ref().clone(),
         config.directory_lock_manager.clone()
-    );
+    ).with_acl(target.acl);
 
     match delete_service.delete_directory(&username, path, dir_name).await {
         Ok(msg) => {
             debug!("Deleted directory {} successfully", dir_name);
+            let dir_path = format!("{}/{}", path, dir_name);
+            forget_acl_path(&config, &username, &dir_path).await;
+            forget_shared_path(&config, &username, &dir_path).await;
             HttpResponse::Ok().body(msg)
         },
         Err((code, e)) => {
@@ -37,22 +55,37 @@ pub async fn delete_user_directory(
 #[post("/file/delete")]
 pub async fn delete_file(
     payload: web::Json<DeleteEntityRequest>,
-    authenticated_user: AuthenticatedUser,
+    authenticated_user: ScopedUser<FilesDelete>,
     config: web::Data<AppConfig>,
 ) -> impl Responder {
     debug!("Received payload:{:?}\n On Route /api/file/delete", payload);
-    let username = authenticated_user.0.sub;
-    let filename = &payload.name;
-    let path = &payload.path;
+    let target = match resolve_target(
+        &config,
+        authenticated_user.username(),
+        payload.owner.as_deref(),
+        payload.group.as_deref(),
+        &payload.path,
+        &payload.name,
+        AclPermission::Delete
+    ).await {
+        Ok(target) => target,
+        Err(response) => return response
+    };
+    let username = target.tree;
+    let filename = &target.name;
+    let path = &target.path;
 
     let delete_service = DeleteService::new(
         config.root_dir.as_ref().clone(),
         config.directory_lock_manager.clone()
-    );
+    ).with_acl(target.acl);
 
     match delete_service.delete_file(&username, path, filename).await {
         Ok(msg) => {
             debug!("Successfully delete file: {}", filename);
+            let file_path = format!("{}/{}", path, filename);
+            forget_acl_path(&config, &username, &file_path).await;
+            forget_shared_path(&config, &username, &file_path).await;
             HttpResponse::Ok().body(msg)
         },
         Err((code, e)) => {
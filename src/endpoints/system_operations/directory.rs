@@ -1,27 +1,46 @@
 use actix_web::{post, web, HttpResponse, Responder};
 use actix_web::http::StatusCode;
 use crate::app_config::AppConfig;
-use crate::models::authentication::auth_user::AuthenticatedUser;
+use crate::endpoints::system_operations::tree_owner::resolve_target;
+use crate::models::acl::acl_entry::AclPermission;
+use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::scope::FilesWrite;
 use crate::models::file_structure::directory_create_request::DirectoryCreateRequest;
 use crate::services::file_structure::directory_service::DirectoryService;
+use crate::services::file_structure::share_service::{is_reserved_path, SHARED_WITH_ME};
 
 #[post("/directory/create")]
 pub async fn create_directory(
     payload: web::Json<DirectoryCreateRequest>,
-    auth_user: AuthenticatedUser,
+    auth_user: ScopedUser<FilesWrite>,
     config: web::Data<AppConfig>
 ) -> impl Responder {
-    let path = &payload.path;
-    let name = &payload.name;
-    let user = &auth_user.0.sub;
+    let target = match resolve_target(
+        &config,
+        auth_user.username(),
+        payload.owner.as_deref(),
+        payload.group.as_deref(),
+        &payload.path,
+        &payload.name,
+        AclPermission::Write
+    ).await {
+        Ok(target) => target,
+        Err(response) => return response
+    };
+    let path = &target.path;
+    let name = &target.name;
+    if is_reserved_path(&format!("{}/{}", path, name)) {
+        return HttpResponse::BadRequest().body(format!("'{}' is reserved", SHARED_WITH_ME));
+    }
+    let user = target.tree;
     let root = config.root_dir.as_ref();
     
     let directory_service = DirectoryService::new(
         root.clone(),
         config.directory_lock_manager.clone()
-    );
+    ).with_acl(target.acl);
     
-    match directory_service.create_directory(user, path, name).await {  
+    match directory_service.create_directory(&user, path, name).await {  
         Ok(msg) => HttpResponse::Ok().body(msg),
         Err((code, m)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(m)
     }
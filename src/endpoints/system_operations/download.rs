@@ -1,33 +1,51 @@
-use std::path::Path;
 use crate::services::file_structure::file_service::FileService;
 use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::http::header::ETAG;
 use actix_web::http::StatusCode;
 use log::info;
 use crate::app_config::AppConfig;
-use crate::models::authentication::auth_user::AuthenticatedUser;
+use crate::endpoints::system_operations::share_links::attachment;
+use crate::endpoints::system_operations::tree_owner::resolve_target;
+use crate::models::acl::acl_entry::AclPermission;
+use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::scope::FilesRead;
 use crate::models::system_operations::download_file_request::DownloadEntityRequest;
 use crate::services::file_structure::directory_service::DirectoryService;
 
 #[post("/download")]
 pub async fn download_file_from_user_directory(
     payload: web::Json<DownloadEntityRequest>,
-    authenticated_user: AuthenticatedUser,
+    authenticated_user: ScopedUser<FilesRead>,
     config: web::Data<AppConfig>,
 ) -> impl Responder {
-    let username = authenticated_user.0.sub;
-    let path = payload.path.as_str();
-    let filename = payload.name.as_str();
+    let target = match resolve_target(
+        &config,
+        authenticated_user.username(),
+        payload.owner.as_deref(),
+        payload.group.as_deref(),
+        &payload.path,
+        &payload.name,
+        AclPermission::Read
+    ).await {
+        Ok(target) => target,
+        Err(response) => return response
+    };
+    let username = target.tree;
+    let path = target.path.as_str();
+    let filename = target.name.as_str();
     let file_service = FileService::new(
         config.root_dir.as_ref().clone(),
         config.directory_lock_manager.clone()
-    );
+    ).with_acl(target.acl);
 
-    match file_service.read_file_from_any_directory(&username, path, filename).await {
-        Ok((content, decoded_filename)) => {
+    match file_service.read_file_with_etag(&username, path, filename).await {
+        Ok((content, decoded_filename, etag)) => {
             info!("Successfully downloaded: {}", decoded_filename);
 
+            // Sent back in `If-Match` to only overwrite the file if nobody changed it meanwhile
             HttpResponse::Ok()
-            .append_header(("Content-Disposition", format!("attachment; filename=\"{}\"", decoded_filename)))
+            .insert_header(attachment(&decoded_filename))
+            .insert_header((ETAG, etag))
             .body(content)
         },
         Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
@@ -37,25 +55,32 @@ pub async fn download_file_from_user_directory(
 #[post("/download/directory")]
 pub async fn download_directory_from_user_directory(
     payload: web::Json<DownloadEntityRequest>,
-    authenticated_user: AuthenticatedUser,
+    authenticated_user: ScopedUser<FilesRead>,
     config: web::Data<AppConfig>,
 ) -> impl Responder {
-    let path = &payload.path;
-    let name = &payload.name;
-    let username = &authenticated_user.0.sub;
+    let target = match resolve_target(
+        &config,
+        authenticated_user.username(),
+        payload.owner.as_deref(),
+        payload.group.as_deref(),
+        &payload.path,
+        &payload.name,
+        AclPermission::Read
+    ).await {
+        Ok(target) => target,
+        Err(response) => return response
+    };
+    let path = &target.path;
+    let name = &target.name;
+    let username = target.tree;
     let root = config.root_dir.as_ref();
     
-    let dir_path = Path::new(root)
-        .join(username)
-        .join(path)
-        .join(name);
-    
     let directory_service = DirectoryService::new(
         root.clone(),
         config.directory_lock_manager.clone()
-    );
+    ).with_acl(target.acl);
     
-    match directory_service.download_directory_streamed(dir_path).await {
+    match directory_service.download_directory_streamed(&username, path, name).await {
         Ok(data) => HttpResponse::Ok().content_type("application/zip").body(data),
         Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
     }
@@ -1,28 +1,76 @@
 use std::path::{Path};
 use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::http::StatusCode;
+use log::error;
 use crate::app_config::AppConfig;
-use crate::models::authentication::auth_user::AuthenticatedUser;
+use crate::endpoints::system_operations::tree_owner::resolve_target;
+use crate::models::acl::acl_entry::AclPermission;
+use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::scope::FilesRead;
 use crate::models::file_structure::file_structure_request::FileStructureRequest;
+use crate::models::shares::share::Share;
+use crate::services::file_structure::acl_service::normalize_acl_path;
 use crate::services::file_structure::directory_service::DirectoryService;
+use crate::services::file_structure::share_service::{ShareService, SHARED_WITH_ME};
 
 #[post("/structure")]
 async fn get_user_directory(
     payload: web::Json<FileStructureRequest>,
-    auth_user: AuthenticatedUser,
+    auth_user: ScopedUser<FilesRead>,
     config: web::Data<AppConfig>
 ) -> impl Responder {
-    let dir_name = Path::new(&payload.path);
-    let user = auth_user.0.sub;
-
     let directory_service = DirectoryService::new(
         config.root_dir.as_ref().clone(),
         config.directory_lock_manager.clone()
     );
 
-    match directory_service.build_dir_tree(&user, dir_name) {
-        Ok(tree) => HttpResponse::Ok().json(tree),
-        Err(err) => {
-            HttpResponse::NotFound().body(format!("Error reading directory: {}", err))
+    // The root of the user's own tree and the `Shared with me` node list the shares
+    let own_tree = payload.owner.as_deref().is_none_or(|o| o.trim().is_empty())
+        && payload.group.as_deref().is_none_or(|g| g.trim().is_empty());
+    let normalized = normalize_acl_path(&payload.path).ok();
+    if own_tree && normalized.as_deref() == Some(SHARED_WITH_ME) {
+        return match shares_of(&config, auth_user.username()).await {
+            Ok(shares) => HttpResponse::Ok().json(directory_service.with_shared(shares).build_shared_tree()),
+            Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+        };
+    }
+    let directory_service = if own_tree && normalized.as_deref() == Some("") {
+        match shares_of(&config, auth_user.username()).await {
+            Ok(shares) => directory_service.with_shared(shares),
+            Err((_, msg)) => {
+                // The user's own files are still worth showing
+                error!("Could not list the shares of {}: {}", auth_user.username(), msg);
+                directory_service
+            }
         }
+    } else {
+        directory_service
+    };
+
+    let target = match resolve_target(
+        &config,
+        auth_user.username(),
+        payload.owner.as_deref(),
+        payload.group.as_deref(),
+        &payload.path,
+        "",
+        AclPermission::Read
+    ).await {
+        Ok(target) => target,
+        Err(response) => return response
+    };
+    let user = target.tree;
+    let dir_name = Path::new(&target.path);
+    let directory_service = directory_service.with_acl(target.acl);
+
+    match directory_service.read_dir_tree(&user, dir_name).await {
+        Ok(tree) => HttpResponse::Ok().json(tree),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
     }
-}
\ No newline at end of file
+}
+
+async fn shares_of(config: &AppConfig, username: &str) -> Result<Vec<Share>, (u16, String)> {
+    ShareService::new(config.share_store.clone(), config.root_dir.as_ref().clone())
+        .list_incoming(username)
+        .await
+}
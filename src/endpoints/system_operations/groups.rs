@@ -0,0 +1,136 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::http::StatusCode;
+use log::info;
+use crate::app_config::AppConfig;
+use crate::dao::db_group_store::DbGroupStore;
+use crate::dao::db_privilege_store::DbPrivilegeStore;
+use crate::models::authentication::auth_user::{AdminUser, ScopedUser, ADMIN_PRIVILEGE};
+use crate::models::authentication::scope::{Account, FilesRead};
+use crate::models::groups::group_access::GroupAccess;
+use crate::models::groups::group_requests::{CreateGroupRequest, SetMemberRequest};
+use crate::services::file_structure::group_service::GroupService;
+use crate::services::file_structure::privilege_service::PrivilegeService;
+
+// All handlers are mounted under the `/api/groups` scope
+
+/// Lists the groups of the user, admins see every group
+#[get("")]
+pub async fn list_groups_handler(
+    authenticated_user: ScopedUser<FilesRead>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let include_all = authenticated_user.0.privilege() >= ADMIN_PRIVILEGE;
+    match group_service(&config).list_groups(authenticated_user.username(), include_all).await {
+        Ok(groups) => HttpResponse::Ok().json(groups),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("")]
+pub async fn create_group_handler(
+    payload: web::Json<CreateGroupRequest>,
+    admin: AdminUser,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    match group_service(&config).create_group(&payload.name).await {
+        Ok(msg) => {
+            info!("{} created group {}", admin.0.username(), payload.name);
+            HttpResponse::Created().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/{group}/delete")]
+pub async fn delete_group_handler(
+    path: web::Path<String>,
+    admin: AdminUser,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let group = path.into_inner();
+
+    match group_service(&config).delete_group(&group).await {
+        Ok(msg) => {
+            info!("{} deleted group {}", admin.0.username(), group);
+            HttpResponse::Ok().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[get("/{group}/members")]
+pub async fn list_members_handler(
+    path: web::Path<String>,
+    authenticated_user: ScopedUser<FilesRead>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let group = path.into_inner();
+    if let Err(response) = require_access(authenticated_user.username(), &group, GroupAccess::Read).await {
+        return response;
+    }
+
+    match group_service(&config).list_members(&group).await {
+        Ok(members) => HttpResponse::Ok().json(members),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+/// Adds a member or changes their access
+#[post("/{group}/members")]
+pub async fn set_member_handler(
+    path: web::Path<String>,
+    payload: web::Json<SetMemberRequest>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let group = path.into_inner();
+    let username = authenticated_user.username();
+    if let Err(response) = require_access(username, &group, GroupAccess::Manage).await {
+        return response;
+    }
+
+    match group_service(&config).set_member(&group, &payload.username, payload.access).await {
+        Ok(msg) => {
+            info!("{} gave {} {} access to group {}", username, payload.username, payload.access.as_str(), group);
+            HttpResponse::Ok().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/{group}/members/{username}/remove")]
+pub async fn remove_member_handler(
+    path: web::Path<(String, String)>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let (group, member) = path.into_inner();
+    let username = authenticated_user.username();
+    if let Err(response) = require_access(username, &group, GroupAccess::Manage).await {
+        return response;
+    }
+
+    match group_service(&config).remove_member(&group, &member).await {
+        Ok(msg) => {
+            info!("{} removed {} from group {}", username, member, group);
+            HttpResponse::Ok().body(msg)
+        },
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+async fn require_access(username: &str, group: &str, access: GroupAccess) -> Result<(), HttpResponse> {
+    PrivilegeService::new(DbPrivilegeStore)
+        .resolve_group_access(username, group, access)
+        .await
+        .map(|_| ())
+        .map_err(|(code, msg)| HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg))
+}
+
+fn group_service(config: &AppConfig) -> GroupService<DbGroupStore> {
+    GroupService::new(
+        DbGroupStore,
+        config.root_dir.as_ref().clone(),
+        config.directory_lock_manager.clone()
+    )
+}
@@ -3,4 +3,11 @@ pub mod download;
 pub mod upload;
 pub mod get_file_structure;
 pub mod rename;
-pub mod directory;
\ No newline at end of file
+pub mod directory;
+pub mod tree_owner;
+pub mod groups;
+
+pub mod acl;
+pub mod shares;
+pub mod share_links;
+pub mod tus;
\ No newline at end of file
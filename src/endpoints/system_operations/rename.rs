@@ -1,21 +1,41 @@
 use actix_web::{post, web, HttpResponse, Responder};
 use actix_web::http::StatusCode;
 use crate::app_config::AppConfig;
-use crate::models::authentication::auth_user::AuthenticatedUser;
+use crate::endpoints::system_operations::tree_owner::{move_acl_path, move_shared_path, resolve_target};
+use crate::models::acl::acl_entry::AclPermission;
+use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::scope::FilesWrite;
 use crate::models::system_operations::rename_item_request::RenameItemRequest;
 use crate::services::file_structure::rename_service::RenameService;
+use crate::services::file_structure::share_service::{is_reserved_path, SHARED_WITH_ME};
 
 #[post("/directory/rename")]
 pub async fn rename_directory(
     req: web::Json<RenameItemRequest>,
-    authenticated_user: AuthenticatedUser,
+    authenticated_user: ScopedUser<FilesWrite>,
     config: web::Data<AppConfig>
 ) -> impl Responder {
-    let username = authenticated_user.0.sub;
-    let path = &req.path;
-    let old_name = &req.old_name;
+    // Renaming takes the item away from where it was like deleting it does, shares don't allow it
+    let target = match resolve_target(
+        &config,
+        authenticated_user.username(),
+        req.owner.as_deref(),
+        req.group.as_deref(),
+        &req.path,
+        &req.old_name,
+        AclPermission::Delete
+    ).await {
+        Ok(target) => target,
+        Err(response) => return response
+    };
+    let username = target.tree;
+    let path = &target.path;
+    let old_name = &target.name;
     let new_name = &req.new_name;
-    let rename_service = RenameService::new(config.root_dir.as_ref().clone());
+    if is_reserved_path(&format!("{}/{}", path, new_name)) {
+        return HttpResponse::BadRequest().body(format!("'{}' is reserved", SHARED_WITH_ME));
+    }
+    let rename_service = RenameService::new(config.root_dir.as_ref().clone()).with_acl(target.acl);
     
     match rename_service.rename_directory(
         &username,
@@ -23,7 +43,13 @@ pub async fn rename_directory(
         old_name,
         new_name
     ).await {
-        Ok(msg) => HttpResponse::Ok().body(msg),
+        Ok(msg) => {
+            let old_path = format!("{}/{}", path, old_name);
+            let new_path = format!("{}/{}", path, new_name);
+            move_acl_path(&config, &username, &old_path, &new_path).await;
+            move_shared_path(&config, &username, &old_path, &new_path).await;
+            HttpResponse::Ok().body(msg)
+        },
         Err((code, msg)) => HttpResponse::build(StatusCode::try_from(code).unwrap()).body(msg)
     }
 }
\ No newline at end of file
@@ -0,0 +1,281 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use actix_multipart::Multipart;
+use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
+use actix_web::http::header::{Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue, RETRY_AFTER};
+use actix_web::http::StatusCode;
+use log::info;
+use crate::app_config::AppConfig;
+use crate::endpoints::system_operations::upload::{read_upload_form, upload_results_response};
+use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::scope::Account;
+use crate::models::shares::share_link::{CreateFileDropRequest, CreateShareLinkRequest, ShareLinkPasswordForm};
+use crate::models::system_operations::upload_file_request::UploadFileResult;
+use crate::services::authentication::opaque_token::hash_opaque_token;
+use crate::services::file_structure::directory_service::DirectoryService;
+use crate::services::file_structure::file_service::FileService;
+use crate::services::file_structure::share_link_service::ShareLinkService;
+
+// The management handlers are mounted under the `/api/share/link` and `/api/share/drop` scopes
+
+/// Creates a link to a file or directory of the user's tree
+#[post("")]
+pub async fn create_share_link_handler(
+    payload: web::Json<CreateShareLinkRequest>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    match share_link_service(&config).create_link(
+        authenticated_user.username(),
+        &payload.path,
+        payload.expires_at,
+        payload.password.as_deref(),
+        payload.max_downloads
+    ).await {
+        // The token is only shown this one time
+        Ok((token, link)) => HttpResponse::Created().json(serde_json::json!({
+            "token": token,
+            "url": format!("/s/{}", token),
+            "link": link
+        })),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+/// Creates a file drop, a link that only accepts uploads into a directory of the user's tree
+#[post("")]
+pub async fn create_file_drop_handler(
+    payload: web::Json<CreateFileDropRequest>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    match share_link_service(&config).create_drop(
+        authenticated_user.username(),
+        &payload.path,
+        payload.expires_at,
+        payload.max_bytes,
+        payload.max_files
+    ).await {
+        Ok((token, link)) => HttpResponse::Created().json(serde_json::json!({
+            "token": token,
+            "url": format!("/s/{}/upload", token),
+            "link": link
+        })),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+/// The links and file drops the user created
+#[get("")]
+pub async fn list_share_links_handler(
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    match share_link_service(&config).list(authenticated_user.username()).await {
+        Ok(links) => HttpResponse::Ok().json(links),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+#[post("/{id}/revoke")]
+pub async fn revoke_share_link_handler(
+    path: web::Path<String>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    match share_link_service(&config).revoke(authenticated_user.username(), &path.into_inner()).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+/// Public download of a link, outside of the `/api` scope
+#[get("/s/{token}")]
+pub async fn open_share_link_handler(
+    req: HttpRequest,
+    path: web::Path<String>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    serve_share_link(&req, &path.into_inner(), None, &config).await
+}
+
+/// Public download of a password protected link, sent by its password form
+#[post("/s/{token}")]
+pub async fn unlock_share_link_handler(
+    req: HttpRequest,
+    path: web::Path<String>,
+    form: web::Form<ShareLinkPasswordForm>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    serve_share_link(&req, &path.into_inner(), Some(&form.password), &config).await
+}
+
+/// Upload form of a file drop
+#[get("/s/{token}/upload")]
+pub async fn file_drop_form_handler(path: web::Path<String>, config: web::Data<AppConfig>) -> impl Responder {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    match share_link_service(&config).open_drop(&path.into_inner(), now).await {
+        Ok(_) => HttpResponse::Ok().content_type("text/html; charset=utf-8").body(UPLOAD_FORM),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+/// Anonymous upload into a file drop. Takes the same form as `/api/upload`, but without a `path`,
+/// `owner` or `group`, the files go where the link points.
+#[post("/s/{token}/upload")]
+pub async fn file_drop_upload_handler(
+    mut payload: Multipart,
+    path: web::Path<String>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    let token = path.into_inner();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    let share_link_service = share_link_service(&config);
+    // Checked before reading the upload, so nothing is staged for unusable links and uploads
+    // are stopped as soon as they exceed the space left
+    let link = match share_link_service.open_drop(&token, now).await {
+        Ok(link) => link,
+        Err((code, msg)) => return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    };
+    let space_left = link.max_bytes.map(|max_bytes| (max_bytes - link.uploaded_bytes).max(0) as u64);
+    let files_left = link.max_files.map(|max_files| (max_files - link.upload_count).max(0) as usize);
+
+    let file_service = FileService::new(
+        config.root_dir.as_ref().clone(),
+        config.directory_lock_manager.clone()
+    );
+    let data = match read_upload_form(&mut payload, &file_service, space_left, files_left, false).await {
+        Ok(data) => data,
+        Err(response) => return response
+    };
+    if data.files.is_empty() {
+        return HttpResponse::BadRequest().body("No file was provided in the request");
+    }
+
+    // Folders are flattened, the uploader can't create directories in the file drop. Every file is
+    // stored on its own, so one that fails doesn't hide the ones stored before it. The stored names
+    // are not returned, the uploader shouldn't learn what else is in the directory.
+    let mut results = Vec::with_capacity(data.files.len());
+    for uploaded_file in data.files {
+        let mut result = UploadFileResult {
+            name: uploaded_file.name,
+            status: 201,
+            stored_as: None,
+            size: uploaded_file.file.size,
+            etag: None,
+            error: None,
+        };
+        let stored = match file_service.sanitize_relative_path(&result.name) {
+            Some((_, filename)) => share_link_service.receive_file(&token, &filename, uploaded_file.file, now).await,
+            None => Err((400, format!("Invalid file name '{}'", result.name)))
+        };
+        if let Err((code, msg)) = stored {
+            result.status = code;
+            result.error = Some(msg);
+        }
+        results.push(result);
+    }
+    upload_results_response(results)
+}
+
+async fn serve_share_link(req: &HttpRequest, token: &str, password: Option<&str>, config: &AppConfig) -> HttpResponse {
+    // Passwords are throttled like logins, keyed by the link instead of a username
+    let throttle_key = format!("link:{}", hash_opaque_token(token));
+    let ip = req.peer_addr().map(|addr| addr.ip().to_string());
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    if password.is_some() {
+        if let Err(retry_after) = config.login_throttle.check(&throttle_key, ip.as_deref(), now) {
+            return HttpResponse::TooManyRequests()
+                .insert_header((RETRY_AFTER, retry_after.to_string()))
+                .body("Too many wrong passwords, try again later");
+        }
+    }
+
+    let link = match share_link_service(config).open(token, password, now).await {
+        Ok(link) => link,
+        Err((401, msg)) => {
+            if password.is_some() {
+                config.login_throttle.record_failure(&throttle_key, ip.as_deref(), now);
+            }
+            return HttpResponse::Unauthorized().content_type("text/html; charset=utf-8").body(password_form(&msg));
+        },
+        Err((code, msg)) => return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    };
+    if password.is_some() {
+        config.login_throttle.record_success(&throttle_key);
+    }
+
+    let (parent, name) = link.path.rsplit_once('/').unwrap_or(("", link.path.as_str()));
+    let read = if link.is_dir {
+        let directory_service = DirectoryService::new(
+            config.root_dir.as_ref().clone(),
+            config.directory_lock_manager.clone()
+        );
+        directory_service
+            .download_directory_streamed(&link.owner, parent, name)
+            .await
+            .map(|data| (data, format!("{}.zip", name), "application/zip"))
+    } else {
+        let file_service = FileService::new(
+            config.root_dir.as_ref().clone(),
+            config.directory_lock_manager.clone()
+        );
+        file_service
+            .read_file_from_any_directory(&link.owner, parent, name)
+            .await
+            .map(|(content, filename)| (content, filename, "application/octet-stream"))
+    };
+    let (content, filename, content_type) = match read {
+        Ok(read) => read,
+        Err((code, msg)) => return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    };
+
+    // Only downloads that can be served use up the link
+    let link = match share_link_service(config).record_download(link, now).await {
+        Ok(link) => link,
+        Err((code, msg)) => return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    };
+    info!("Link {} of {} was downloaded", link.id, link.owner);
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(attachment(&filename))
+        .body(content)
+}
+
+/// Names the download. Browsers take the exact name from `filename*` (RFC 6266), `filename` is
+/// a plain ASCII fallback, so names with quotes or line breaks can't break the header.
+pub(crate) fn attachment(filename: &str) -> ContentDisposition {
+    let fallback = filename
+        .chars()
+        .map(|c| if c.is_ascii() && !c.is_ascii_control() { c } else { '_' })
+        .collect();
+    ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![
+            DispositionParam::Filename(fallback),
+            DispositionParam::FilenameExt(ExtendedValue {
+                charset: Charset::Ext("UTF-8".to_string()),
+                language_tag: None,
+                value: filename.as_bytes().to_vec(),
+            }),
+        ],
+    }
+}
+
+/// Posts the file back to the address it was opened from
+const UPLOAD_FORM: &str = "<!DOCTYPE html><html><body>\
+    <form method=\"post\" enctype=\"multipart/form-data\"><input type=\"file\" name=\"file\"> \
+    <button type=\"submit\">Upload</button></form></body></html>";
+
+/// Posts the password back to the link it was opened from
+fn password_form(message: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><body><p>{}</p>\
+         <form method=\"post\"><input type=\"password\" name=\"password\" autofocus> \
+         <button type=\"submit\">Download</button></form></body></html>",
+        message
+    )
+}
+
+fn share_link_service(config: &AppConfig) -> ShareLinkService {
+    ShareLinkService::new(config.share_link_store.clone(), config.root_dir.as_ref().clone())
+}
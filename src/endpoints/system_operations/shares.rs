@@ -0,0 +1,69 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::http::StatusCode;
+use crate::app_config::AppConfig;
+use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::scope::{Account, FilesRead};
+use crate::models::shares::share_requests::CreateShareRequest;
+use crate::services::file_structure::share_service::ShareService;
+
+// All handlers are mounted under the `/api/shares` scope
+
+/// Shares a file or directory of the user's tree with another user
+#[post("")]
+pub async fn create_share_handler(
+    payload: web::Json<CreateShareRequest>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    match share_service(&config).create_share(
+        authenticated_user.username(),
+        &payload.path,
+        &payload.recipient,
+        payload.name.as_deref(),
+        payload.allow_upload
+    ).await {
+        Ok(share) => HttpResponse::Created().json(share),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+/// The shares the user created
+#[get("")]
+pub async fn list_shares_handler(
+    authenticated_user: ScopedUser<FilesRead>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    match share_service(&config).list_outgoing(authenticated_user.username()).await {
+        Ok(shares) => HttpResponse::Ok().json(shares),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+/// The shares the user received
+#[get("/incoming")]
+pub async fn list_incoming_shares_handler(
+    authenticated_user: ScopedUser<FilesRead>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    match share_service(&config).list_incoming(authenticated_user.username()).await {
+        Ok(shares) => HttpResponse::Ok().json(shares),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+/// Owners revoke a share, recipients remove it from their `Shared with me` node
+#[post("/{id}/revoke")]
+pub async fn revoke_share_handler(
+    path: web::Path<i64>,
+    authenticated_user: ScopedUser<Account>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    match share_service(&config).revoke(authenticated_user.username(), path.into_inner()).await {
+        Ok(msg) => HttpResponse::Ok().body(msg),
+        Err((code, msg)) => HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    }
+}
+
+fn share_service(config: &AppConfig) -> ShareService {
+    ShareService::new(config.share_store.clone(), config.root_dir.as_ref().clone())
+}
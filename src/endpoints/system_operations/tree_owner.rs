@@ -0,0 +1,182 @@
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use log::{error, info};
+use crate::app_config::AppConfig;
+use crate::dao::db_privilege_store::DbPrivilegeStore;
+use crate::models::groups::group_access::GroupAccess;
+use crate::models::acl::acl_entry::AclPermission;
+use crate::services::file_structure::acl_service::{normalize_acl_path, AclCheck, AclService};
+use crate::services::file_structure::group_service::group_tree;
+use crate::services::file_structure::privilege_service::PrivilegeService;
+use crate::services::file_structure::share_link_service::ShareLinkService;
+use crate::services::file_structure::share_service::{is_plain_name, strip_shared_prefix, ShareService, SHARED_WITH_ME};
+
+/// The tree a file request operates on
+pub(crate) struct TreeAccess {
+    pub tree: String,
+    /// Set when the requester is not allowed into the tree as a whole. The services then
+    /// check every operation against the ACL of the tree.
+    pub acl: Option<AclCheck>,
+}
+
+/// Where in which tree a file request operates
+pub(crate) struct FileTarget {
+    pub tree: String,
+    pub path: String,
+    pub name: String,
+    pub acl: Option<AclCheck>,
+}
+
+/// Resolves the optional `owner` or `group` of a file request to the tree that is operated on,
+/// or to the response refusing the request. `permission` is what the request needs.
+pub(crate) async fn resolve_tree_owner(
+    config: &AppConfig,
+    requester: &str,
+    owner: Option<&str>,
+    group: Option<&str>,
+    permission: AclPermission
+) -> Result<TreeAccess, HttpResponse> {
+    let access = match permission {
+        AclPermission::Read => GroupAccess::Read,
+        AclPermission::Write | AclPermission::Delete => GroupAccess::Write,
+        AclPermission::Share => GroupAccess::Manage,
+    };
+    let privilege_service = PrivilegeService::new(DbPrivilegeStore);
+    let (result, tree) = match (owner.filter(|o| !o.trim().is_empty()), group.filter(|g| !g.trim().is_empty())) {
+        (Some(_), Some(_)) => {
+            return Err(HttpResponse::BadRequest().body("Either an owner or a group can be given, not both"))
+        },
+        (None, Some(group)) => (
+            privilege_service.resolve_group_access(requester, group.trim(), access).await,
+            group_tree(group.trim())
+        ),
+        (owner, None) => (
+            privilege_service.resolve_tree_owner(requester, owner).await,
+            owner.map(str::trim).unwrap_or(requester).to_string()
+        ),
+    };
+
+    match result {
+        Ok(tree) => {
+            if tree != requester {
+                info!("{} is accessing the files of {}", requester, tree);
+            }
+            Ok(TreeAccess { tree, acl: None })
+        },
+        // Not allowed into the whole tree, but maybe into parts of it
+        Err((403, _)) => {
+            info!("{} is accessing the files of {} through its ACL", requester, tree);
+            let acl_service = AclService::new(config.acl_store.clone());
+            Ok(TreeAccess { tree, acl: Some(AclCheck::new(acl_service, requester.to_string())) })
+        },
+        Err((code, msg)) => Err(HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg))
+    }
+}
+
+/// Like `resolve_tree_owner`, but also resolves `path` and `name` below the `Shared with me` node of the
+/// requester's own tree to the tree of the share's owner. `name` is empty for requests without one.
+/// Shares can be read, and written to if the owner allows uploads.
+pub(crate) async fn resolve_target(
+    config: &AppConfig,
+    requester: &str,
+    owner: Option<&str>,
+    group: Option<&str>,
+    path: &str,
+    name: &str,
+    permission: AclPermission
+) -> Result<FileTarget, HttpResponse> {
+    let own_tree = owner.is_none_or(|o| o.trim().is_empty()) && group.is_none_or(|g| g.trim().is_empty());
+    let shared_path = normalize_acl_path(path)
+        .ok()
+        .filter(|_| own_tree)
+        .and_then(|path| strip_shared_prefix(&path).map(str::to_string));
+    let Some(shared_path) = shared_path else {
+        let access = resolve_tree_owner(config, requester, owner, group, permission).await?;
+        return Ok(FileTarget {
+            tree: access.tree,
+            path: path.to_string(),
+            name: name.to_string(),
+            acl: access.acl,
+        });
+    };
+
+    let refuse = |code: u16, msg: String| HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg);
+    if !name.is_empty() && !is_plain_name(name) {
+        return Err(refuse(400, format!("Invalid name '{}'", name)));
+    }
+    // Either a share itself, named by `name`, or something inside a shared directory
+    let (share_name, inner) = match shared_path.split_once('/') {
+        Some((share_name, inner)) => (share_name.to_string(), Some(inner.to_string())),
+        None if shared_path.is_empty() && !name.is_empty() => (name.to_string(), None),
+        None if shared_path.is_empty() => return Err(refuse(400, format!("'{}' is not a real directory", SHARED_WITH_ME))),
+        None => (shared_path.clone(), Some(String::new())),
+    };
+
+    let share_service = ShareService::new(config.share_store.clone(), config.root_dir.as_ref().clone());
+    let share = share_service.resolve(requester, &share_name).await.map_err(|(code, msg)| refuse(code, msg))?;
+    let allowed = match permission {
+        AclPermission::Read => true,
+        AclPermission::Write => share.allow_upload,
+        AclPermission::Delete | AclPermission::Share => false,
+    };
+    if !allowed {
+        return Err(refuse(403, format!("'{}' doesn't allow this", share.name)));
+    }
+
+    let (path, name) = match inner {
+        Some(inner) if inner.is_empty() => (share.path.clone(), name.to_string()),
+        Some(inner) => (format!("{}/{}", share.path, inner), name.to_string()),
+        None => match share.path.rsplit_once('/') {
+            Some((parent, source_name)) => (parent.to_string(), source_name.to_string()),
+            None => (String::new(), share.path.clone()),
+        },
+    };
+    info!("{} is accessing {} of {} through a share", requester, path, share.owner);
+    Ok(FileTarget { tree: share.owner, path, name, acl: None })
+}
+
+/// Drops the ACL entries of a deleted file or directory. The deletion stands even if this fails.
+pub(crate) async fn forget_acl_path(config: &AppConfig, tree: &str, path: &str) {
+    if let Err((_, e)) = AclService::new(config.acl_store.clone()).delete_path(tree, path).await {
+        error!("Could not remove the ACL entries of {} in {}: {}", path, tree, e);
+    }
+}
+
+/// Moves the ACL entries of a renamed file or directory. The rename stands even if this fails.
+pub(crate) async fn move_acl_path(config: &AppConfig, tree: &str, old_path: &str, new_path: &str) {
+    if let Err((_, e)) = AclService::new(config.acl_store.clone()).move_path(tree, old_path, new_path).await {
+        error!("Could not move the ACL entries of {} in {}: {}", old_path, tree, e);
+    }
+}
+
+/// Drops the shares and revokes the links of a deleted file or directory. The deletion stands even if this fails.
+pub(crate) async fn forget_shared_path(config: &AppConfig, tree: &str, path: &str) {
+    let share_service = ShareService::new(config.share_store.clone(), config.root_dir.as_ref().clone());
+    if let Err((_, e)) = share_service.delete_path(tree, path).await {
+        error!("Could not remove the shares of {} in {}: {}", path, tree, e);
+    }
+    let share_link_service = ShareLinkService::new(config.share_link_store.clone(), config.root_dir.as_ref().clone());
+    if let Err((_, e)) = share_link_service.delete_path(tree, path).await {
+        error!("Could not revoke the links of {} in {}: {}", path, tree, e);
+    }
+}
+
+/// Keeps the shares of a file that was stored again at its path. The upload stands even if this fails.
+pub(crate) async fn refresh_shared_file(config: &AppConfig, tree: &str, path: &str) {
+    let share_service = ShareService::new(config.share_store.clone(), config.root_dir.as_ref().clone());
+    if let Err((_, e)) = share_service.replace_file(tree, path).await {
+        error!("Could not keep the shares of {} in {}: {}", path, tree, e);
+    }
+}
+
+/// Moves the shares and links of a renamed file or directory. The rename stands even if this fails.
+pub(crate) async fn move_shared_path(config: &AppConfig, tree: &str, old_path: &str, new_path: &str) {
+    let share_service = ShareService::new(config.share_store.clone(), config.root_dir.as_ref().clone());
+    if let Err((_, e)) = share_service.move_path(tree, old_path, new_path).await {
+        error!("Could not move the shares of {} in {}: {}", old_path, tree, e);
+    }
+    let share_link_service = ShareLinkService::new(config.share_link_store.clone(), config.root_dir.as_ref().clone());
+    if let Err((_, e)) = share_link_service.move_path(tree, old_path, new_path).await {
+        error!("Could not move the links of {} in {}: {}", old_path, tree, e);
+    }
+}
@@ -0,0 +1,231 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use actix_web::{delete, head, options, patch, post, web, HttpRequest, HttpResponse, HttpResponseBuilder, Responder};
+use actix_web::http::header::{HttpDate, CACHE_CONTROL, CONTENT_TYPE, LOCATION};
+use actix_web::http::StatusCode;
+use futures_util::stream;
+use log::info;
+use crate::app_config::AppConfig;
+use crate::endpoints::system_operations::tree_owner::resolve_target;
+use crate::endpoints::system_operations::upload::store_upload;
+use crate::models::acl::acl_entry::AclPermission;
+use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::scope::FilesWrite;
+use crate::models::system_operations::conflict_policy::ConflictPolicy;
+use crate::models::system_operations::tus_upload::TusUpload;
+use crate::services::file_structure::file_service::{FileService, StagedFile};
+use crate::services::file_structure::tus_service::{parse_upload_metadata, TusService};
+
+// All handlers are mounted under the `/api/tus` scope and implement tus 1.0 with the
+// creation, termination and expiration extensions
+
+const TUS_VERSION: &str = "1.0.0";
+const TUS_EXTENSIONS: &str = "creation,termination,expiration";
+const OFFSET_CONTENT_TYPE: &str = "application/offset+octet-stream";
+
+/// Tells clients what the server supports
+#[options("")]
+pub async fn tus_options_handler(config: web::Data<AppConfig>) -> impl Responder {
+    let mut response = HttpResponse::NoContent();
+    response
+        .insert_header(("Tus-Version", TUS_VERSION))
+        .insert_header(("Tus-Extension", TUS_EXTENSIONS));
+    response
+        .insert_header(("Tus-Max-Size", config.tus_settings.max_size.to_string()))
+        .finish()
+}
+
+/// Creates an upload from the `Upload-Length` and `Upload-Metadata` headers
+#[post("")]
+pub async fn tus_create_handler(
+    req: HttpRequest,
+    authenticated_user: ScopedUser<FilesWrite>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    if let Some(response) = check_tus_resumable(&req) {
+        return response;
+    }
+    let Some(length) = header(&req, "Upload-Length").and_then(|length| length.parse::<u64>().ok()) else {
+        return tus_error(400, "A valid Upload-Length is required".to_string());
+    };
+    let metadata = match parse_upload_metadata(header(&req, "Upload-Metadata").unwrap_or_default()) {
+        Ok(metadata) => metadata,
+        Err((code, msg)) => return tus_error(code, msg)
+    };
+
+    let username = authenticated_user.username();
+    let upload = match tus_service(&config).create(username, length, &metadata, now()).await {
+        Ok(upload) => upload,
+        Err((code, msg)) => return tus_error(code, msg)
+    };
+    // Refused before any data is sent if the user can't upload there
+    let target = resolve_target(
+        &config,
+        username,
+        upload.owner.as_deref(),
+        upload.group.as_deref(),
+        &upload.path,
+        &upload.filename,
+        AclPermission::Write
+    ).await;
+    if let Err(response) = target {
+        let _ = tus_service(&config).terminate(username, &upload.id, now()).await;
+        return response;
+    }
+
+    // An empty file is complete right away
+    if length == 0 {
+        let empty = stream::empty::<Result<&[u8], std::io::Error>>();
+        let staged_file = match tus_service(&config).append(username, &upload.id, 0, empty, now()).await {
+            Ok((_, _, Some(staged_file))) => staged_file,
+            Ok((_, _, None)) => return tus_error(500, "The empty upload was not complete".to_string()),
+            Err((code, msg)) => return tus_error(code, msg)
+        };
+        if let Err(response) = finish_upload(&config, username, &upload, staged_file).await {
+            return response;
+        }
+    }
+
+    let mut response = tus_response(StatusCode::CREATED);
+    response.insert_header((LOCATION, format!("/api/tus/{}", upload.id)));
+    with_expiration(&mut response, &upload).finish()
+}
+
+/// How much of the upload the server has, so the client knows where to resume
+#[head("/{id}")]
+pub async fn tus_offset_handler(
+    req: HttpRequest,
+    path: web::Path<String>,
+    authenticated_user: ScopedUser<FilesWrite>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    if let Some(response) = check_tus_resumable(&req) {
+        return response;
+    }
+    match tus_service(&config).offset(authenticated_user.username(), &path.into_inner(), now()).await {
+        Ok((upload, offset)) => {
+            let mut response = tus_response(StatusCode::OK);
+            response
+                .insert_header(("Upload-Offset", offset.to_string()))
+                .insert_header(("Upload-Length", upload.length.to_string()))
+                .insert_header((CACHE_CONTROL, "no-store"));
+            with_expiration(&mut response, &upload).finish()
+        },
+        Err((code, msg)) => tus_error(code, msg)
+    }
+}
+
+/// Appends the body at `Upload-Offset`. The last chunk moves the file into place.
+#[patch("/{id}")]
+pub async fn tus_append_handler(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Payload,
+    authenticated_user: ScopedUser<FilesWrite>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    if let Some(response) = check_tus_resumable(&req) {
+        return response;
+    }
+    if header(&req, CONTENT_TYPE.as_str()) != Some(OFFSET_CONTENT_TYPE) {
+        return tus_error(415, format!("The Content-Type must be {}", OFFSET_CONTENT_TYPE));
+    }
+    let Some(offset) = header(&req, "Upload-Offset").and_then(|offset| offset.parse::<u64>().ok()) else {
+        return tus_error(400, "A valid Upload-Offset is required".to_string());
+    };
+
+    let username = authenticated_user.username();
+    let tus_service = tus_service(&config);
+    let (upload, offset, completed) = match tus_service.append(username, &path.into_inner(), offset, body, now()).await {
+        Ok(appended) => appended,
+        Err((code, msg)) => return tus_error(code, msg)
+    };
+    if let Some(staged_file) = completed {
+        if let Err(response) = finish_upload(&config, username, &upload, staged_file).await {
+            return response;
+        }
+    }
+
+    let mut response = tus_response(StatusCode::NO_CONTENT);
+    response.insert_header(("Upload-Offset", offset.to_string()));
+    with_expiration(&mut response, &upload).finish()
+}
+
+/// Cancels the upload and deletes what was received
+#[delete("/{id}")]
+pub async fn tus_terminate_handler(
+    req: HttpRequest,
+    path: web::Path<String>,
+    authenticated_user: ScopedUser<FilesWrite>,
+    config: web::Data<AppConfig>
+) -> impl Responder {
+    if let Some(response) = check_tus_resumable(&req) {
+        return response;
+    }
+    match tus_service(&config).terminate(authenticated_user.username(), &path.into_inner(), now()).await {
+        Ok(()) => tus_response(StatusCode::NO_CONTENT).finish(),
+        Err((code, msg)) => tus_error(code, msg)
+    }
+}
+
+/// Stores the data of a complete upload like a regular one. If that fails the upload is discarded.
+async fn finish_upload(
+    config: &AppConfig,
+    username: &str,
+    upload: &TusUpload,
+    staged_file: StagedFile
+) -> Result<(), HttpResponse> {
+    let target = resolve_target(
+        config,
+        username,
+        upload.owner.as_deref(),
+        upload.group.as_deref(),
+        &upload.path,
+        &upload.filename,
+        AclPermission::Write
+    ).await?;
+    let file_service = FileService::new(config.root_dir.as_ref().clone(), config.directory_lock_manager.clone());
+    store_upload(config, file_service, target, staged_file, &ConflictPolicy::Overwrite).await?;
+    info!("{} finished upload {} to {}/{}", username, upload.id, upload.path, upload.filename);
+    Ok(())
+}
+
+/// Requests of another protocol version are refused
+fn check_tus_resumable(req: &HttpRequest) -> Option<HttpResponse> {
+    if header(req, "Tus-Resumable") == Some(TUS_VERSION) {
+        return None;
+    }
+    Some(HttpResponse::PreconditionFailed()
+        .insert_header(("Tus-Version", TUS_VERSION))
+        .body(format!("Only tus {} is supported", TUS_VERSION)))
+}
+
+fn header<'a>(req: &'a HttpRequest, name: &str) -> Option<&'a str> {
+    req.headers().get(name).and_then(|value| value.to_str().ok())
+}
+
+fn tus_response(status: StatusCode) -> HttpResponseBuilder {
+    let mut response = HttpResponse::build(status);
+    response.insert_header(("Tus-Resumable", TUS_VERSION));
+    response
+}
+
+fn tus_error(code: u16, msg: String) -> HttpResponse {
+    tus_response(StatusCode::from_u16(code).unwrap()).body(msg)
+}
+
+fn with_expiration<'a>(response: &'a mut HttpResponseBuilder, upload: &TusUpload) -> &'a mut HttpResponseBuilder {
+    let expires = UNIX_EPOCH + Duration::from_secs(upload.expires_at.max(0) as u64);
+    response.insert_header(("Upload-Expires", HttpDate::from(expires).to_string()))
+}
+
+fn tus_service(config: &AppConfig) -> TusService {
+    TusService::new(
+        config.root_dir.as_ref().clone(),
+        config.directory_lock_manager.clone(),
+        config.tus_settings.clone()
+    )
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+}
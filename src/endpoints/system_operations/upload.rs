@@ -1,37 +1,346 @@
 use crate::services::file_structure::file_service;
-use actix_web::{post, web, HttpResponse, Responder};
-use std::path::Path;
-use actix_multipart::Multipart;
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
+use std::path::{Path, PathBuf};
+use actix_multipart::{Field, Multipart};
+use actix_web::body::to_bytes;
+use actix_web::http::header::IF_MATCH;
 use actix_web::http::StatusCode;
 use futures_util::TryStreamExt;
 use log::{error, info};
 use crate::app_config::AppConfig;
-use crate::models::authentication::auth_user::AuthenticatedUser;
-use crate::models::system_operations::upload_file_request::{UploadRequestData};
+use crate::endpoints::system_operations::tree_owner::{refresh_shared_file, resolve_target, FileTarget};
+use crate::models::acl::acl_entry::AclPermission;
+use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::scope::FilesWrite;
+use crate::models::system_operations::conflict_policy::{ConflictPolicy, UploadQuery};
+use crate::models::system_operations::upload_file_request::{UploadFileResult, UploadRequestData, UploadedFile};
+use crate::services::file_structure::acl_service::normalize_acl_path;
+use crate::services::file_structure::archive_service::ArchiveService;
+use crate::services::file_structure::file_service::StagedFile;
+use crate::services::file_structure::share_service::{is_reserved_path, SHARED_WITH_ME};
 
 /// POST endpoint to handle file uploads from the user directory.
 #[post("/upload")]
 pub async fn upload_file_from_user_directory(
+    req: HttpRequest,
     mut payload: Multipart,
-    authenticated_user: AuthenticatedUser,
+    query: web::Query<UploadQuery>,
+    authenticated_user: ScopedUser<FilesWrite>,
     config: web::Data<AppConfig>,
 ) -> impl Responder {
-    let username = authenticated_user.0.sub;
     let file_service = file_service::FileService::new(
         config.root_dir.as_ref().clone(),
         config.directory_lock_manager.clone()
     );
 
+    let data = match read_upload_form(&mut payload, &file_service, None, None, true).await {
+        Ok(data) => data,
+        Err(response) => return response
+    };
+
+    // Now that we've read all fields, check if we have both the files and path
+    if data.files.is_empty() {
+        return HttpResponse::BadRequest().body("No file was provided in the request");
+    }
+
+    let path = match data.path {
+        Some(p) => p,
+        None => {
+            return HttpResponse::BadRequest().body("No path was provided in the request");
+        }
+    };
+
+    // The form field wins over the query parameter
+    let if_match = req.headers().get(IF_MATCH).and_then(|value| value.to_str().ok());
+    let policy = match conflict_policy(data.conflict.as_deref().or(query.conflict.as_deref()), if_match) {
+        Ok(policy) => policy,
+        Err((code, msg)) => return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    };
+
+    // Every file is stored on its own, one that can't be stored doesn't stop the others
+    let mut results = Vec::with_capacity(data.files.len());
+    for uploaded_file in data.files {
+        results.push(upload_file(
+            &config,
+            authenticated_user.username(),
+            data.owner.as_deref(),
+            data.group.as_deref(),
+            &path,
+            uploaded_file,
+            &policy
+        ).await);
+    }
+
+    upload_results_response(results)
+}
+
+/// Extracts an uploaded zip, tar or tar.gz archive into `path`. Takes the same form and conflict modes as
+/// `/upload` with a single archive as the file, and answers like it with a result per extracted file.
+#[post("/upload/archive")]
+pub async fn upload_archive_handler(
+    req: HttpRequest,
+    mut payload: Multipart,
+    query: web::Query<UploadQuery>,
+    authenticated_user: ScopedUser<FilesWrite>,
+    config: web::Data<AppConfig>,
+) -> impl Responder {
+    let file_service = file_service::FileService::new(
+        config.root_dir.as_ref().clone(),
+        config.directory_lock_manager.clone()
+    );
+    // Archives too large to be extracted are stopped before they fill the staging folder
+    let max_archive_size = config.archive_limits.max_archive_size();
+    let data = match read_upload_form(&mut payload, &file_service, Some(max_archive_size), None, true).await {
+        Ok(data) => data,
+        Err(response) => return response
+    };
+
+    let mut files = data.files;
+    let archive = match (files.pop(), files.is_empty()) {
+        (Some(archive), true) => archive,
+        (Some(_), false) => return HttpResponse::BadRequest().body("Only one archive can be extracted at a time"),
+        (None, _) => return HttpResponse::BadRequest().body("No file was provided in the request")
+    };
+    let Some(path) = data.path else {
+        return HttpResponse::BadRequest().body("No path was provided in the request");
+    };
+    let if_match = req.headers().get(IF_MATCH).and_then(|value| value.to_str().ok());
+    let policy = match conflict_policy(data.conflict.as_deref().or(query.conflict.as_deref()), if_match) {
+        Ok(policy) => policy,
+        Err((code, msg)) => return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    };
+
+    // Refused before extracting anything if the user can't upload there. Every file is
+    // checked again when it is stored.
+    let username = authenticated_user.username();
+    if let Err(response) = resolve_target(
+        &config,
+        username,
+        data.owner.as_deref(),
+        data.group.as_deref(),
+        &path,
+        "",
+        AclPermission::Write
+    ).await {
+        return response;
+    }
+
+    let archive_service = ArchiveService::new(config.root_dir.as_ref().clone(), config.archive_limits.clone());
+    let extracted = match archive_service.extract(archive.file).await {
+        Ok(extracted) => extracted,
+        Err((code, msg)) => return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg)
+    };
+    if extracted.files.is_empty() {
+        return HttpResponse::BadRequest().body("The archive contains no files");
+    }
+    info!("{} is extracting {} files into {}", username, extracted.files.len(), path);
+
+    // Every file is stored on its own like an upload, moved into place at once under the lock of its
+    // path. Nothing holds the folder for the whole archive, so other writes into it can come between
+    // its files, and with `overwrite` the last write of a name wins.
+    let mut results = Vec::with_capacity(extracted.files.len());
+    for (name, size) in &extracted.files {
+        let uploaded_file = match extracted.take_file(name, *size) {
+            Ok(file) => UploadedFile { name: name.clone(), file },
+            Err(e) => {
+                error!("Could not take {} from the extracted archive: {}", name, e);
+                results.push(UploadFileResult {
+                    name: name.clone(),
+                    status: 500,
+                    stored_as: None,
+                    size: *size,
+                    etag: None,
+                    error: Some(e.to_string()),
+                });
+                continue;
+            }
+        };
+        results.push(upload_file(
+            &config,
+            username,
+            data.owner.as_deref(),
+            data.group.as_deref(),
+            &path,
+            uploaded_file,
+            &policy
+        ).await);
+    }
+    upload_results_response(results)
+}
+
+/// A request whose files all ended the same way answers with their status, others with `207`
+pub(crate) fn upload_results_response(results: Vec<UploadFileResult>) -> HttpResponse {
+    let status = match results.iter().all(|result| result.status == results[0].status) {
+        true => StatusCode::from_u16(results[0].status).unwrap(),
+        false => StatusCode::MULTI_STATUS
+    };
+    HttpResponse::build(status).json(results)
+}
+
+/// Stores one file of an upload into `path`, or the folder it was uploaded in below `path`
+async fn upload_file(
+    config: &AppConfig,
+    username: &str,
+    owner: Option<&str>,
+    group: Option<&str>,
+    path: &str,
+    uploaded_file: UploadedFile,
+    policy: &ConflictPolicy
+) -> UploadFileResult {
+    let mut result = UploadFileResult {
+        name: uploaded_file.name,
+        status: 200,
+        stored_as: None,
+        size: uploaded_file.file.size,
+        etag: None,
+        error: None,
+    };
+    let file_service = file_service::FileService::new(
+        config.root_dir.as_ref().clone(),
+        config.directory_lock_manager.clone()
+    );
+    let Some((folder, filename)) = file_service.sanitize_relative_path(&result.name) else {
+        result.status = 400;
+        result.error = Some(format!("Invalid file name '{}'", result.name));
+        return result;
+    };
+    let dir = [path.trim_matches('/'), folder.as_str()]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    // Taken before the file is moved into place, so another upload replacing it right away can't change it
+    let etag = uploaded_file.file.etag().await.ok();
+    let stored = match resolve_target(config, username, owner, group, &dir, &filename, AclPermission::Write).await {
+        Ok(target) => store_upload(config, file_service, target, uploaded_file.file, policy).await,
+        Err(response) => Err(response)
+    };
+    match stored {
+        Ok(stored_path) => {
+            let stored_name = stored_path.file_name().unwrap_or_default().to_string_lossy();
+            result.stored_as = Some(format!("{}/{}", dir, stored_name).trim_start_matches('/').to_string());
+            result.etag = etag;
+        },
+        Err(response) => {
+            result.status = response.status().as_u16();
+            result.error = to_bytes(response.into_body())
+                .await
+                .ok()
+                .map(|body| String::from_utf8_lossy(&body).to_string());
+        }
+    }
+    result
+}
+
+/// The conflict policy of an upload, `overwrite` if none is given
+fn conflict_policy(mode: Option<&str>, if_match: Option<&str>) -> Result<ConflictPolicy, (u16, String)> {
+    let Some(mode) = mode.map(str::trim).filter(|mode| !mode.is_empty()) else {
+        return Ok(ConflictPolicy::Overwrite);
+    };
+    match ConflictPolicy::parse(mode, if_match) {
+        Some(policy) => Ok(policy),
+        None if mode == "if-match" => Err((428, "The conflict mode 'if-match' requires an If-Match header".to_string())),
+        None => Err((400, format!("Unknown conflict mode '{}', expected overwrite, keep-both, reject or if-match", mode)))
+    }
+}
+
+/// Moves a staged upload to its target, with the same checks for every way of uploading.
+/// Returns where the file was stored.
+pub(crate) async fn store_upload(
+    config: &AppConfig,
+    file_service: file_service::FileService,
+    target: FileTarget,
+    staged_file: StagedFile,
+    policy: &ConflictPolicy
+) -> Result<PathBuf, HttpResponse> {
+    let username = target.tree;
+    // The path is joined below the tree as it is, so it must not climb out of it
+    let path = match normalize_acl_path(&target.path) {
+        Ok(path) => path,
+        Err((code, msg)) => return Err(HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg))
+    };
+    let filename = target.name;
+    if is_reserved_path(&format!("{}/{}", path, filename)) {
+        return Err(HttpResponse::BadRequest().body(format!("'{}' is reserved", SHARED_WITH_ME)));
+    }
+    let file_service = file_service.with_acl(target.acl);
+    if let Err((code, msg)) = file_service
+        .check_access(&username, &format!("{}/{}", path, filename), AclPermission::Write)
+        .await
+    {
+        return Err(HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg));
+    }
+
+    // Now we can safely move the file into place
+    let abs_path = Path::new(config.root_dir.as_ref())
+        .join(&username)
+        .join(path.trim_start_matches('/'))
+        .join(&filename);
+
+    // Create all necessary directories if not present
+    if let Some(parent) = abs_path.parent() {
+        if let Err(e) = tokio::fs::create_dir_all(parent).await {
+            error!("Failed to create directories: {}", e);
+            return Err(HttpResponse::InternalServerError()
+                .body("Failed to create target directory"));
+        }
+    }
+
+    // Save the file
+    match file_service
+        .commit_staged_upload(staged_file, &abs_path, policy)
+        .await
+    {
+        Ok(stored_path) => {
+            // The file now at the path of a shared one replaces it, the shares stay with it
+            if stored_path == abs_path {
+                refresh_shared_file(config, &username, &format!("{}/{}", path, filename)).await;
+            }
+            Ok(stored_path)
+        },
+        Err((code, msg)) => {
+            error!("{}", msg);
+            Err(HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg))
+        }
+    }
+}
+
+/// The most a text field of an upload form may hold
+const MAX_TEXT_FIELD_SIZE: usize = 4 * 1024;
+
+/// Reads the fields of an upload form. The file is streamed to the staging folder, so the fields
+/// can come in any order. An interrupted request fails and leaves nothing behind, as does one
+/// with more than `max_files` files or more than `max_total_size` bytes in all files together.
+/// It is stopped as soon as it exceeds them. Unless `targeted`, the form can't say where the files
+/// go, a `path`, `owner` or `group` field is refused.
+pub(crate) async fn read_upload_form(
+    payload: &mut Multipart,
+    file_service: &file_service::FileService,
+    max_total_size: Option<u64>,
+    max_files: Option<usize>,
+    targeted: bool
+) -> Result<UploadRequestData, HttpResponse> {
     // We'll store all fields in this struct while iterating,
     // then process them after the loop to avoid ordering issues.
     let mut data = UploadRequestData {
-        filename: None,
-        file_bytes: None,
+        files: Vec::new(),
         path: None,
+        owner: None,
+        group: None,
+        conflict: None,
     };
 
     // Iterate over multipart fields
-    while let Ok(Some(mut field)) = payload.try_next().await {
+    loop {
+        let mut field = match payload.try_next().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => {
+                error!("Failed to read the upload: {}", e);
+                return Err(HttpResponse::BadRequest().body("The upload was interrupted"));
+            }
+        };
         let content_disposition = field.content_disposition();
 
         // Get the field name
@@ -44,43 +353,58 @@ pub async fn upload_file_from_user_directory(
         };
 
         match field_name.as_str() {
+            "path" | "owner" | "group" if !targeted => {
+                return Err(HttpResponse::BadRequest().body(format!("The upload can't have a {}", field_name)));
+            }
+
             "path" => {
-                // Read the entire path field into a string
-                let mut path_bytes = Vec::new();
-                while let Ok(Some(chunk)) = field.try_next().await {
-                    path_bytes.extend_from_slice(&chunk);
+                let cleaned = match read_text_field(&mut field, &field_name).await {
+                    Ok(value) => value,
+                    Err((code, msg)) => return Err(HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg))
+                };
+                if let Err((code, msg)) = normalize_acl_path(&cleaned) {
+                    return Err(HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg));
                 }
-                match String::from_utf8(path_bytes) {
-                    Ok(path_str) => {
-                        let cleaned = path_str.trim().to_string();
-                        data.path = Some(cleaned);
-                    }
-                    Err(e) => {
-                        error!("Failed to parse path as UTF-8: {:?}", e);
-                        return HttpResponse::BadRequest().body("Invalid path encoding");
-                    }
+                data.path = Some(cleaned);
+            }
+
+            "owner" | "group" | "conflict" => {
+                let value = match read_text_field(&mut field, &field_name).await {
+                    Ok(value) => value,
+                    Err((code, msg)) => return Err(HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg))
+                };
+                match field_name.as_str() {
+                    "owner" => data.owner = Some(value),
+                    "group" => data.group = Some(value),
+                    _ => data.conflict = Some(value),
                 }
             }
 
             "file" => {
-                // We have a file: read its entire content into memory
-                // and store the sanitized filename
+                // We have a file: stream its content to the staging folder. There can be any
+                // number of files, their names are sanitized when they are stored.
                 if let Some(filename) = content_disposition.and_then(|cd| cd.get_filename()) {
-                    // Sanitize the filename
-                    let sanitized_filename = file_service.sanitize_filename(&filename);
-
-                    // Read file bytes
-                    let mut file_bytes = Vec::new();
-                    while let Ok(Some(chunk)) = field.try_next().await {
-                        file_bytes.extend_from_slice(&chunk);
+                    let name = filename.to_string();
+                    if max_files.is_some_and(|max_files| data.files.len() >= max_files) {
+                        return Err(HttpResponse::PayloadTooLarge().body("The upload contains too many files"));
                     }
 
+                    // Each file may use what the files before it left of the budget
+                    let staged_size = data.files.iter().map(|file| file.file.size).sum::<u64>();
+                    let space_left = max_total_size.map(|max_total_size| max_total_size.saturating_sub(staged_size));
+                    let staged_file = match file_service.stage_upload(&mut field, space_left).await {
+                        Ok(staged_file) => staged_file,
+                        Err((code, msg)) => {
+                            error!("{}", msg);
+                            return Err(HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg));
+                        }
+                    };
+
                     // Store in our data struct
-                    data.filename = Some(sanitized_filename);
-                    data.file_bytes = Some(file_bytes);
+                    data.files.push(UploadedFile { name, file: staged_file });
                 } else {
                     error!("File field without filename");
-                    return HttpResponse::BadRequest().body("File field missing filename");
+                    return Err(HttpResponse::BadRequest().body("File field missing filename"));
                 }
             }
 
@@ -94,56 +418,32 @@ pub async fn upload_file_from_user_directory(
         }
     }
 
-    // Now that we've read all fields, check if we have both the file and path
-    let filename = match data.filename {
-        Some(f) => f,
-        None => {
-            return HttpResponse::BadRequest().body("No file was provided in the request");
-        }
-    };
+    Ok(data)
+}
 
-    let file_bytes = match data.file_bytes {
-        Some(bytes) => bytes,
-        None => {
-            return HttpResponse::BadRequest().body("File bytes were not captured");
-        }
-    };
-
-    let path = match data.path {
-        Some(p) => p,
-        None => {
-            return HttpResponse::BadRequest().body("No path was provided in the request");
-        }
-    };
-
-    // Now we can safely write the file to disk
-    let abs_path = Path::new(config.root_dir.as_ref())
-        .join(&username)
-        .join(path.trim_start_matches('/'))
-        .join(&filename);
-
-    // Create all necessary directories if not present
-    if let Some(parent) = abs_path.parent() {
-        if let Err(e) = tokio::fs::create_dir_all(parent).await {
-            error!("Failed to create directories: {}", e);
-            return HttpResponse::InternalServerError()
-                .body("Failed to create target directory");
-        }
-    }
-
-    // Save the file
-    match file_service
-        .save_file_bytes_to_root_directory(&abs_path, &file_bytes)
-        .await
-    {
-        Ok(success) => {
-            info!("{}", success);
-        }
-        Err((code, msg)) => {
-            error!("{}", msg);
-            return HttpResponse::build(StatusCode::from_u16(code).unwrap()).body(msg);
+/// Reads a text field of an upload form, trimmed. A broken stream or a value over
+/// `MAX_TEXT_FIELD_SIZE` fails the upload.
+async fn read_text_field(field: &mut Field, field_name: &str) -> Result<String, (u16, String)> {
+    let mut bytes = Vec::new();
+    loop {
+        match field.try_next().await {
+            Ok(Some(chunk)) => {
+                if bytes.len() + chunk.len() > MAX_TEXT_FIELD_SIZE {
+                    return Err((413, format!("The {} is too long", field_name)));
+                }
+                bytes.extend_from_slice(&chunk);
+            }
+            Ok(None) => break,
+            Err(e) => {
+                error!("Failed to read the {}: {}", field_name, e);
+                return Err((400, "The upload was interrupted".to_string()));
+            }
         }
     }
-
-    HttpResponse::Ok().body("File uploaded successfully")
-}
\ No newline at end of file
+    String::from_utf8(bytes)
+        .map(|value| value.trim().to_string())
+        .map_err(|e| {
+            error!("Failed to parse {} as UTF-8: {:?}", field_name, e);
+            (400, format!("Invalid {} encoding", field_name))
+        })
+}
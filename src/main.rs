@@ -4,15 +4,46 @@ use actix_web::{middleware::Logger, web, App, HttpServer};
 use dotenv::dotenv;
 use models::authentication;
 use std::sync::Arc;
+use std::time::Duration;
 use crate::app_config::AppConfig;
 extern crate env_logger;
-use crate::endpoints::authentication::authentication::{login_handler, protected_resource_handler};
+use crate::endpoints::authentication::account::{change_password_handler, delete_account_handler, register_handler, set_registration_handler};
+use crate::endpoints::authentication::api_keys::{create_api_key_handler, list_api_keys_handler, revoke_api_key_handler};
+use crate::endpoints::authentication::jwks::jwks_handler;
+use crate::endpoints::authentication::oidc::{oidc_callback_handler, oidc_start_handler};
+use crate::endpoints::authentication::mfa::{mfa_confirm_handler, mfa_disable_handler, mfa_enroll_handler, mfa_login_enroll_handler, mfa_login_handler};
+use crate::endpoints::authentication::user_administration::{change_role_handler, create_user_handler, list_users_handler, reset_password_handler, set_disabled_handler};
+use crate::endpoints::authentication::authentication::{login_handler, logout_handler, protected_resource_handler, refresh_token_handler, scoped_token_handler};
+use crate::endpoints::system_operations::acl::{remove_acl_entry_handler, set_acl_entry_handler, view_acl_handler};
 use crate::endpoints::system_operations::delete::{delete_file, delete_user_directory};
 use crate::endpoints::system_operations::directory::create_directory;
+use crate::endpoints::system_operations::groups::{create_group_handler, delete_group_handler, list_groups_handler, list_members_handler, remove_member_handler, set_member_handler};
 use crate::endpoints::system_operations::download::{download_directory_from_user_directory, download_file_from_user_directory};
 use crate::endpoints::system_operations::get_file_structure::get_user_directory;
 use crate::endpoints::system_operations::rename::rename_directory;
-use crate::endpoints::system_operations::upload::{upload_file_from_user_directory};
+use crate::endpoints::system_operations::share_links::{create_file_drop_handler, create_share_link_handler, file_drop_form_handler, file_drop_upload_handler, list_share_links_handler, open_share_link_handler, revoke_share_link_handler, unlock_share_link_handler};
+use crate::endpoints::system_operations::shares::{create_share_handler, list_incoming_shares_handler, list_shares_handler, revoke_share_handler};
+use crate::endpoints::system_operations::tus::{tus_append_handler, tus_create_handler, tus_offset_handler, tus_options_handler, tus_terminate_handler};
+use crate::endpoints::system_operations::upload::{upload_archive_handler, upload_file_from_user_directory};
+use crate::dao::credential_store::CredentialStore;
+use crate::dao::db_acl_store::DbAclStore;
+use crate::dao::db_share_store::DbShareStore;
+use crate::dao::db_share_link_store::DbShareLinkStore;
+use crate::dao::db_api_key_store::DbApiKeyStore;
+use crate::dao::db_credential_store::DbCredentialStore;
+use crate::dao::db_privilege_store::DbPrivilegeStore;
+use crate::dao::ldap_credential_store::LdapCredentialStore;
+use crate::dao::db_token_revocation_store::DbTokenRevocationStore;
+use crate::services::authentication::api_key_service::ApiKeyService;
+use crate::services::authentication::authentication_service::AuthenticationService;
+use crate::services::authentication::ldap_settings::LdapSettings;
+use crate::services::authentication::login_throttle::LoginThrottle;
+use crate::services::authentication::mfa_service::MfaPolicy;
+use crate::services::authentication::oidc_client::{OidcClient, OidcSettings};
+use crate::services::authentication::token_revocation_service::TokenRevocationService;
+use crate::services::file_structure::archive_service::ArchiveLimits;
+use crate::services::file_structure::file_service::FileService;
+use crate::services::file_structure::tus_service::TusSettings;
 use crate::services::locking::directory_locking_manager::DirectoryLockManager;
 
 static ROOT_DIR: &str = "./root";
@@ -31,9 +62,35 @@ async fn main() -> std::io::Result<()> {
     dotenv().ok();
     let root_dir = std::env::var("ROOT_DIR").unwrap_or_else(|_| "./root".to_string());
     let lock_manager = DirectoryLockManager::new();
+    FileService::new(root_dir.clone(), lock_manager.clone()).remove_orphaned_staged_files().await;
+    let token_revocation_service = TokenRevocationService::new(Arc::new(DbTokenRevocationStore));
+    token_revocation_service.reload().await.expect("Failed to load revoked tokens");
+    token_revocation_service.spawn_periodic_reload(Duration::from_secs(60));
+    let authentication_service = AuthenticationService::from_env(token_revocation_service)
+        .with_api_key_service(ApiKeyService::new(Arc::new(DbApiKeyStore)))
+        .with_privilege_store(Arc::new(DbPrivilegeStore));
+    let key_reload_interval = std::env::var("JWT_KEYS_RELOAD_SECONDS")
+        .map(|v| v.parse().expect("JWT_KEYS_RELOAD_SECONDS must be a valid integer"))
+        .unwrap_or(300);
+    authentication_service.spawn_periodic_key_reload(Duration::from_secs(key_reload_interval));
+    let credential_store: Arc<dyn CredentialStore> = match std::env::var("AUTH_BACKEND").as_deref() {
+        Ok("ldap") => Arc::new(LdapCredentialStore::new(LdapSettings::from_env())),
+        Ok("postgres") | Err(_) => Arc::new(DbCredentialStore),
+        Ok(other) => panic!("Unknown AUTH_BACKEND '{}', expected 'postgres' or 'ldap'", other),
+    };
     let config = AppConfig { 
         root_dir: Arc::new(root_dir),
-        directory_lock_manager: lock_manager
+        directory_lock_manager: lock_manager,
+        authentication_service,
+        credential_store,
+        acl_store: Arc::new(DbAclStore),
+        share_store: Arc::new(DbShareStore),
+        share_link_store: Arc::new(DbShareLinkStore),
+        login_throttle: LoginThrottle::default(),
+        mfa_policy: MfaPolicy::from_env(),
+        tus_settings: TusSettings::from_env(),
+        archive_limits: ArchiveLimits::from_env(),
+        oidc_client: OidcSettings::from_env().map(OidcClient::new)
     };
 
     println!("Server running on http://0.0.0.0:8080");
@@ -42,11 +99,14 @@ async fn main() -> std::io::Result<()> {
         // Configure CORS
         let cors = Cors::default()
             .allowed_origin("http://localhost:5173") // Allow only this origin
-            .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"]) // Allowed HTTP methods
+            .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD"]) // Allowed HTTP methods
             .allowed_headers(vec![
                 actix_web::http::header::CONTENT_TYPE,
                 actix_web::http::header::AUTHORIZATION,
             ]) // Allow specific headers
+            // Used by resumable uploads
+            .allowed_headers(vec!["Tus-Resumable", "Upload-Length", "Upload-Metadata", "Upload-Offset"])
+            .expose_headers(vec!["Location", "Tus-Resumable", "Tus-Version", "Tus-Extension", "Tus-Max-Size", "Upload-Offset", "Upload-Length", "Upload-Expires"])
             .supports_credentials(); // Allow cookies or authorization headers
 
         App::new()
@@ -54,18 +114,83 @@ async fn main() -> std::io::Result<()> {
             .wrap(Logger::default())
             .wrap(cors) // Add the CORS middleware
             .service(login_handler)
+            .service(refresh_token_handler)
+            .service(mfa_login_handler)
+            .service(mfa_login_enroll_handler)
+            .service(jwks_handler)
+            .service(register_handler)
+            .service(oidc_start_handler)
+            .service(oidc_callback_handler)
+            .service(open_share_link_handler)
+            .service(unlock_share_link_handler)
+            .service(file_drop_form_handler)
+            .service(file_drop_upload_handler)
             .service(
                 web::scope("/api")
                     .wrap(authentication::auth_models::JwtAuth)
                     .service(web::resource("/protected").route(web::get().to(protected_resource_handler)))
+                    .service(logout_handler)
+                    .service(scoped_token_handler)
+                    .service(change_password_handler)
+                    .service(delete_account_handler)
+                    .service(mfa_enroll_handler)
+                    .service(mfa_confirm_handler)
+                    .service(mfa_disable_handler)
+                    .service(create_api_key_handler)
+                    .service(list_api_keys_handler)
+                    .service(revoke_api_key_handler)
+                    .service(set_registration_handler)
+                    .service(
+                        web::scope("/admin/users")
+                            .service(list_users_handler)
+                            .service(create_user_handler)
+                            .service(reset_password_handler)
+                            .service(change_role_handler)
+                            .service(set_disabled_handler)
+                    )
+                    .service(
+                        web::scope("/shares")
+                            .service(list_incoming_shares_handler)
+                            .service(list_shares_handler)
+                            .service(create_share_handler)
+                            .service(revoke_share_handler)
+                    )
+                    .service(
+                        web::scope("/share/link")
+                            .service(list_share_links_handler)
+                            .service(create_share_link_handler)
+                            .service(revoke_share_link_handler)
+                    )
+                    .service(web::scope("/share/drop").service(create_file_drop_handler))
+                    .service(
+                        web::scope("/tus")
+                            .service(tus_options_handler)
+                            .service(tus_create_handler)
+                            .service(tus_offset_handler)
+                            .service(tus_append_handler)
+                            .service(tus_terminate_handler)
+                    )
+                    .service(
+                        web::scope("/groups")
+                            .service(list_groups_handler)
+                            .service(create_group_handler)
+                            .service(delete_group_handler)
+                            .service(list_members_handler)
+                            .service(set_member_handler)
+                            .service(remove_member_handler)
+                    )
                     .service(download_file_from_user_directory)
                     .service(upload_file_from_user_directory)
+                    .service(upload_archive_handler)
                     .service(get_user_directory)
                     .service(delete_user_directory)
                     .service(delete_file)
                     .service(rename_directory)
                     .service(create_directory)
-                    .service(download_directory_from_user_directory),
+                    .service(download_directory_from_user_directory)
+                    .service(view_acl_handler)
+                    .service(set_acl_entry_handler)
+                    .service(remove_acl_entry_handler),
             )
     })
         .bind(("0.0.0.0", 8080))?
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+/// What an ACL entry allows on a path. Unlike group access the permissions are independent of each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AclPermission {
+    /// Browse and download
+    Read,
+    /// Upload, create and rename
+    Write,
+    Delete,
+    /// View and edit the ACL of the path
+    Share,
+}
+
+impl AclPermission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AclPermission::Read => "read",
+            AclPermission::Write => "write",
+            AclPermission::Delete => "delete",
+            AclPermission::Share => "share",
+        }
+    }
+}
+
+/// Who an entry applies to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "name", rename_all = "lowercase")]
+pub enum AclPrincipal {
+    User(String),
+    /// Every member of the group
+    Group(String),
+}
+
+impl AclPrincipal {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AclPrincipal::User(_) => "user",
+            AclPrincipal::Group(_) => "group",
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            AclPrincipal::User(name) | AclPrincipal::Group(name) => name,
+        }
+    }
+
+    pub fn parse(kind: &str, name: String) -> Option<Self> {
+        match kind {
+            "user" => Some(AclPrincipal::User(name)),
+            "group" => Some(AclPrincipal::Group(name)),
+            _ => None,
+        }
+    }
+}
+
+/// Grants permissions on a path of a tree. With `inherit` the entry applies to
+/// everything below the path as well.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AclEntry {
+    /// Relative to the tree, the empty path is the tree itself
+    pub path: String,
+    pub principal: AclPrincipal,
+    pub permissions: Vec<AclPermission>,
+    pub inherit: bool,
+}
+
+impl AclEntry {
+    pub fn allows(&self, permission: AclPermission) -> bool {
+        self.permissions.contains(&permission)
+    }
+}
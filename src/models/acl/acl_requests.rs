@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use crate::models::acl::acl_entry::{AclEntry, AclPermission, AclPrincipal};
+
+/// Addresses a path like the file requests do
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AclPathRequest {
+    pub path: String,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetAclEntryRequest {
+    pub path: String,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    pub principal: AclPrincipal,
+    pub permissions: Vec<AclPermission>,
+    #[serde(default = "default_inherit")]
+    pub inherit: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RemoveAclEntryRequest {
+    pub path: String,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    pub principal: AclPrincipal,
+}
+
+/// The ACL of a path: its own entries and the ones inherited from the paths above it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AclView {
+    pub path: String,
+    pub entries: Vec<AclEntry>,
+    pub inherited: Vec<AclEntry>,
+}
+
+fn default_inherit() -> bool {
+    true
+}
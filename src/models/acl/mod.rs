@@ -0,0 +1,2 @@
+pub mod acl_entry;
+pub mod acl_requests;
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// An API key as listed to its owner. The key itself is only stored hashed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiKeyRecord {
+    pub id: String,
+    pub username: String,
+    pub name: String,
+    /// First characters of the key, so the owner can tell their keys apart
+    pub key_prefix: String,
+    pub read_only: bool,
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+    pub revoked: bool,
+}
+
+/// An API key together with the current state of its owner
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKeyIdentity {
+    pub key: ApiKeyRecord,
+    pub role: String,
+    pub privilege: i32,
+    pub user_disabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    /// Unix timestamp, the key never expires if omitted
+    pub expires_at: Option<i64>,
+    #[serde(default)]
+    pub read_only: bool,
+}
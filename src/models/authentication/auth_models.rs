@@ -1,16 +1,17 @@
 use actix_service::{Service, Transform};
 use actix_web::dev::{ServiceRequest, ServiceResponse};
-use actix_web::HttpMessage;
+use actix_web::{web, HttpMessage};
 use std::future::{ready, Future, Ready};
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::{Context, Poll};
-use crate::services::authentication::authentication_service::validate_jwt_token;
+use crate::app_config::AppConfig;
 
 pub(crate) struct JwtAuth;
 
 impl<S, B> Transform<S, ServiceRequest> for JwtAuth
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
     S::Future: 'static,
 {
     type Response = ServiceResponse<B>;
@@ -20,17 +21,17 @@ where
     type Future = Ready<Result<Self::Transform, Self::InitError>>;
 
     fn new_transform(&self, service: S) -> Self::Future {
-        ready(Ok(JwtAuthMiddleware { service }))
+        ready(Ok(JwtAuthMiddleware { service: Rc::new(service) }))
     }
 }
 
 pub struct JwtAuthMiddleware<S> {
-    service: S,
+    service: Rc<S>,
 }
 
 impl<S, B> Service<ServiceRequest> for JwtAuthMiddleware<S>
 where
-    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
     S::Future: 'static,
 {
     type Response = ServiceResponse<B>;
@@ -42,17 +43,23 @@ where
     }
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        if let Some(header) = req.headers().get("Authorization") {
-            if let Ok(auth_str) = header.to_str() {
-                if auth_str.starts_with("Bearer ") {
-                    let token = auth_str.trim_start_matches("Bearer ");
-                    if let Ok(claims) = validate_jwt_token(token) {
-                        req.extensions_mut().insert(claims);
-                        return Box::pin(self.service.call(req));
-                    }
+        let service = Rc::clone(&self.service);
+        let config = req.app_data::<web::Data<AppConfig>>().cloned();
+
+        Box::pin(async move {
+            let result = match &config {
+                Some(config) => config.authentication_service.authenticate_request(req.headers()).await,
+                None => Err("Authentication is not configured".to_string()),
+            };
+
+            match result {
+                Ok(claims) => {
+                    // Handlers extracting `AuthenticatedUser` reuse these claims
+                    req.extensions_mut().insert(claims);
+                    service.call(req).await
                 }
+                Err(e) => Err(actix_web::error::ErrorUnauthorized(e)),
             }
-        }
-        Box::pin(async { Err(actix_web::error::ErrorUnauthorized("Invalid token")) })
+        })
     }
-}
\ No newline at end of file
+}
@@ -3,71 +3,122 @@
 use crate::services::authentication::authentication_service::Claims;
 use actix_web::{
     dev::Payload,
-    http::header::HeaderValue,
     Error,
     FromRequest,
+    HttpMessage,
     HttpRequest,
+    web,
 };
-use futures::future::{ready, Ready};
-use jsonwebtoken::{decode, DecodingKey, Validation};
-use std::env;
+use futures::future::{ready, LocalBoxFuture};
+use futures::FutureExt;
+use std::marker::PhantomData;
+use crate::app_config::AppConfig;
+use crate::models::authentication::scope::{RequiredScope, ADMIN};
+
+/// Privilege level of the admin role
+pub const ADMIN_PRIVILEGE: i32 = 999;
 
 /// Wrapper for claims extracted from a valid token.
 pub struct AuthenticatedUser(pub Claims);
 
-// If you want custom error types, you can define them here, but let's keep it simple:
+impl AuthenticatedUser {
+    pub fn username(&self) -> &str {
+        &self.0.sub
+    }
+
+    pub fn role(&self) -> &str {
+        &self.0.role
+    }
+
+    pub fn privilege(&self) -> i32 {
+        self.0.privilege
+    }
+}
+
 impl FromRequest for AuthenticatedUser {
     type Error = Error;
-    type Future = Ready<Result<Self, Self::Error>>;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
 
     fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
-        // 1) Extract the "Authorization" header
-        let token = match req.headers().get("Authorization") {
-            Some(hv) => hv,
-            None => {
-                return ready(Err(actix_web::error::ErrorUnauthorized(
-                    "Missing Authorization header",
-                )));
-            }
-        };
+        // Behind the `JwtAuth` middleware the token has already been validated
+        if let Some(claims) = req.extensions().get::<Claims>() {
+            return ready(Ok(AuthenticatedUser(claims.clone()))).boxed_local();
+        }
 
-        // 2) Parse "Bearer <token>"
-        let token_str = match parse_bearer_token(token) {
-            Ok(t) => t,
-            Err(e) => {
-                return ready(Err(actix_web::error::ErrorUnauthorized(e)));
-            }
-        };
+        let config = req.app_data::<web::Data<AppConfig>>().cloned();
+        let headers = req.headers().clone();
+        async move {
+            let result = match config {
+                Some(config) => config.authentication_service.authenticate_request(&headers).await,
+                None => Err("Authentication is not configured".to_string()),
+            };
 
-        // 3) Decode + validate JWT.
-        // You should store/use a real secret or public key for production.
-        let secret = env::var("JWT_TOKEN_SECRET").expect("JWT_TOKEN_SECRET must be set");
-        let validation = Validation::default();
+            result
+                .map(AuthenticatedUser)
+                .map_err(actix_web::error::ErrorUnauthorized)
+        }.boxed_local()
+    }
+}
 
-        match decode::<Claims>(&token_str, &DecodingKey::from_secret(secret.as_ref()), &validation) {
-            Ok(decoded) => {
-                let claims = decoded.claims;
-                ready(Ok(AuthenticatedUser(claims)))
-            }
-            Err(err) => {
-                ready(Err(actix_web::error::ErrorUnauthorized(
-                    format!("Invalid token: {}", err),
-                )))
-            }
-        }
+/// An authenticated user whose token grants the scope `S`.
+/// Used as an extractor, it rejects every other token with 403, e.g.:
+/// `async fn handler(user: ScopedUser<FilesRead>) -> impl Responder`
+pub struct ScopedUser<S: RequiredScope>(pub AuthenticatedUser, PhantomData<S>);
+
+impl<S: RequiredScope> ScopedUser<S> {
+    pub fn username(&self) -> &str {
+        self.0.username()
     }
 }
 
-fn parse_bearer_token(hv: &HeaderValue) -> Result<String, String> {
-    let value = hv
-        .to_str()
-        .map_err(|_| "Invalid Authorization header".to_string())?;
-    if !value.starts_with("Bearer ") {
-        return Err("Expected 'Bearer <token>'".to_string());
+impl<S: RequiredScope + 'static> FromRequest for ScopedUser<S> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = AuthenticatedUser::from_request(req, payload);
+
+        async move {
+            let user = user.await?;
+            if !user.0.has_scope(S::SCOPE) {
+                return Err(actix_web::error::ErrorForbidden(format!(
+                    "The token does not have the '{}' scope",
+                    S::SCOPE
+                )));
+            }
+
+            Ok(ScopedUser(user, PhantomData))
+        }.boxed_local()
     }
-    let token = value.trim_start_matches("Bearer ").trim().to_string();
-    if token.is_empty() {
-        return Err("No token after 'Bearer '".to_string());
+}
+
+/// An authenticated user whose privilege level is at least `LEVEL`.
+/// Used as an extractor, it rejects everyone else with 403, e.g.:
+/// `async fn handler(admin: RequirePrivilege<ADMIN_PRIVILEGE>) -> impl Responder`
+///
+/// The token needs the `admin` scope as well, so a restricted token of an admin
+/// doesn't carry their privileges along.
+pub struct RequirePrivilege<const LEVEL: i32>(pub AuthenticatedUser);
+
+pub type AdminUser = RequirePrivilege<ADMIN_PRIVILEGE>;
+
+impl<const LEVEL: i32> FromRequest for RequirePrivilege<LEVEL> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let user = AuthenticatedUser::from_request(req, payload);
+
+        async move {
+            let user = user.await?;
+            if user.privilege() < LEVEL || !user.0.has_scope(ADMIN) {
+                return Err(actix_web::error::ErrorForbidden(format!(
+                    "Your role '{}' does not have the required privileges",
+                    user.role()
+                )));
+            }
+
+            Ok(RequirePrivilege(user))
+        }.boxed_local()
     }
-    Ok(token)
-}
\ No newline at end of file
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LogoutRequest {
+    /// The refresh token of the session, revoked together with the access token
+    pub refresh_token: Option<String>,
+    /// Revokes every token of the user, e.g. when a device is lost
+    pub all_sessions: Option<bool>,
+}
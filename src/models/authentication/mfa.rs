@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+/// TOTP settings of a user as stored in the `users` table
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MfaState {
+    /// Base32 secret, set as soon as the enrollment starts
+    pub totp_secret: Option<String>,
+    /// Only set once the user proved they can generate codes with the secret
+    pub enabled: bool,
+    /// The last time step a code was accepted for, so a code can't be replayed
+    pub last_used_step: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaLoginRequest {
+    pub mfa_token: String,
+    pub code: Option<String>,
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaEnrollRequest {
+    pub mfa_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaEnrollment {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
@@ -1,2 +1,12 @@
 pub mod auth_models;
-pub mod auth_user;
\ No newline at end of file
+pub mod auth_user;
+pub mod refresh_token;
+pub mod logout_request;
+pub mod account_requests;
+pub mod user_administration;
+pub mod mfa;
+pub mod api_key;
+pub mod scope;
+pub mod scoped_token_request;
+pub mod verified_user;
+pub mod oidc_callback;
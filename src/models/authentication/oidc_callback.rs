@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+/// Query parameters the provider appends to the redirect URI
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// A refresh token as persisted in the `refresh_tokens` table.
+/// Only the SHA-256 hash of the token is stored, never the token itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefreshTokenRecord {
+    pub token_hash: String,
+    /// All tokens produced by rotating the same login share a family id,
+    /// so the whole chain can be revoked at once when reuse is detected.
+    pub family_id: String,
+    pub username: String,
+    /// Expiration as a unix timestamp in seconds
+    pub expires_at: i64,
+    pub used: bool,
+    pub revoked: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
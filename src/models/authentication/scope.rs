@@ -0,0 +1,47 @@
+/// Download files and read the directory structure
+pub const FILES_READ: &str = "files:read";
+/// Upload files, create and rename directories
+pub const FILES_WRITE: &str = "files:write";
+/// Delete files and directories
+pub const FILES_DELETE: &str = "files:delete";
+/// Manage the own account: password, MFA, API keys and scoped tokens
+pub const ACCOUNT: &str = "account";
+/// Use the admin endpoints, only granted to users with the admin privilege
+pub const ADMIN: &str = "admin";
+
+pub const ALL_SCOPES: &[&str] = &[FILES_READ, FILES_WRITE, FILES_DELETE, ACCOUNT, ADMIN];
+
+/// The scopes of a regular login, i.e. everything the user is allowed to do
+pub fn default_scopes(is_admin: bool) -> Vec<String> {
+    ALL_SCOPES
+        .iter()
+        .filter(|scope| is_admin || **scope != ADMIN)
+        .map(|scope| scope.to_string())
+        .collect()
+}
+
+/// Marker types naming the scope an endpoint requires, used as `ScopedUser<FilesRead>`
+pub trait RequiredScope {
+    const SCOPE: &'static str;
+}
+
+pub struct FilesRead;
+pub struct FilesWrite;
+pub struct FilesDelete;
+pub struct Account;
+
+impl RequiredScope for FilesRead {
+    const SCOPE: &'static str = FILES_READ;
+}
+
+impl RequiredScope for FilesWrite {
+    const SCOPE: &'static str = FILES_WRITE;
+}
+
+impl RequiredScope for FilesDelete {
+    const SCOPE: &'static str = FILES_DELETE;
+}
+
+impl RequiredScope for Account {
+    const SCOPE: &'static str = ACCOUNT;
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScopedTokenRequest {
+    /// Must be a subset of the scopes of the token making the request
+    pub scopes: Vec<String>,
+    /// Lifetime in seconds, defaults to the lifetime of regular access tokens
+    pub ttl_seconds: Option<u64>,
+}
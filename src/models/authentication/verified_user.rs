@@ -0,0 +1,7 @@
+/// A user whose credentials were accepted by a `CredentialStore`
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifiedUser {
+    pub username: String,
+    /// Role assigned by an external directory. `None` when the role is managed in the `users` table.
+    pub role: Option<String>,
+}
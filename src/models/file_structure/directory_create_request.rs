@@ -3,5 +3,11 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DirectoryCreateRequest {
     pub path: String,
-    pub name: String
+    pub name: String,
+    /// User whose tree is addressed, defaults to the authenticated user
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Group whose folder is addressed instead of a user's
+    #[serde(default)]
+    pub group: Option<String>,
 }
\ No newline at end of file
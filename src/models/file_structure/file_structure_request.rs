@@ -2,5 +2,11 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Debug, Deserialize)]
 pub struct FileStructureRequest {
-    pub path: String
+    pub path: String,
+    /// User whose tree is addressed, defaults to the authenticated user
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Group whose folder is addressed instead of a user's
+    #[serde(default)]
+    pub group: Option<String>,
 }
\ No newline at end of file
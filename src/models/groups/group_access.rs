@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+/// What a member may do in a group folder. Each level includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupAccess {
+    /// Browse and download
+    Read,
+    /// Upload, create, rename and delete
+    Write,
+    /// Add and remove members
+    Manage,
+}
+
+impl GroupAccess {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupAccess::Read => "read",
+            GroupAccess::Write => "write",
+            GroupAccess::Manage => "manage",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "read" => Some(GroupAccess::Read),
+            "write" => Some(GroupAccess::Write),
+            "manage" => Some(GroupAccess::Manage),
+            _ => None,
+        }
+    }
+}
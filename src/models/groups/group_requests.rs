@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use crate::models::groups::group_access::GroupAccess;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupSummary {
+    pub name: String,
+    /// Access of the requesting user, `None` for admins who are not a member
+    pub access: Option<GroupAccess>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupMember {
+    pub username: String,
+    pub access: GroupAccess,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateGroupRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetMemberRequest {
+    pub username: String,
+    pub access: GroupAccess,
+}
@@ -0,0 +1,2 @@
+pub mod group_access;
+pub mod group_requests;
@@ -1,3 +1,6 @@
 pub mod authentication;
 pub mod file_structure;
-pub mod system_operations;
\ No newline at end of file
+pub mod system_operations;
+pub mod groups;
+pub mod acl;
+pub mod shares;
\ No newline at end of file
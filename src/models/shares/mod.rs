@@ -0,0 +1,4 @@
+pub mod share;
+pub mod share_requests;
+
+pub mod share_link;
\ No newline at end of file
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// A file or directory of the owner's tree shared with another user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Share {
+    pub id: i64,
+    pub owner: String,
+    pub recipient: String,
+    /// Name of the share under the recipient's `Shared with me` node
+    pub name: String,
+    /// The source, relative to the owner's tree
+    pub path: String,
+    /// Identity of the source, see `source_identity`. Unlike the path it can't be taken over by
+    /// another file or directory created at the same place.
+    #[serde(skip)]
+    pub file_id: String,
+    pub is_dir: bool,
+    /// Whether the recipient may upload into the share
+    pub allow_upload: bool,
+    pub created_at: i64,
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+/// What a link can be used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareLinkKind {
+    /// Downloads the file or directory
+    Download,
+    /// Only uploads into the directory, which can't be listed or downloaded through the link
+    Drop,
+}
+
+impl ShareLinkKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShareLinkKind::Download => "download",
+            ShareLinkKind::Drop => "drop",
+        }
+    }
+
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "download" => Some(ShareLinkKind::Download),
+            "drop" => Some(ShareLinkKind::Drop),
+            _ => None,
+        }
+    }
+}
+
+/// A public link to a file or directory of the owner's tree. The token of the link is only stored hashed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShareLink {
+    pub id: String,
+    pub kind: ShareLinkKind,
+    pub owner: String,
+    /// The source, relative to the owner's tree
+    pub path: String,
+    pub is_dir: bool,
+    pub expires_at: Option<i64>,
+    pub max_downloads: Option<i32>,
+    pub download_count: i32,
+    /// Limits the total size of the files uploaded into a file drop
+    pub max_bytes: Option<i64>,
+    /// Limits the number of files uploaded into a file drop
+    pub max_files: Option<i32>,
+    pub uploaded_bytes: i64,
+    pub upload_count: i32,
+    #[serde(skip)]
+    pub password_hash: Option<String>,
+    pub has_password: bool,
+    pub created_at: i64,
+    pub revoked: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateShareLinkRequest {
+    /// File or directory of the user's tree
+    pub path: String,
+    /// Unix timestamp, the link never expires if omitted
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    /// Visitors have to enter it before downloading
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub max_downloads: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateFileDropRequest {
+    /// Directory of the user's tree the files are uploaded to
+    pub path: String,
+    /// Unix timestamp, the file drop never expires if omitted
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    /// Total size of all uploaded files in bytes
+    #[serde(default)]
+    pub max_bytes: Option<i64>,
+    #[serde(default)]
+    pub max_files: Option<i32>,
+}
+
+/// Sent by the password form of a protected link
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShareLinkPasswordForm {
+    pub password: String,
+}
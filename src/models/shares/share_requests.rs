@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateShareRequest {
+    /// File or directory of the user's tree
+    pub path: String,
+    pub recipient: String,
+    /// Name shown to the recipient, defaults to the name of the file or directory
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub allow_upload: bool,
+}
@@ -0,0 +1,43 @@
+use serde::Deserialize;
+
+/// What a write does when a file of the same name already exists
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConflictPolicy {
+    /// Replaces the existing file
+    Overwrite,
+    /// Stores the new file under a numbered name like `photo (1).jpg`
+    KeepBoth,
+    /// Fails with `409`
+    Reject,
+    /// Replaces the existing file only if it still has one of these ETags, `*` matching any file.
+    /// Fails with `412` otherwise.
+    IfMatch(Vec<String>),
+}
+
+impl ConflictPolicy {
+    /// Parses the `conflict` mode of a request. `if-match` takes the ETags of an `If-Match` header.
+    pub fn parse(mode: &str, if_match: Option<&str>) -> Option<Self> {
+        match mode {
+            "overwrite" => Some(ConflictPolicy::Overwrite),
+            "keep-both" => Some(ConflictPolicy::KeepBoth),
+            "reject" => Some(ConflictPolicy::Reject),
+            "if-match" => {
+                let etags = if_match?
+                    .split(',')
+                    .map(|etag| etag.trim().to_string())
+                    .filter(|etag| !etag.is_empty())
+                    .collect::<Vec<_>>();
+                (!etags.is_empty()).then_some(ConflictPolicy::IfMatch(etags))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Query parameters of an upload
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    /// `overwrite` (default), `keep-both`, `reject` or `if-match`
+    #[serde(default)]
+    pub conflict: Option<String>,
+}
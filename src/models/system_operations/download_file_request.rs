@@ -4,4 +4,10 @@ use serde::{Deserialize, Serialize};
 pub struct DownloadEntityRequest {
     pub path: String,
     pub name: String,
+    /// User whose tree is addressed, defaults to the authenticated user
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Group whose folder is addressed instead of a user's
+    #[serde(default)]
+    pub group: Option<String>,
 }
\ No newline at end of file
@@ -1,4 +1,6 @@
 pub mod delete_file_request;
 pub mod download_file_request;
 pub mod upload_file_request;
-pub mod rename_item_request;
\ No newline at end of file
+pub mod rename_item_request;
+pub mod tus_upload;
+pub mod conflict_policy;
\ No newline at end of file
@@ -4,5 +4,11 @@ use serde::{Deserialize, Serialize};
 pub struct RenameItemRequest {
     pub path: String,
     pub old_name: String,
-    pub new_name: String
+    pub new_name: String,
+    /// User whose tree is addressed, defaults to the authenticated user
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Group whose folder is addressed instead of a user's
+    #[serde(default)]
+    pub group: Option<String>,
 }
\ No newline at end of file
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+/// A resumable upload, kept next to its data in the staging folder until it is complete.
+/// Where it goes is resolved like the fields of a regular upload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TusUpload {
+    pub id: String,
+    /// The user who created the upload, the only one who can continue it
+    pub username: String,
+    /// Total size announced with `Upload-Length`
+    pub length: u64,
+    pub path: String,
+    /// Sanitized like the name of a regular upload
+    pub filename: String,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use crate::services::file_structure::file_service::StagedFile;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UploadFileRequest {
@@ -6,12 +7,42 @@ pub struct UploadFileRequest {
     pub path: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug)]
 pub struct UploadRequestData {
-    /// The final, sanitized filename.
-    pub filename: Option<String>,
-    /// The full file bytes read from the request.
-    pub file_bytes: Option<Vec<u8>>,
+    /// The files in the order they were sent, streamed to the staging folder while the request was read.
+    pub files: Vec<UploadedFile>,
     /// The path (directory) where the file should be stored.
     pub path: Option<String>,
-}
\ No newline at end of file
+    /// The user whose tree the file is uploaded to, defaults to the authenticated user.
+    pub owner: Option<String>,
+    /// The group whose folder the file is uploaded to instead of a user's.
+    pub group: Option<String>,
+    /// What happens if the file exists, see `ConflictPolicy`.
+    pub conflict: Option<String>,
+}
+
+/// A file of an upload request
+#[derive(Debug)]
+pub struct UploadedFile {
+    /// The name the file was sent with. Files of an uploaded folder carry their path inside the
+    /// folder, like `holiday/day1/photo.jpg`. Not sanitized yet.
+    pub name: String,
+    pub file: StagedFile,
+}
+
+/// The outcome of one file of an upload
+#[derive(Debug, Serialize)]
+pub struct UploadFileResult {
+    /// The name the file was sent with
+    pub name: String,
+    /// HTTP status code of this file
+    pub status: u16,
+    /// Where the file was stored, relative to the folder. Differs from the name with `keep-both`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stored_as: Option<String>,
+    pub size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
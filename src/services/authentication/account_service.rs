@@ -0,0 +1,257 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use bcrypt::{hash, verify};
+use log::error;
+use crate::dao::user_store::UserStore;
+use crate::models::authentication::user_administration::UserSummary;
+use crate::services::locking::directory_locking_manager::DirectoryLockManager;
+
+/// Role given to users who register themselves
+pub const DEFAULT_ROLE: &str = "user";
+
+/// Directory under the root where the trees of deleted accounts are moved to
+pub const ARCHIVE_DIR: &str = "_archive";
+
+/// Stored for users whose password is checked by an external directory.
+/// It is not a valid bcrypt hash, so no password ever matches it.
+pub const EXTERNAL_PASSWORD_HASH: &str = "!";
+
+const MIN_PASSWORD_LENGTH: usize = 8;
+const MAX_USERNAME_LENGTH: usize = 50;
+
+#[cfg(not(test))]
+const PASSWORD_HASH_COST: u32 = bcrypt::DEFAULT_COST;
+// Hashing with the default cost makes the tests crawl
+#[cfg(test)]
+const PASSWORD_HASH_COST: u32 = 4;
+
+pub struct AccountService<T: UserStore> {
+    store: T,
+    root_dir: String,
+    directory_lock_manager: DirectoryLockManager
+}
+
+impl<T: UserStore> AccountService<T> {
+    pub fn new(store: T, root_dir: String, directory_lock_manager: DirectoryLockManager) -> Self {
+        Self { store, root_dir, directory_lock_manager }
+    }
+
+    /// Creates a new account with the default role, if registration is enabled
+    pub async fn register(&self, username: &str, password: &str) -> Result<String, (u16, String)> {
+        match self.store.is_registration_enabled().await {
+            Ok(true) => {}
+            Ok(false) => return Err((403, "Registration is disabled".to_string())),
+            Err(e) => return Err((500, e)),
+        }
+
+        self.create_account(username, password, DEFAULT_ROLE).await
+    }
+
+    /// Creates the user together with their directory under the root
+    pub async fn create_account(&self, username: &str, password: &str, role: &str) -> Result<String, (u16, String)> {
+        validate_username(username)?;
+        validate_password(password)?;
+
+        let password_hash = hash_password(password)?;
+        match self.store.create_user(username, &password_hash, role).await {
+            Ok(true) => {}
+            Ok(false) => return Err((409, format!("User '{}' already exists", username))),
+            Err(e) => return Err((500, e)),
+        }
+
+        let user_dir = Path::new(&self.root_dir).join(username);
+        if let Err(e) = tokio::fs::create_dir_all(&user_dir).await {
+            error!("Could not create directory for {}: {}", username, e);
+            // Don't leave behind a user without a directory
+            if let Err(e) = self.store.delete_user(username).await {
+                error!("Could not roll back user {}: {}", username, e);
+            }
+            return Err((500, "Could not create the user directory".to_string()));
+        }
+
+        Ok(format!("User '{}' created successfully.", username))
+    }
+
+    /// Mirrors a user of an external directory into the `users` table on login,
+    /// with the role mapped from their groups, and creates their directory on the first login
+    pub async fn provision_external_account(&self, username: &str, role: &str) -> Result<(), (u16, String)> {
+        validate_username(username)?;
+        self.ensure_role_exists(role).await?;
+
+        match self.store.upsert_external_user(username, EXTERNAL_PASSWORD_HASH, role).await {
+            Ok(Some(false)) => {}
+            Ok(Some(true)) => return Err((403, "Account is disabled".to_string())),
+            // Otherwise the directory could take over the account and its files
+            Ok(None) => return Err((403, format!("'{}' is a local account", username))),
+            Err(e) => return Err((500, e)),
+        }
+
+        let user_dir = Path::new(&self.root_dir).join(username);
+        if let Err(e) = tokio::fs::create_dir_all(&user_dir).await {
+            error!("Could not create directory for {}: {}", username, e);
+            return Err((500, "Could not create the user directory".to_string()));
+        }
+        Ok(())
+    }
+
+    pub async fn change_password(
+        &self,
+        username: &str,
+        old_password: &str,
+        new_password: &str
+    ) -> Result<String, (u16, String)> {
+        self.verify_password(username, old_password).await?;
+        validate_password(new_password)?;
+
+        let password_hash = hash_password(new_password)?;
+        match self.store.update_password_hash(username, &password_hash).await {
+            Ok(_) => Ok("Password changed successfully.".to_string()),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    /// Deletes the user and moves their directory to the archive,
+    /// so the files can still be recovered by an admin
+    pub async fn delete_account(&self, username: &str, password: &str) -> Result<String, (u16, String)> {
+        self.verify_password(username, password).await?;
+        self.remove_account(username).await
+    }
+
+    /// Deletes the user without asking for their password
+    pub async fn remove_account(&self, username: &str) -> Result<String, (u16, String)> {
+        let user_dir = Path::new(&self.root_dir).join(username);
+        let lock_arc = self.directory_lock_manager.lock_for_path(user_dir.clone()).await;
+        let _guard = lock_arc.lock().await;
+
+        if tokio::fs::metadata(&user_dir).await.is_ok() {
+            let archive_dir = Path::new(&self.root_dir).join(ARCHIVE_DIR);
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+            let archived = archive_dir.join(format!("{}-{}", username, timestamp));
+
+            let archive_result = match tokio::fs::create_dir_all(&archive_dir).await {
+                Ok(_) => tokio::fs::rename(&user_dir, &archived).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = archive_result {
+                error!("Could not archive directory of {}: {}", username, e);
+                return Err((500, "Could not archive the user directory".to_string()));
+            }
+        }
+
+        match self.store.delete_user(username).await {
+            Ok(_) => Ok(format!("User '{}' deleted successfully.", username)),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    pub async fn set_registration_enabled(&self, enabled: bool) -> Result<String, (u16, String)> {
+        match self.store.set_registration_enabled(enabled).await {
+            Ok(_) if enabled => Ok("Registration enabled.".to_string()),
+            Ok(_) => Ok("Registration disabled.".to_string()),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    pub async fn list_users(&self) -> Result<Vec<UserSummary>, (u16, String)> {
+        self.store.list_users().await.map_err(|e| (500, e))
+    }
+
+    /// Creates an account with the given role, regardless of whether registration is enabled
+    pub async fn create_account_with_role(
+        &self,
+        username: &str,
+        password: &str,
+        role: &str
+    ) -> Result<String, (u16, String)> {
+        self.ensure_role_exists(role).await?;
+        self.create_account(username, password, role).await
+    }
+
+    /// Sets a new password without knowing the old one
+    pub async fn reset_password(&self, username: &str, new_password: &str) -> Result<String, (u16, String)> {
+        validate_password(new_password)?;
+
+        let password_hash = hash_password(new_password)?;
+        match self.store.update_password_hash(username, &password_hash).await {
+            Ok(_) => Ok(format!("Password of '{}' reset successfully.", username)),
+            Err(e) if e == "User not found" => Err((404, e)),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    pub async fn change_role(&self, username: &str, role: &str) -> Result<String, (u16, String)> {
+        self.ensure_role_exists(role).await?;
+
+        match self.store.set_user_role(username, role).await {
+            Ok(_) => Ok(format!("Role of '{}' changed to '{}'.", username, role)),
+            Err(e) if e == "User not found" => Err((404, e)),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    pub async fn set_disabled(&self, username: &str, disabled: bool) -> Result<String, (u16, String)> {
+        match self.store.set_user_disabled(username, disabled).await {
+            Ok(_) if disabled => Ok(format!("User '{}' disabled.", username)),
+            Ok(_) => Ok(format!("User '{}' enabled.", username)),
+            Err(e) if e == "User not found" => Err((404, e)),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    async fn ensure_role_exists(&self, role: &str) -> Result<(), (u16, String)> {
+        match self.store.role_exists(role).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err((400, format!("Unknown role '{}'", role))),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    async fn verify_password(&self, username: &str, password: &str) -> Result<(), (u16, String)> {
+        let password_hash = match self.store.get_password_hash(username).await {
+            Ok(Some(hash)) => hash,
+            Ok(None) => return Err((404, "User not found".to_string())),
+            Err(e) => return Err((500, e)),
+        };
+        if password_hash == EXTERNAL_PASSWORD_HASH {
+            return Err((400, "The password of this account is managed by an external directory".to_string()));
+        }
+
+        match verify(password, &password_hash) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err((401, "Invalid credentials".to_string())),
+            Err(e) => Err((500, e.to_string())),
+        }
+    }
+}
+
+pub fn hash_password(password: &str) -> Result<String, (u16, String)> {
+    hash(password, PASSWORD_HASH_COST).map_err(|e| (500, e.to_string()))
+}
+
+/// Usernames double as directory names under the root, so they are restricted
+/// to a safe character set. Names starting with `_` or `.` are reserved for the server.
+pub fn validate_username(username: &str) -> Result<(), (u16, String)> {
+    let valid_chars = username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.');
+
+    if username.is_empty()
+        || username.len() > MAX_USERNAME_LENGTH
+        || !valid_chars
+        || username.starts_with('_')
+        || username.starts_with('.')
+    {
+        return Err((400, format!(
+            "Invalid username '{}': use up to {} letters, digits, '.', '_' or '-', not starting with '.' or '_'",
+            username, MAX_USERNAME_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+pub fn validate_password(password: &str) -> Result<(), (u16, String)> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err((400, format!("Password must be at least {} characters long", MIN_PASSWORD_LENGTH)));
+    }
+    Ok(())
+}
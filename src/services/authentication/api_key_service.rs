@@ -0,0 +1,134 @@
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::error;
+use crate::dao::api_key_store::ApiKeyStore;
+use crate::models::authentication::api_key::ApiKeyRecord;
+use crate::models::authentication::scope::{FILES_DELETE, FILES_READ, FILES_WRITE};
+use crate::services::authentication::authentication_service::Claims;
+use crate::services::authentication::opaque_token::{generate_opaque_token, hash_opaque_token};
+
+/// Makes API keys recognizable, e.g. for secret scanners
+const API_KEY_PREFIX: &str = "fsk_";
+const API_KEY_BYTES: usize = 32;
+/// Length of the prefix stored in clear text, including `fsk_`
+const KEY_PREFIX_LENGTH: usize = 12;
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Long-lived keys for scripts that can't log in interactively.
+/// A key is shown once on creation and only its hash is stored.
+#[derive(Clone)]
+pub struct ApiKeyService {
+    store: Arc<dyn ApiKeyStore>,
+}
+
+impl ApiKeyService {
+    pub fn new(store: Arc<dyn ApiKeyStore>) -> Self {
+        Self { store }
+    }
+
+    /// Returns the new key together with its record
+    pub async fn create(
+        &self,
+        username: &str,
+        name: &str,
+        expires_at: Option<i64>,
+        read_only: bool
+    ) -> Result<(String, ApiKeyRecord), (u16, String)> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err((400, format!("The name must have between 1 and {} characters", MAX_NAME_LENGTH)));
+        }
+        let created_at = now();
+        if expires_at.is_some_and(|expires_at| expires_at <= created_at) {
+            return Err((400, "The expiration must be in the future".to_string()));
+        }
+
+        let key = format!("{}{}", API_KEY_PREFIX, generate_opaque_token(API_KEY_BYTES));
+        let record = ApiKeyRecord {
+            id: generate_opaque_token(8),
+            username: username.to_string(),
+            name: name.to_string(),
+            key_prefix: key[..KEY_PREFIX_LENGTH].to_string(),
+            read_only,
+            created_at,
+            expires_at,
+            last_used_at: None,
+            revoked: false,
+        };
+
+        self.store
+            .insert_api_key(&record, &hash_opaque_token(&key))
+            .await
+            .map_err(|e| (500, e))?;
+
+        Ok((key, record))
+    }
+
+    pub async fn list(&self, username: &str) -> Result<Vec<ApiKeyRecord>, (u16, String)> {
+        self.store.list_api_keys(username).await.map_err(|e| (500, e))
+    }
+
+    pub async fn revoke(&self, username: &str, id: &str) -> Result<String, (u16, String)> {
+        match self.store.revoke_api_key(username, id).await {
+            Ok(true) => Ok(format!("API key '{}' revoked.", id)),
+            Ok(false) => Err((404, format!("API key '{}' not found", id))),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    pub async fn revoke_all(&self, username: &str) -> Result<(), (u16, String)> {
+        self.store.revoke_all_api_keys(username).await.map_err(|e| (500, e))
+    }
+
+    /// Resolves a key to the identity of its owner, in the same shape as the claims of a token
+    pub async fn authenticate(&self, key: &str) -> Result<Claims, String> {
+        let identity = self.store
+            .find_api_key(&hash_opaque_token(key))
+            .await?
+            .ok_or_else(|| "Invalid API key".to_string())?;
+        let key = identity.key;
+        let now = now();
+
+        if key.revoked {
+            return Err("API key has been revoked".to_string());
+        }
+        if key.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err("API key has expired".to_string());
+        }
+        if identity.user_disabled {
+            return Err("Account is disabled".to_string());
+        }
+
+        if let Err(e) = self.store.touch_api_key(&key.id, now).await {
+            error!("Could not update last use of API key {}: {}", key.id, e);
+        }
+
+        Ok(Claims {
+            sub: key.username,
+            // Keys without an expiration never expire
+            exp: key.expires_at.unwrap_or(i64::MAX) as usize,
+            iat: key.created_at as usize,
+            jti: key.id.clone(),
+            role: identity.role,
+            privilege: identity.privilege,
+            iss: None,
+            aud: None,
+            mfa_pending: false,
+            api_key_id: Some(key.id),
+            parent_jti: None,
+            // API keys are meant for file access only, they can't manage the account
+            scopes: if key.read_only {
+                vec![FILES_READ.to_string()]
+            } else {
+                vec![FILES_READ.to_string(), FILES_WRITE.to_string(), FILES_DELETE.to_string()]
+            },
+        })
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
@@ -1,40 +1,508 @@
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
-use once_cell::sync::Lazy;
-use serde::{Deserialize, Serialize};
 use std::env;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use actix_web::http::header::HeaderMap;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use crate::dao::privilege_store::PrivilegeStore;
+use crate::models::authentication::auth_user::ADMIN_PRIVILEGE;
+use crate::models::authentication::scope::{default_scopes, ALL_SCOPES};
+use crate::services::authentication::api_key_service::ApiKeyService;
+use crate::services::authentication::key_ring::{ForeignIssuer, KeyRing};
+use crate::services::authentication::opaque_token::{generate_opaque_token, hash_opaque_token};
+use crate::services::authentication::token_revocation_service::TokenRevocationService;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: String,  // User ID
     pub exp: usize,   // Expiration timestamp
+    pub iat: usize,   // Issued at timestamp
+    pub jti: String,  // Unique token ID, used for revocation
+    pub role: String, // Role of the user at login time
+    pub privilege: i32, // Privilege level of the role at login time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>, // Issuer, set when configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>, // Audience, set when configured
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mfa_pending: bool, // Password was checked, but the second factor is still missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_id: Option<String>, // Set when authenticated with an API key instead of a token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_jti: Option<String>, // Set on restricted tokens, the session token they were derived from
+    #[serde(default)]
+    pub scopes: Vec<String>, // What the token may be used for, tokens without scopes can't call any endpoint
 }
 
-static SECRET_KEY: Lazy<Vec<u8>> = Lazy::new(|| {
-    env::var("JWT_TOKEN_SECRET")
-        .expect("JWT_TOKEN_SECRET must be set")
-        .into_bytes()
-});
-pub fn generate_jwt(user_id: String) -> Result<String, jsonwebtoken::errors::Error> {
-    let expiration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
-        + 3600; // Expires in 1 hour
+impl Claims {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+
+    /// Whether this is the token of a login session, not a restricted token, an API key
+    /// or a token still waiting for the MFA step
+    pub fn is_session(&self) -> bool {
+        self.parent_jti.is_none() && self.api_key_id.is_none() && !self.mfa_pending
+    }
+}
+
+/// The claims read from the tokens of other services. Only `sub`, `exp`, `iss` and `aud` are
+/// required, like in any standard token. What the user may do comes from their account.
+#[derive(Debug, Deserialize)]
+struct ForeignClaims {
+    sub: String,
+    exp: usize,
+    /// Without it the token counts as issued before any cutoff of `revoke_all_user_tokens`
+    #[serde(default)]
+    iat: usize,
+    jti: Option<String>,
+}
 
-    let claims = Claims {
-        sub: user_id,
-        exp: expiration as usize,
-    };
+impl ForeignClaims {
+    /// Tokens without a `jti` are identified by their hash, so they can still be revoked one by one
+    fn into_claims(self, token: &str, issuer: &ForeignIssuer) -> Claims {
+        Claims {
+            sub: self.sub,
+            exp: self.exp,
+            iat: self.iat,
+            jti: self.jti.unwrap_or_else(|| hash_opaque_token(token)),
+            role: String::new(),
+            privilege: 0,
+            iss: Some(issuer.issuer.clone()),
+            aud: Some(issuer.audience.clone()),
+            mfa_pending: false,
+            api_key_id: None,
+            parent_jti: None,
+            scopes: Vec::new(),
+        }
+    }
+}
+
+/// Lifetime of the token handed out between the password and the MFA step
+pub const MFA_PENDING_TTL_SECONDS: u64 = 300;
+
+/// Upper bound for the lifetime of scoped tokens, longer-lived access should use API keys
+pub const MAX_SCOPED_TOKEN_TTL_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+/// Rules every token has to satisfy on top of a valid signature
+#[derive(Debug, Clone)]
+pub struct TokenSettings {
+    /// Lifetime of access tokens in seconds
+    pub token_ttl: u64,
+    /// Allowed clock skew in seconds when checking `exp`
+    pub leeway: u64,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+}
+
+impl Default for TokenSettings {
+    fn default() -> Self {
+        Self {
+            token_ttl: 3600, // Expires in 1 hour
+            leeway: 30,
+            issuer: None,
+            audience: None,
+        }
+    }
+}
 
-    encode(&Header::default(), &claims, &EncodingKey::from_secret(&SECRET_KEY))
+impl TokenSettings {
+    /// Reads the optional `JWT_ISSUER`, `JWT_AUDIENCE`, `JWT_LEEWAY_SECONDS`
+    /// and `JWT_TOKEN_TTL_SECONDS` variables, falling back to the defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            token_ttl: env::var("JWT_TOKEN_TTL_SECONDS")
+                .map(|v| v.parse().expect("JWT_TOKEN_TTL_SECONDS must be a valid integer"))
+                .unwrap_or(defaults.token_ttl),
+            leeway: env::var("JWT_LEEWAY_SECONDS")
+                .map(|v| v.parse().expect("JWT_LEEWAY_SECONDS must be a valid integer"))
+                .unwrap_or(defaults.leeway),
+            issuer: env::var("JWT_ISSUER").ok(),
+            audience: env::var("JWT_AUDIENCE").ok(),
+        }
+    }
 }
 
-pub fn validate_jwt_token(token: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(&SECRET_KEY),
-        &Validation::new(Algorithm::HS256),
-    )?;
-    Ok(token_data.claims)
-}
\ No newline at end of file
+/// HS256 keys derived from `JWT_TOKEN_SECRET`
+struct HmacKeys {
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+}
+
+/// The single place where access tokens are issued and validated.
+/// Both the `JwtAuth` middleware and the `AuthenticatedUser` extractor delegate
+/// to it, so they can never disagree on whether a token is valid.
+///
+/// Tokens are signed with the active asymmetric key of the key ring (RS256 or EdDSA)
+/// and carry its `kid`. Without asymmetric keys, the HS256 shared secret is used instead.
+#[derive(Clone)]
+pub struct AuthenticationService {
+    hmac_keys: Option<Arc<HmacKeys>>,
+    key_ring: Arc<RwLock<KeyRing>>,
+    /// The manifest the key ring was loaded from, used for reloading
+    key_manifest: Option<PathBuf>,
+    validation: Arc<Validation>,
+    settings: TokenSettings,
+    token_revocation_service: TokenRevocationService,
+    /// Resolves `Authorization: ApiKey <key>` headers, not configured in tests
+    api_key_service: Option<ApiKeyService>,
+    /// Looks up the role and privilege level of users of tokens signed by other services,
+    /// without it those tokens are rejected
+    privilege_store: Option<Arc<dyn PrivilegeStore>>,
+}
+
+impl AuthenticationService {
+    pub fn with_keys(
+        secret: Option<&[u8]>,
+        key_ring: KeyRing,
+        key_manifest: Option<PathBuf>,
+        settings: TokenSettings,
+        token_revocation_service: TokenRevocationService
+    ) -> Self {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = settings.leeway;
+        // Configured claims must also be present, not just match when they happen to be there
+        let mut required_claims = vec!["exp"];
+        if let Some(issuer) = &settings.issuer {
+            validation.set_issuer(&[issuer]);
+            required_claims.push("iss");
+        }
+        match &settings.audience {
+            Some(audience) => {
+                validation.set_audience(&[audience]);
+                required_claims.push("aud");
+            }
+            None => validation.validate_aud = false,
+        }
+        validation.set_required_spec_claims(&required_claims);
+
+        Self {
+            hmac_keys: secret.map(|secret| Arc::new(HmacKeys {
+                encoding_key: EncodingKey::from_secret(secret),
+                decoding_key: DecodingKey::from_secret(secret),
+            })),
+            key_ring: Arc::new(RwLock::new(key_ring)),
+            key_manifest,
+            validation: Arc::new(validation),
+            settings,
+            token_revocation_service,
+            api_key_service: None,
+            privilege_store: None,
+        }
+    }
+
+    /// Accepts API keys besides bearer tokens
+    pub fn with_api_key_service(mut self, api_key_service: ApiKeyService) -> Self {
+        self.api_key_service = Some(api_key_service);
+        self
+    }
+
+    /// Accepts tokens signed by the keys of other services, taking the role and privilege
+    /// level of their users from the store instead of the token
+    pub fn with_privilege_store(mut self, privilege_store: Arc<dyn PrivilegeStore>) -> Self {
+        self.privilege_store = Some(privilege_store);
+        self
+    }
+
+    /// Uses the keys listed in `JWT_KEYS_MANIFEST` if set, and `JWT_TOKEN_SECRET` as a fallback.
+    /// At least one of them has to be configured.
+    pub fn from_env(token_revocation_service: TokenRevocationService) -> Self {
+        let secret = env::var("JWT_TOKEN_SECRET").ok();
+        let key_manifest = env::var("JWT_KEYS_MANIFEST").ok().map(PathBuf::from);
+        let key_ring = match &key_manifest {
+            Some(path) => KeyRing::load_manifest(path).expect("JWT_KEYS_MANIFEST must be a valid key manifest"),
+            None => KeyRing::default(),
+        };
+        if secret.is_none() && key_ring.is_empty() {
+            panic!("Either JWT_TOKEN_SECRET or JWT_KEYS_MANIFEST must be set");
+        }
+
+        Self::with_keys(
+            secret.as_ref().map(|s| s.as_bytes()),
+            key_ring,
+            key_manifest,
+            TokenSettings::from_env(),
+            token_revocation_service
+        )
+    }
+
+    /// Re-reads the key manifest, picking up newly added or retired keys.
+    /// On failure the current keys are kept.
+    pub fn reload_keys(&self) -> Result<(), String> {
+        if let Some(path) = &self.key_manifest {
+            let key_ring = KeyRing::load_manifest(path)?;
+            *self.key_ring.write().unwrap() = key_ring;
+            info!("Reloaded signing keys from {:?}", path);
+        }
+        Ok(())
+    }
+
+    pub fn spawn_periodic_key_reload(&self, interval: Duration) {
+        if self.key_manifest.is_none() {
+            return;
+        }
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes immediately and the keys were just loaded
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = service.reload_keys() {
+                    error!("Failed to reload signing keys: {}", e);
+                }
+            }
+        });
+    }
+
+    /// The public keys other services can use to verify our tokens
+    pub fn jwks(&self) -> JwkSet {
+        self.key_ring.read().unwrap().jwks(now())
+    }
+
+    pub fn generate_token(
+        &self,
+        user_id: String,
+        role: String,
+        privilege: i32
+    ) -> Result<String, String> {
+        let scopes = default_scopes(privilege >= ADMIN_PRIVILEGE);
+        let claims = self.new_claims(user_id, role, privilege, scopes, self.settings.token_ttl);
+        self.sign(&claims)
+    }
+
+    /// Derives a token from an existing one that can only do a subset of what the original can,
+    /// e.g. a read-only token for a device that should not be able to change anything.
+    /// Revoking the session the token was derived from revokes it as well.
+    pub fn restrict_token(
+        &self,
+        claims: &Claims,
+        scopes: Vec<String>,
+        ttl: Option<u64>
+    ) -> Result<String, (u16, String)> {
+        if scopes.is_empty() {
+            return Err((400, "At least one scope is required".to_string()));
+        }
+        if let Some(scope) = scopes.iter().find(|scope| !ALL_SCOPES.contains(&scope.as_str())) {
+            return Err((400, format!("Unknown scope '{}'", scope)));
+        }
+        if let Some(scope) = scopes.iter().find(|scope| !claims.has_scope(scope)) {
+            return Err((403, format!("The token does not have the '{}' scope", scope)));
+        }
+
+        let mut ttl = ttl.unwrap_or(self.settings.token_ttl);
+        if ttl == 0 || ttl > MAX_SCOPED_TOKEN_TTL_SECONDS {
+            return Err((400, format!("The lifetime must be between 1 and {} seconds", MAX_SCOPED_TOKEN_TTL_SECONDS)));
+        }
+        // Tokens derived from restricted tokens belong to the same session and don't outlive
+        // their origin, so the revocation of the session always covers them
+        if claims.parent_jti.is_some() {
+            ttl = ttl.min((claims.exp as u64).saturating_sub(now() as u64));
+        }
+
+        let mut child = self.new_claims(claims.sub.clone(), claims.role.clone(), claims.privilege, scopes, ttl);
+        child.parent_jti = Some(claims.parent_jti.clone().unwrap_or_else(|| claims.jti.clone()));
+        self.sign(&child).map_err(|e| (500, e))
+    }
+
+    /// Issues a short-lived token that proves the password was correct. It is only
+    /// accepted by the MFA login step, never as an access token.
+    pub fn generate_mfa_pending_token(&self, user_id: String) -> Result<String, String> {
+        let mut claims = self.new_claims(user_id, String::new(), 0, Vec::new(), MFA_PENDING_TTL_SECONDS);
+        claims.mfa_pending = true;
+        self.sign(&claims)
+    }
+
+    fn new_claims(
+        &self,
+        user_id: String,
+        role: String,
+        privilege: i32,
+        scopes: Vec<String>,
+        ttl: u64
+    ) -> Claims {
+        let issued_at = now();
+        Claims {
+            sub: user_id,
+            exp: (issued_at as u64 + ttl) as usize,
+            iat: issued_at as usize,
+            jti: generate_opaque_token(16),
+            role,
+            privilege,
+            iss: self.settings.issuer.clone(),
+            aud: self.settings.audience.clone(),
+            mfa_pending: false,
+            api_key_id: None,
+            parent_jti: None,
+            scopes,
+        }
+    }
+
+    fn sign(&self, claims: &Claims) -> Result<String, String> {
+        let key_ring = self.key_ring.read().unwrap();
+        let result = match (key_ring.active_signing_key(claims.iat as i64), &self.hmac_keys) {
+            (Some(key), _) => {
+                let mut header = Header::new(key.algorithm);
+                header.kid = Some(key.kid.clone());
+                encode(&header, claims, key.encoding_key.as_ref().unwrap())
+            }
+            (None, Some(hmac_keys)) => encode(&Header::new(Algorithm::HS256), claims, &hmac_keys.encoding_key),
+            (None, None) => return Err("No active signing key".to_string()),
+        };
+
+        result.map_err(|e| e.to_string())
+    }
+
+    /// Checks the signature, expiration, issuer and audience of the token
+    /// and makes sure it hasn't been revoked. Tokens of other services are only
+    /// accepted by `authenticate`, which replaces what they claim about the user.
+    pub fn validate_token(&self, token: &str) -> Result<Claims, String> {
+        match self.decode_token(token)? {
+            (claims, None) => Ok(claims),
+            (_, Some(_)) => Err("Invalid token: issued by another service".to_string()),
+        }
+    }
+
+    /// Validates a token, also ones signed by the keys of other services, which
+    /// are returned together with their issuer
+    fn decode_token(&self, token: &str) -> Result<(Claims, Option<ForeignIssuer>), String> {
+        let header = decode_header(token).map_err(|e| format!("Invalid token: {}", e))?;
+
+        // The algorithm is pinned to the one of the key, so a token can't pick
+        // a weaker algorithm than the key was meant for
+        let mut validation = self.validation.as_ref().clone();
+        let key_ring = self.key_ring.read().unwrap();
+        let mut foreign_issuer = None;
+        let decoding_key = match (&header.kid, &self.hmac_keys) {
+            (Some(kid), _) => {
+                let key = key_ring
+                    .verification_key(kid, now())
+                    .ok_or_else(|| format!("Invalid token: unknown key '{}'", kid))?;
+                validation.algorithms = vec![key.algorithm];
+                // Tokens of another service have to be meant for us, whatever our own tokens carry
+                if let Some(issuer) = &key.foreign_issuer {
+                    validation.set_issuer(&[&issuer.issuer]);
+                    validation.set_audience(&[&issuer.audience]);
+                    validation.validate_aud = true;
+                    validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+                    foreign_issuer = Some(issuer.clone());
+                }
+                &key.decoding_key
+            }
+            (None, Some(hmac_keys)) => &hmac_keys.decoding_key,
+            (None, None) => return Err("Invalid token: missing key id".to_string()),
+        };
+
+        let claims = match &foreign_issuer {
+            Some(issuer) => decode::<ForeignClaims>(token, decoding_key, &validation)
+                .map(|data| data.claims.into_claims(token, issuer)),
+            None => decode::<Claims>(token, decoding_key, &validation).map(|data| data.claims),
+        }.map_err(|e| format!("Invalid token: {}", e))?;
+
+        if self.token_revocation_service.is_revoked(&claims) {
+            return Err("Token has been revoked".to_string());
+        }
+
+        Ok((claims, foreign_issuer))
+    }
+
+    /// Validates a token issued by `generate_mfa_pending_token`
+    pub fn validate_mfa_pending_token(&self, token: &str) -> Result<Claims, String> {
+        let claims = self.validate_token(token)?;
+        if !claims.mfa_pending {
+            return Err("Invalid token: not an MFA token".to_string());
+        }
+        Ok(claims)
+    }
+
+    /// Authenticates a request by its `Authorization: Bearer <token>` header.
+    /// Tokens still waiting for the MFA step are rejected.
+    pub async fn authenticate(&self, headers: &HeaderMap) -> Result<Claims, String> {
+        let header = headers
+            .get("Authorization")
+            .ok_or_else(|| "Missing Authorization header".to_string())?;
+        let token = parse_bearer_token(
+            header.to_str().map_err(|_| "Invalid Authorization header".to_string())?
+        )?;
+
+        let (claims, foreign_issuer) = self.decode_token(token)?;
+        if claims.mfa_pending {
+            return Err("MFA verification required".to_string());
+        }
+        match foreign_issuer {
+            Some(_) => self.resolve_foreign_claims(claims).await,
+            None => Ok(claims),
+        }
+    }
+
+    /// Another service can tell who the user is, but not what they may do here.
+    /// The role, privilege level and scopes are those of the user's account.
+    async fn resolve_foreign_claims(&self, mut claims: Claims) -> Result<Claims, String> {
+        let privilege_store = self.privilege_store
+            .as_ref()
+            .ok_or_else(|| "Tokens of other services are not supported".to_string())?;
+        // Disabling only revokes the tokens issued so far, the other service may still issue new ones
+        if privilege_store.is_user_disabled(&claims.sub).await? {
+            return Err("Account is disabled".to_string());
+        }
+        let role = privilege_store.get_user_role(&claims.sub).await?;
+        let privilege = privilege_store.get_privilege_level(&role).await?.unwrap_or(0);
+
+        claims.scopes = default_scopes(privilege >= ADMIN_PRIVILEGE);
+        claims.role = role;
+        claims.privilege = privilege;
+        claims.api_key_id = None;
+        Ok(claims)
+    }
+
+    /// Authenticates a request either by a bearer token or by an `Authorization: ApiKey <key>` header
+    pub async fn authenticate_request(&self, headers: &HeaderMap) -> Result<Claims, String> {
+        let api_key = headers
+            .get("Authorization")
+            .and_then(|header| header.to_str().ok())
+            .and_then(|value| value.strip_prefix("ApiKey "));
+
+        match (api_key, &self.api_key_service) {
+            (Some(key), Some(api_key_service)) => api_key_service.authenticate(key.trim()).await,
+            (Some(_), None) => Err("API keys are not supported".to_string()),
+            (None, _) => self.authenticate(headers).await,
+        }
+    }
+
+    /// Revokes the token and, for a session token, the tokens derived from it. The revocation
+    /// is kept for as long as any of them can still be accepted.
+    pub async fn revoke_token(&self, claims: &Claims) -> Result<(), String> {
+        let mut revoked_until = (claims.exp as u64).saturating_add(self.settings.leeway);
+        if claims.is_session() {
+            revoked_until = revoked_until.saturating_add(MAX_SCOPED_TOKEN_TTL_SECONDS + self.settings.leeway);
+        }
+        let revoked_until = i64::try_from(revoked_until).unwrap_or(i64::MAX);
+        self.token_revocation_service.revoke_token(&claims.jti, revoked_until).await
+    }
+
+    pub async fn revoke_all_user_tokens(&self, username: &str) -> Result<(), String> {
+        self.token_revocation_service.revoke_all_user_tokens(username).await
+    }
+}
+
+fn parse_bearer_token(value: &str) -> Result<&str, String> {
+    let token = value
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| "Expected 'Bearer <token>'".to_string())?
+        .trim();
+    if token.is_empty() {
+        return Err("No token after 'Bearer '".to_string());
+    }
+    Ok(token)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
@@ -0,0 +1,229 @@
+use std::path::{Path, PathBuf};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+    OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use ring::signature::{Ed25519KeyPair, KeyPair, RsaKeyPair, RsaPublicKeyComponents};
+use serde::Deserialize;
+
+/// An entry of the key manifest file pointed to by `JWT_KEYS_MANIFEST`, e.g.:
+/// ```json
+/// { "keys": [
+///     { "kid": "2026-10", "algorithm": "EdDSA", "private_key": "keys/2026-10.pem",
+///       "activate_at": 1790812800, "retire_at": 1798761600 },
+///     { "kid": "media-center", "algorithm": "RS256", "public_key": "keys/media-center.pub.pem",
+///       "issuer": "media-center", "audience": "file-server" }
+/// ] }
+/// ```
+/// Relative paths are resolved against the directory of the manifest.
+#[derive(Debug, Deserialize)]
+pub struct KeyManifestEntry {
+    pub kid: String,
+    /// `RS256` or `EdDSA`
+    pub algorithm: String,
+    /// PKCS#8 (or PKCS#1 for RSA) PEM file. Keys with a private key can sign tokens
+    /// and are published in the JWKS.
+    pub private_key: Option<PathBuf>,
+    /// PEM file of a key that is only used to verify tokens issued by another service
+    pub public_key: Option<PathBuf>,
+    /// The `iss` the tokens of another service must carry, required with `public_key`
+    pub issuer: Option<String>,
+    /// The `aud` the tokens of another service must be addressed to, required with `public_key`
+    pub audience: Option<String>,
+    /// The key signs tokens from this unix timestamp on. Until then it is only published,
+    /// so that verifiers already know it when the rotation happens.
+    #[serde(default)]
+    pub activate_at: i64,
+    /// From this unix timestamp on the key neither signs nor verifies tokens
+    pub retire_at: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct KeyManifest {
+    pub keys: Vec<KeyManifestEntry>,
+}
+
+/// Who the tokens verified with a key of another service come from, and who they are meant for
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForeignIssuer {
+    pub issuer: String,
+    pub audience: String,
+}
+
+pub struct SigningKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub encoding_key: Option<EncodingKey>,
+    pub decoding_key: DecodingKey,
+    /// Public part of the key, only present for our own keys
+    pub jwk: Option<Jwk>,
+    /// Only present for keys of other services. Their tokens tell who the user is,
+    /// but not what the user may do.
+    pub foreign_issuer: Option<ForeignIssuer>,
+    pub activate_at: i64,
+    pub retire_at: Option<i64>,
+}
+
+impl SigningKey {
+    fn is_retired(&self, now: i64) -> bool {
+        self.retire_at.is_some_and(|retire_at| retire_at <= now)
+    }
+}
+
+/// The asymmetric keys used to sign and verify tokens, selected by their `kid`.
+#[derive(Default)]
+pub struct KeyRing {
+    keys: Vec<SigningKey>,
+}
+
+impl KeyRing {
+    pub fn new(keys: Vec<SigningKey>) -> Self {
+        Self { keys }
+    }
+
+    pub fn load_manifest(manifest_path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(manifest_path)
+            .map_err(|e| format!("Could not read key manifest {:?}: {}", manifest_path, e))?;
+        let manifest: KeyManifest = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid key manifest {:?}: {}", manifest_path, e))?;
+        let base_dir = manifest_path.parent().unwrap_or(Path::new("."));
+
+        let keys = manifest.keys
+            .into_iter()
+            .map(|entry| load_key(entry, base_dir))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self::new(keys))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The most recently activated key that can sign and is not retired yet
+    pub fn active_signing_key(&self, now: i64) -> Option<&SigningKey> {
+        self.keys
+            .iter()
+            .filter(|key| key.encoding_key.is_some())
+            .filter(|key| key.activate_at <= now && !key.is_retired(now))
+            .max_by_key(|key| key.activate_at)
+    }
+
+    /// Looks up a key that may still be used to verify tokens
+    pub fn verification_key(&self, kid: &str, now: i64) -> Option<&SigningKey> {
+        self.keys
+            .iter()
+            .find(|key| key.kid == kid && !key.is_retired(now))
+    }
+
+    /// The public parts of all our keys that are not retired, including
+    /// the ones that are not active yet
+    pub fn jwks(&self, now: i64) -> JwkSet {
+        JwkSet {
+            keys: self.keys
+                .iter()
+                .filter(|key| !key.is_retired(now))
+                .filter_map(|key| key.jwk.clone())
+                .collect(),
+        }
+    }
+}
+
+fn load_key(entry: KeyManifestEntry, base_dir: &Path) -> Result<SigningKey, String> {
+    let algorithm = match entry.algorithm.as_str() {
+        "RS256" => Algorithm::RS256,
+        "EdDSA" => Algorithm::EdDSA,
+        other => return Err(format!("Unsupported algorithm '{}' for key '{}'", other, entry.kid)),
+    };
+
+    let read_pem = |path: &PathBuf| -> Result<Vec<u8>, String> {
+        std::fs::read(base_dir.join(path))
+            .map_err(|e| format!("Could not read key file {:?} of key '{}': {}", path, entry.kid, e))
+    };
+    let invalid_key = |e: String| format!("Invalid key '{}': {}", entry.kid, e);
+
+    let (encoding_key, decoding_key, jwk, foreign_issuer) = match (&entry.private_key, &entry.public_key) {
+        (Some(private_key), _) => {
+            let pem = read_pem(private_key)?;
+            let (encoding_key, params) = match algorithm {
+                Algorithm::RS256 => (
+                    EncodingKey::from_rsa_pem(&pem).map_err(|e| invalid_key(e.to_string()))?,
+                    rsa_jwk_parameters(&pem).map_err(invalid_key)?,
+                ),
+                _ => (
+                    EncodingKey::from_ed_pem(&pem).map_err(|e| invalid_key(e.to_string()))?,
+                    ed25519_jwk_parameters(&pem).map_err(invalid_key)?,
+                ),
+            };
+            let jwk = Jwk {
+                common: CommonParameters {
+                    public_key_use: Some(PublicKeyUse::Signature),
+                    key_algorithm: Some(match algorithm {
+                        Algorithm::RS256 => KeyAlgorithm::RS256,
+                        _ => KeyAlgorithm::EdDSA,
+                    }),
+                    key_id: Some(entry.kid.clone()),
+                    ..CommonParameters::default()
+                },
+                algorithm: params,
+            };
+            let decoding_key = DecodingKey::from_jwk(&jwk).map_err(|e| invalid_key(e.to_string()))?;
+            (Some(encoding_key), decoding_key, Some(jwk), None)
+        }
+        (None, Some(public_key)) => {
+            // Without them, any token the other service signs would be accepted, whoever it was meant for
+            let (Some(issuer), Some(audience)) = (&entry.issuer, &entry.audience) else {
+                return Err(invalid_key("keys with a public_key need an issuer and an audience".to_string()));
+            };
+            let foreign_issuer = ForeignIssuer { issuer: issuer.clone(), audience: audience.clone() };
+            let pem = read_pem(public_key)?;
+            let decoding_key = match algorithm {
+                Algorithm::RS256 => DecodingKey::from_rsa_pem(&pem),
+                _ => DecodingKey::from_ed_pem(&pem),
+            }.map_err(|e| invalid_key(e.to_string()))?;
+            (None, decoding_key, None, Some(foreign_issuer))
+        }
+        (None, None) => return Err(invalid_key("either private_key or public_key must be set".to_string())),
+    };
+
+    Ok(SigningKey {
+        kid: entry.kid,
+        algorithm,
+        encoding_key,
+        decoding_key,
+        jwk,
+        foreign_issuer,
+        activate_at: entry.activate_at,
+        retire_at: entry.retire_at,
+    })
+}
+
+fn rsa_jwk_parameters(pem: &[u8]) -> Result<AlgorithmParameters, String> {
+    let parsed = pem::parse(pem).map_err(|e| e.to_string())?;
+    let key_pair = match parsed.tag() {
+        "RSA PRIVATE KEY" => RsaKeyPair::from_der(parsed.contents()),
+        _ => RsaKeyPair::from_pkcs8(parsed.contents()),
+    }.map_err(|e| e.to_string())?;
+    let components = RsaPublicKeyComponents::<Vec<u8>>::from(key_pair.public());
+
+    Ok(AlgorithmParameters::RSA(RSAKeyParameters {
+        key_type: RSAKeyType::RSA,
+        n: URL_SAFE_NO_PAD.encode(components.n),
+        e: URL_SAFE_NO_PAD.encode(components.e),
+    }))
+}
+
+fn ed25519_jwk_parameters(pem: &[u8]) -> Result<AlgorithmParameters, String> {
+    let parsed = pem::parse(pem).map_err(|e| e.to_string())?;
+    let key_pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(parsed.contents())
+        .map_err(|e| e.to_string())?;
+
+    Ok(AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+        key_type: OctetKeyPairType::OctetKeyPair,
+        curve: EllipticCurve::Ed25519,
+        x: URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+    }))
+}
@@ -0,0 +1,99 @@
+use std::env;
+use std::time::Duration;
+use ldap3::{dn_escape, ldap_escape};
+
+/// How to find and authenticate users in an LDAP or Active Directory server, read from the environment
+#[derive(Debug, Clone)]
+pub struct LdapSettings {
+    /// e.g. `ldap://localhost:3893` or `ldaps://ad.example.com`
+    pub url: String,
+    /// Users are searched below this DN
+    pub base_dn: String,
+    /// Attribute holding the login name, `uid` for OpenLDAP or `sAMAccountName` for Active Directory
+    pub user_attribute: String,
+    /// Attribute listing the DNs of the user's groups
+    pub group_attribute: String,
+    /// Service account used to look up users. Without it users bind directly
+    /// as `<user_attribute>=<username>,<base_dn>`.
+    pub bind_dn: Option<String>,
+    pub bind_password: Option<String>,
+    /// Group DN to role, in order of precedence
+    pub group_roles: Vec<(String, String)>,
+    /// Role of users in none of the mapped groups. Without it such users can't log in.
+    pub default_role: Option<String>,
+    pub starttls: bool,
+    pub timeout: Duration,
+}
+
+impl LdapSettings {
+    /// Reads `LDAP_URL` and `LDAP_BASE_DN`, and the optional `LDAP_USER_ATTRIBUTE`, `LDAP_GROUP_ATTRIBUTE`,
+    /// `LDAP_BIND_DN`, `LDAP_BIND_PASSWORD`, `LDAP_GROUP_ROLES`, `LDAP_DEFAULT_ROLE`, `LDAP_STARTTLS`
+    /// and `LDAP_TIMEOUT_SECONDS` variables
+    pub fn from_env() -> Self {
+        let group_roles = env::var("LDAP_GROUP_ROLES")
+            .map(|v| parse_group_roles(&v).expect("LDAP_GROUP_ROLES must be a list of 'role:group DN' separated by ';'"))
+            .unwrap_or_default();
+
+        Self {
+            url: env::var("LDAP_URL").expect("LDAP_URL must be set when AUTH_BACKEND is 'ldap'"),
+            base_dn: env::var("LDAP_BASE_DN").expect("LDAP_BASE_DN must be set when AUTH_BACKEND is 'ldap'"),
+            user_attribute: env::var("LDAP_USER_ATTRIBUTE").unwrap_or_else(|_| "uid".to_string()),
+            group_attribute: env::var("LDAP_GROUP_ATTRIBUTE").unwrap_or_else(|_| "memberOf".to_string()),
+            bind_dn: env::var("LDAP_BIND_DN").ok(),
+            bind_password: env::var("LDAP_BIND_PASSWORD").ok(),
+            group_roles,
+            default_role: env::var("LDAP_DEFAULT_ROLE").ok(),
+            starttls: env::var("LDAP_STARTTLS")
+                .map(|v| v.parse().expect("LDAP_STARTTLS must be 'true' or 'false'"))
+                .unwrap_or(false),
+            timeout: Duration::from_secs(
+                env::var("LDAP_TIMEOUT_SECONDS")
+                    .map(|v| v.parse().expect("LDAP_TIMEOUT_SECONDS must be a valid integer"))
+                    .unwrap_or(5)
+            ),
+        }
+    }
+
+    /// The DN users bind as when there is no service account
+    pub fn user_dn(&self, username: &str) -> String {
+        format!("{}={},{}", self.user_attribute, dn_escape(username), self.base_dn)
+    }
+
+    /// The filter finding a user by login name
+    pub fn user_filter(&self, username: &str) -> String {
+        format!("({}={})", self.user_attribute, ldap_escape(username))
+    }
+
+    /// The role of the first mapped group the user is a member of, or the default role
+    pub fn role_for_groups(&self, groups: &[String]) -> Option<String> {
+        let groups: Vec<String> = groups.iter().map(|group| normalize_dn(group)).collect();
+        self.group_roles
+            .iter()
+            .find(|(group, _)| groups.contains(&normalize_dn(group)))
+            .map(|(_, role)| role.clone())
+            .or_else(|| self.default_role.clone())
+    }
+}
+
+/// Parses `admin:cn=admins,ou=groups,dc=example,dc=com;user:cn=staff,ou=groups,dc=example,dc=com`
+pub fn parse_group_roles(value: &str) -> Result<Vec<(String, String)>, String> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once(':') {
+            Some((role, group)) if !role.trim().is_empty() && !group.trim().is_empty() => {
+                Ok((group.trim().to_string(), role.trim().to_string()))
+            }
+            _ => Err(format!("Invalid group mapping '{}'", entry)),
+        })
+        .collect()
+}
+
+/// DNs are compared case-insensitively and servers differ in whether they put spaces after commas
+fn normalize_dn(dn: &str) -> String {
+    dn.split(',')
+        .map(|part| part.trim().to_lowercase())
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// How failed logins of a single key (username or IP) are penalized
+#[derive(Debug, Clone)]
+pub struct ThrottlePolicy {
+    /// Failures allowed before any delay is enforced
+    pub free_attempts: u32,
+    /// Delay after the first penalized failure, doubled with every further failure
+    pub base_delay: i64,
+    /// Upper bound of the exponential backoff
+    pub max_delay: i64,
+    /// Number of failures after which the key is locked out
+    pub lockout_threshold: u32,
+    pub lockout_duration: i64,
+    /// Failures are forgotten once nothing happened for this long
+    pub reset_after: i64,
+}
+
+impl ThrottlePolicy {
+    pub fn default_for_username() -> Self {
+        Self {
+            free_attempts: 3,
+            base_delay: 1,
+            max_delay: 300,
+            lockout_threshold: 10,
+            lockout_duration: 900,
+            reset_after: 3600,
+        }
+    }
+
+    /// A single IP may legitimately serve a whole household, so it gets more room
+    pub fn default_for_ip() -> Self {
+        Self {
+            free_attempts: 10,
+            base_delay: 1,
+            max_delay: 300,
+            lockout_threshold: 50,
+            lockout_duration: 900,
+            reset_after: 3600,
+        }
+    }
+
+    fn delay(&self, failures: u32) -> i64 {
+        if failures >= self.lockout_threshold {
+            return self.lockout_duration;
+        }
+        if failures < self.free_attempts {
+            return 0;
+        }
+
+        let exponent = (failures - self.free_attempts).min(30);
+        self.base_delay.saturating_mul(1 << exponent).min(self.max_delay)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AttemptState {
+    failures: u32,
+    last_failure: i64,
+    blocked_until: i64,
+}
+
+/// Tracks failed logins per username and per IP in memory.
+///
+/// Every failure blocks the key for an exponentially growing delay and too many
+/// failures lock it out temporarily. Unknown usernames are tracked like existing ones,
+/// so the throttle does not reveal which accounts exist.
+#[derive(Clone)]
+pub struct LoginThrottle {
+    username_policy: ThrottlePolicy,
+    ip_policy: ThrottlePolicy,
+    usernames: Arc<Mutex<HashMap<String, AttemptState>>>,
+    ips: Arc<Mutex<HashMap<String, AttemptState>>>,
+}
+
+/// Entries are pruned once a map grows beyond this size
+const PRUNE_THRESHOLD: usize = 10_000;
+
+impl Default for LoginThrottle {
+    fn default() -> Self {
+        Self::new(ThrottlePolicy::default_for_username(), ThrottlePolicy::default_for_ip())
+    }
+}
+
+impl LoginThrottle {
+    pub fn new(username_policy: ThrottlePolicy, ip_policy: ThrottlePolicy) -> Self {
+        Self {
+            username_policy,
+            ip_policy,
+            usernames: Arc::new(Mutex::new(HashMap::new())),
+            ips: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the number of seconds to wait if either the username or the IP is blocked
+    pub fn check(&self, username: &str, ip: Option<&str>, now: i64) -> Result<(), i64> {
+        let mut retry_after = blocked_for(&self.usernames, username, now);
+        if let Some(ip) = ip {
+            retry_after = retry_after.max(blocked_for(&self.ips, ip, now));
+        }
+
+        if retry_after > 0 {
+            Err(retry_after)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn record_failure(&self, username: &str, ip: Option<&str>, now: i64) {
+        register_failure(&self.usernames, &self.username_policy, username, now);
+        if let Some(ip) = ip {
+            register_failure(&self.ips, &self.ip_policy, ip, now);
+        }
+    }
+
+    /// Forgets the failures of the username. The IP keeps its history, otherwise
+    /// an attacker could reset it by logging into their own account in between.
+    pub fn record_success(&self, username: &str) {
+        self.usernames.lock().unwrap().remove(username);
+    }
+}
+
+fn blocked_for(entries: &Mutex<HashMap<String, AttemptState>>, key: &str, now: i64) -> i64 {
+    entries
+        .lock()
+        .unwrap()
+        .get(key)
+        .map(|state| (state.blocked_until - now).max(0))
+        .unwrap_or(0)
+}
+
+fn register_failure(
+    entries: &Mutex<HashMap<String, AttemptState>>,
+    policy: &ThrottlePolicy,
+    key: &str,
+    now: i64
+) {
+    let mut entries = entries.lock().unwrap();
+    if entries.len() >= PRUNE_THRESHOLD {
+        entries.retain(|_, state| {
+            state.blocked_until > now || now - state.last_failure < policy.reset_after
+        });
+    }
+
+    let state = entries.entry(key.to_string()).or_insert(AttemptState {
+        failures: 0,
+        last_failure: now,
+        blocked_until: 0,
+    });
+    if now - state.last_failure >= policy.reset_after && state.blocked_until <= now {
+        state.failures = 0;
+    }
+
+    state.failures += 1;
+    state.last_failure = now;
+    state.blocked_until = now + policy.delay(state.failures);
+}
@@ -0,0 +1,156 @@
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::dao::mfa_store::MfaStore;
+use crate::models::authentication::mfa::{MfaEnrollment, MfaState};
+use crate::services::authentication::opaque_token::{generate_opaque_token, hash_opaque_token};
+use crate::services::authentication::totp::{generate_totp_secret, provisioning_uri, verify_totp};
+
+const RECOVERY_CODE_COUNT: usize = 10;
+/// Bytes of entropy per recovery code, hex encoded to 10 characters
+const RECOVERY_CODE_BYTES: usize = 5;
+
+/// Who has to use MFA, read from the environment
+#[derive(Debug, Clone)]
+pub struct MfaPolicy {
+    /// Users whose privilege is at least this level can't log in without MFA
+    pub required_min_privilege: Option<i32>,
+    /// Shown as the account name in authenticator apps
+    pub issuer: String,
+}
+
+impl Default for MfaPolicy {
+    fn default() -> Self {
+        Self {
+            required_min_privilege: None,
+            issuer: "File Server".to_string(),
+        }
+    }
+}
+
+impl MfaPolicy {
+    /// Reads the optional `MFA_REQUIRED_MIN_PRIVILEGE` and `MFA_ISSUER` variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            required_min_privilege: env::var("MFA_REQUIRED_MIN_PRIVILEGE")
+                .ok()
+                .map(|v| v.parse().expect("MFA_REQUIRED_MIN_PRIVILEGE must be a valid integer")),
+            issuer: env::var("MFA_ISSUER").unwrap_or(defaults.issuer),
+        }
+    }
+
+    pub fn requires_mfa(&self, privilege: i32) -> bool {
+        self.required_min_privilege.is_some_and(|min| privilege >= min)
+    }
+}
+
+pub struct MfaService<T: MfaStore> {
+    store: T,
+}
+
+impl<T: MfaStore> MfaService<T> {
+    pub fn new(store: T) -> Self {
+        Self { store }
+    }
+
+    pub async fn is_enabled(&self, username: &str) -> Result<bool, (u16, String)> {
+        Ok(self.state(username).await?.enabled)
+    }
+
+    /// Generates a new secret. It only takes effect once confirmed with a code,
+    /// so a user who never finishes the enrollment is not locked out.
+    pub async fn start_enrollment(&self, username: &str, issuer: &str) -> Result<MfaEnrollment, (u16, String)> {
+        if self.state(username).await?.enabled {
+            return Err((409, "MFA is already enabled".to_string()));
+        }
+
+        let secret = generate_totp_secret();
+        self.store.set_pending_secret(username, &secret).await.map_err(|e| (500, e))?;
+
+        Ok(MfaEnrollment {
+            provisioning_uri: provisioning_uri(issuer, username, &secret),
+            secret,
+        })
+    }
+
+    /// Enables MFA if the code matches the pending secret. Returns the recovery codes,
+    /// which are only stored hashed and can't be shown again.
+    pub async fn confirm_enrollment(&self, username: &str, code: &str) -> Result<Vec<String>, (u16, String)> {
+        let state = self.state(username).await?;
+        if state.enabled {
+            return Err((409, "MFA is already enabled".to_string()));
+        }
+        let secret = state.totp_secret
+            .as_deref()
+            .ok_or_else(|| (400, "No MFA enrollment in progress".to_string()))?;
+
+        self.check_code(username, secret, code).await?;
+
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| generate_opaque_token(RECOVERY_CODE_BYTES))
+            .collect();
+        let hashes: Vec<String> = recovery_codes.iter().map(|code| hash_opaque_token(code)).collect();
+        self.store.enable_mfa(username, &hashes).await.map_err(|e| (500, e))?;
+
+        Ok(recovery_codes)
+    }
+
+    /// Checks the second factor, either a TOTP code or a single-use recovery code
+    pub async fn verify(
+        &self,
+        username: &str,
+        code: Option<&str>,
+        recovery_code: Option<&str>
+    ) -> Result<(), (u16, String)> {
+        let state = self.state(username).await?;
+        let secret = match (&state.totp_secret, state.enabled) {
+            (Some(secret), true) => secret,
+            _ => return Err((400, "MFA is not enabled".to_string())),
+        };
+
+        match (code, recovery_code) {
+            (Some(code), _) => self.check_code(username, secret, code).await,
+            (None, Some(recovery_code)) => {
+                let code_hash = hash_opaque_token(recovery_code.trim());
+                match self.store.consume_recovery_code(username, &code_hash).await {
+                    Ok(true) => Ok(()),
+                    Ok(false) => Err((401, "Invalid recovery code".to_string())),
+                    Err(e) => Err((500, e)),
+                }
+            }
+            (None, None) => Err((400, "Either a code or a recovery code is required".to_string())),
+        }
+    }
+
+    pub async fn disable(&self, username: &str, code: &str) -> Result<String, (u16, String)> {
+        self.verify(username, Some(code), None).await?;
+        self.store.disable_mfa(username).await.map_err(|e| (500, e))?;
+        Ok("MFA disabled.".to_string())
+    }
+
+    async fn check_code(&self, username: &str, secret: &str, code: &str) -> Result<(), (u16, String)> {
+        let step = verify_totp(secret, code, now())
+            .ok_or_else(|| (401, "Invalid code".to_string()))?;
+
+        match self.store.mark_step_used(username, step).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err((401, "Code was already used".to_string())),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    async fn state(&self, username: &str) -> Result<MfaState, (u16, String)> {
+        match self.store.get_mfa_state(username).await {
+            Ok(Some(state)) => Ok(state),
+            Ok(None) => Err((404, "User not found".to_string())),
+            Err(e) => Err((500, e)),
+        }
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
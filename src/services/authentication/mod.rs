@@ -1 +1,13 @@
-pub mod authentication_service;
\ No newline at end of file
+pub mod authentication_service;
+pub mod opaque_token;
+pub mod refresh_token_service;
+pub mod token_revocation_service;
+pub mod key_ring;
+pub mod account_service;
+pub mod login_throttle;
+pub mod totp;
+pub mod mfa_service;
+pub mod api_key_service;
+pub mod ldap_settings;
+pub mod oidc_client;
+pub mod oidc_account_service;
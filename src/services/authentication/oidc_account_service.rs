@@ -0,0 +1,82 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::{error, info};
+use crate::dao::oidc_identity_store::OidcIdentityStore;
+use crate::services::authentication::account_service::{validate_username, EXTERNAL_PASSWORD_HASH};
+use crate::services::authentication::oidc_client::{OidcIdentity, OidcSettings};
+
+/// Maps identities of the OpenID Connect provider to local users
+pub struct OidcAccountService<T: OidcIdentityStore> {
+    store: T,
+    root_dir: String,
+}
+
+impl<T: OidcIdentityStore> OidcAccountService<T> {
+    pub fn new(store: T, root_dir: String) -> Self {
+        Self { store, root_dir }
+    }
+
+    /// Returns the local user of the identity. An identity seen for the first time is linked to
+    /// the user named by its username claim, or gets a new user, as far as the settings allow.
+    pub async fn resolve_user(&self, identity: &OidcIdentity, settings: &OidcSettings) -> Result<String, (u16, String)> {
+        let linked = self.store
+            .find_linked_user(&identity.issuer, &identity.subject)
+            .await
+            .map_err(|e| (500, e))?;
+        if let Some((username, disabled)) = linked {
+            return check_enabled(username, disabled);
+        }
+
+        let username = identity.username.as_deref().ok_or_else(|| {
+            (403, format!("The provider did not send the '{}' claim", settings.username_claim))
+        })?;
+        validate_username(username).map_err(|(_, e)| (403, e))?;
+
+        match self.store.get_user_disabled(username).await.map_err(|e| (500, e))? {
+            Some(disabled) if settings.link_existing_users => {
+                self.store
+                    .link_identity(&identity.issuer, &identity.subject, username, now())
+                    .await
+                    .map_err(|e| (500, e))?;
+                info!("Linked OIDC subject {} to {}", identity.subject, username);
+                check_enabled(username.to_string(), disabled)
+            }
+            Some(_) => Err((403, format!("User '{}' exists but is not linked to this identity", username))),
+            None if settings.auto_provision => self.provision(identity, username, &settings.default_role).await,
+            None => Err((403, "No user is linked to this identity".to_string())),
+        }
+    }
+
+    async fn provision(&self, identity: &OidcIdentity, username: &str, role: &str) -> Result<String, (u16, String)> {
+        let created = self.store
+            .create_linked_user(&identity.issuer, &identity.subject, username, EXTERNAL_PASSWORD_HASH, role, now())
+            .await
+            .map_err(|e| (500, e))?;
+        if !created {
+            // Someone took the name in the meantime
+            return Err((409, format!("User '{}' already exists", username)));
+        }
+
+        let user_dir = Path::new(&self.root_dir).join(username);
+        if let Err(e) = tokio::fs::create_dir_all(&user_dir).await {
+            error!("Could not create directory for {}: {}", username, e);
+            return Err((500, "Could not create the user directory".to_string()));
+        }
+        info!("Created user {} for OIDC subject {}", username, identity.subject);
+        Ok(username.to_string())
+    }
+}
+
+fn check_enabled(username: String, disabled: bool) -> Result<String, (u16, String)> {
+    if disabled {
+        return Err((403, "Account is disabled".to_string()));
+    }
+    Ok(username)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
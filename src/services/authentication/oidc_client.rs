@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use log::error;
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use crate::services::authentication::opaque_token::generate_opaque_token;
+
+/// How long the user has to finish the login at the provider
+pub const PENDING_LOGIN_TTL_SECONDS: i64 = 600;
+/// Bound on unfinished logins, so abandoned ones can't fill the memory
+const MAX_PENDING_LOGINS: usize = 10_000;
+
+/// The OpenID Connect provider and how its users map to local ones, read from the environment
+#[derive(Debug, Clone)]
+pub struct OidcSettings {
+    /// The provider's issuer URL, its configuration is discovered below `/.well-known/openid-configuration`
+    pub issuer: String,
+    pub client_id: String,
+    /// Not needed for public clients, PKCE protects the code exchange
+    pub client_secret: Option<String>,
+    /// Where the provider sends the user back to, it has to end up at `/login/oidc/callback`
+    pub redirect_uri: String,
+    pub scopes: String,
+    /// ID token claim used as the local username when an identity is seen for the first time
+    pub username_claim: String,
+    /// Create a local user for identities that don't match any
+    pub auto_provision: bool,
+    /// Role of auto-provisioned users
+    pub default_role: String,
+    /// Link identities to existing users of the same name. Only safe if the provider
+    /// doesn't let users pick that claim themselves.
+    pub link_existing_users: bool,
+}
+
+impl OidcSettings {
+    /// Reads `OIDC_ISSUER`, `OIDC_CLIENT_ID` and `OIDC_REDIRECT_URI`, and the optional `OIDC_CLIENT_SECRET`,
+    /// `OIDC_SCOPES`, `OIDC_USERNAME_CLAIM`, `OIDC_AUTO_PROVISION`, `OIDC_DEFAULT_ROLE` and
+    /// `OIDC_LINK_EXISTING_USERS` variables. Returns `None` if OIDC is not configured.
+    pub fn from_env() -> Option<Self> {
+        let issuer = env::var("OIDC_ISSUER").ok()?;
+        let flag = |name: &str| {
+            env::var(name)
+                .map(|v| v.parse().unwrap_or_else(|_| panic!("{} must be 'true' or 'false'", name)))
+                .unwrap_or(false)
+        };
+
+        Some(Self {
+            issuer,
+            client_id: env::var("OIDC_CLIENT_ID").expect("OIDC_CLIENT_ID must be set when OIDC_ISSUER is set"),
+            client_secret: env::var("OIDC_CLIENT_SECRET").ok(),
+            redirect_uri: env::var("OIDC_REDIRECT_URI").expect("OIDC_REDIRECT_URI must be set when OIDC_ISSUER is set"),
+            scopes: env::var("OIDC_SCOPES").unwrap_or_else(|_| "openid profile email".to_string()),
+            username_claim: env::var("OIDC_USERNAME_CLAIM").unwrap_or_else(|_| "preferred_username".to_string()),
+            auto_provision: flag("OIDC_AUTO_PROVISION"),
+            default_role: env::var("OIDC_DEFAULT_ROLE").unwrap_or_else(|_| "user".to_string()),
+            link_existing_users: flag("OIDC_LINK_EXISTING_USERS"),
+        })
+    }
+}
+
+/// An identity confirmed by the provider
+#[derive(Debug, Clone, PartialEq)]
+pub struct OidcIdentity {
+    pub issuer: String,
+    pub subject: String,
+    /// Value of the configured username claim, if the provider sent it
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+struct PendingLogin {
+    code_verifier: String,
+    nonce: String,
+    expires_at: i64,
+}
+
+/// Signs users in through the authorization code flow with PKCE, which works with any
+/// spec-compliant provider. The provider's configuration and keys are fetched on first use.
+#[derive(Clone)]
+pub struct OidcClient {
+    settings: Arc<OidcSettings>,
+    http: reqwest::Client,
+    metadata: Arc<Mutex<Option<ProviderMetadata>>>,
+    jwks: Arc<Mutex<Option<JwkSet>>>,
+    pending: Arc<Mutex<HashMap<String, PendingLogin>>>,
+}
+
+impl OidcClient {
+    pub fn new(settings: OidcSettings) -> Self {
+        Self {
+            settings: Arc::new(settings),
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("Failed to build the HTTP client"),
+            metadata: Arc::new(Mutex::new(None)),
+            jwks: Arc::new(Mutex::new(None)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn settings(&self) -> &OidcSettings {
+        &self.settings
+    }
+
+    /// Returns the URL of the provider's login page and the `state` in it, which identifies the login at the callback
+    pub async fn start(&self, now: i64) -> Result<(String, String), (u16, String)> {
+        let metadata = self.metadata().await?;
+
+        let state = generate_opaque_token(16);
+        let nonce = generate_opaque_token(16);
+        let code_verifier = generate_opaque_token(32);
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        let mut url = Url::parse(&metadata.authorization_endpoint)
+            .map_err(|e| (502, format!("Invalid authorization endpoint: {}", e)))?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.settings.client_id)
+            .append_pair("redirect_uri", &self.settings.redirect_uri)
+            .append_pair("scope", &self.settings.scopes)
+            .append_pair("state", &state)
+            .append_pair("nonce", &nonce)
+            .append_pair("code_challenge", &code_challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, login| login.expires_at > now);
+        if pending.len() >= MAX_PENDING_LOGINS {
+            return Err((503, "Too many logins in progress, try again later".to_string()));
+        }
+        pending.insert(state.clone(), PendingLogin {
+            code_verifier,
+            nonce,
+            expires_at: now + PENDING_LOGIN_TTL_SECONDS,
+        });
+
+        Ok((url.to_string(), state))
+    }
+
+    /// Exchanges the code from the callback for an ID token and returns the identity in it
+    pub async fn complete(&self, code: &str, state: &str, now: i64) -> Result<OidcIdentity, (u16, String)> {
+        // Each state can only be used once
+        let login = self.pending
+            .lock()
+            .unwrap()
+            .remove(state)
+            .filter(|login| login.expires_at > now)
+            .ok_or_else(|| (400, "Unknown or expired login, start again".to_string()))?;
+        let metadata = self.metadata().await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.settings.redirect_uri.as_str()),
+            ("client_id", self.settings.client_id.as_str()),
+            ("code_verifier", login.code_verifier.as_str()),
+        ];
+        if let Some(client_secret) = &self.settings.client_secret {
+            form.push(("client_secret", client_secret.as_str()));
+        }
+
+        let response = self.http
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| (502, format!("Could not reach the token endpoint: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            error!("The token endpoint answered {}: {}", status, body);
+            return Err((401, "The provider rejected the login".to_string()));
+        }
+        let tokens: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| (502, format!("Invalid token response: {}", e)))?;
+
+        self.validate_id_token(&tokens.id_token, &login.nonce, &metadata).await
+    }
+
+    async fn validate_id_token(
+        &self,
+        id_token: &str,
+        nonce: &str,
+        metadata: &ProviderMetadata
+    ) -> Result<OidcIdentity, (u16, String)> {
+        let invalid = |e: String| (401, format!("Invalid ID token: {}", e));
+
+        let header = decode_header(id_token).map_err(|e| invalid(e.to_string()))?;
+        // Only keys published by the provider are trusted
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            return Err(invalid(format!("unsupported algorithm {:?}", header.alg)));
+        }
+        let key = self.decoding_key(header.kid.as_deref(), metadata).await?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.settings.client_id]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let claims = decode::<HashMap<String, serde_json::Value>>(id_token, &key, &validation)
+            .map_err(|e| invalid(e.to_string()))?
+            .claims;
+
+        if claims.get("nonce").and_then(|v| v.as_str()) != Some(nonce) {
+            return Err(invalid("nonce mismatch".to_string()));
+        }
+        let subject = claims
+            .get("sub")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| invalid("missing subject".to_string()))?;
+
+        Ok(OidcIdentity {
+            issuer: metadata.issuer.clone(),
+            subject: subject.to_string(),
+            username: claims
+                .get(&self.settings.username_claim)
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+        })
+    }
+
+    /// Looks the key up in the cached key set, re-fetching it once in case the provider rotated its keys
+    async fn decoding_key(&self, kid: Option<&str>, metadata: &ProviderMetadata) -> Result<DecodingKey, (u16, String)> {
+        for refresh in [false, true] {
+            let cached = self.jwks.lock().unwrap().clone();
+            let jwks = match cached {
+                Some(jwks) if !refresh => jwks,
+                _ => {
+                    let jwks: JwkSet = self.fetch_json(&metadata.jwks_uri).await?;
+                    *self.jwks.lock().unwrap() = Some(jwks.clone());
+                    jwks
+                }
+            };
+
+            let jwk = match kid {
+                Some(kid) => jwks.find(kid),
+                // Without a key id the token can only be matched if there is a single key
+                None if jwks.keys.len() == 1 => jwks.keys.first(),
+                None => None,
+            };
+            if let Some(jwk) = jwk {
+                return DecodingKey::from_jwk(jwk).map_err(|e| (502, format!("Invalid provider key: {}", e)));
+            }
+        }
+        Err((401, "Invalid ID token: unknown signing key".to_string()))
+    }
+
+    async fn metadata(&self) -> Result<ProviderMetadata, (u16, String)> {
+        if let Some(metadata) = self.metadata.lock().unwrap().clone() {
+            return Ok(metadata);
+        }
+
+        let issuer = self.settings.issuer.trim_end_matches('/');
+        let metadata: ProviderMetadata = self
+            .fetch_json(&format!("{}/.well-known/openid-configuration", issuer))
+            .await?;
+        if metadata.issuer.trim_end_matches('/') != issuer {
+            return Err((502, format!("The provider claims to be '{}', expected '{}'", metadata.issuer, issuer)));
+        }
+
+        *self.metadata.lock().unwrap() = Some(metadata.clone());
+        Ok(metadata)
+    }
+
+    async fn fetch_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T, (u16, String)> {
+        self.http
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| (502, format!("Could not reach the provider: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| (502, format!("Invalid response from the provider: {}", e)))
+    }
+}
@@ -0,0 +1,15 @@
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Generates a random, URL-safe token made of `byte_len` random bytes encoded as hex.
+pub fn generate_opaque_token(byte_len: usize) -> String {
+    let mut bytes = vec![0u8; byte_len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Hashes an opaque token for storage. Tokens are high-entropy random values,
+/// so a fast hash is enough and, unlike bcrypt, lets us look them up by hash.
+pub fn hash_opaque_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
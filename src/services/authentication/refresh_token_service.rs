@@ -0,0 +1,112 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::warn;
+use crate::dao::refresh_token_store::RefreshTokenStore;
+use crate::models::authentication::refresh_token::RefreshTokenRecord;
+use crate::services::authentication::opaque_token::{generate_opaque_token, hash_opaque_token};
+
+/// Refresh tokens are valid for 30 days
+pub const REFRESH_TOKEN_TTL_SECONDS: i64 = 30 * 24 * 3600;
+
+pub struct RefreshTokenService<T: RefreshTokenStore> {
+    store: T,
+}
+
+impl<T: RefreshTokenStore> RefreshTokenService<T> {
+    pub fn new(store: T) -> Self {
+        Self { store }
+    }
+
+    /// Issues the first refresh token of a new family, used right after a successful login.
+    pub async fn issue(&self, username: &str) -> Result<String, (u16, String)> {
+        let family_id = generate_opaque_token(16);
+        self.issue_in_family(username, &family_id).await
+    }
+
+    /// Exchanges a refresh token for a new one from the same family.
+    /// Returns the username the token belongs to and the new refresh token.
+    ///
+    /// Every refresh token can be used exactly once. Presenting a token that was
+    /// already used means it has leaked, so the whole family gets revoked and the
+    /// user has to log in again.
+    pub async fn rotate(&self, refresh_token: &str) -> Result<(String, String), (u16, String)> {
+        let token_hash = hash_opaque_token(refresh_token);
+
+        let record = match self.store.find_refresh_token(&token_hash).await {
+            Ok(Some(record)) => record,
+            Ok(None) => return Err((401, "Invalid refresh token".to_string())),
+            Err(e) => return Err((500, e)),
+        };
+
+        if record.used || record.revoked {
+            return Err(self.revoke_after_reuse(&record).await);
+        }
+
+        if record.expires_at <= now() {
+            return Err((401, "Refresh token has expired".to_string()));
+        }
+
+        match self.store.mark_refresh_token_used(&token_hash).await {
+            Ok(true) => {}
+            // Lost a race against another request presenting the same token
+            Ok(false) => return Err(self.revoke_after_reuse(&record).await),
+            Err(e) => return Err((500, e)),
+        }
+
+        let new_token = self.issue_in_family(&record.username, &record.family_id).await?;
+        Ok((record.username, new_token))
+    }
+
+    /// Revokes the family of the given token, e.g. on logout.
+    /// Unknown tokens are ignored, since there is nothing to revoke.
+    pub async fn revoke(&self, refresh_token: &str) -> Result<(), (u16, String)> {
+        let token_hash = hash_opaque_token(refresh_token);
+
+        match self.store.find_refresh_token(&token_hash).await {
+            Ok(Some(record)) => self.store
+                .revoke_token_family(&record.family_id)
+                .await
+                .map_err(|e| (500, e)),
+            Ok(None) => Ok(()),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    pub async fn revoke_all(&self, username: &str) -> Result<(), (u16, String)> {
+        self.store.revoke_user_tokens(username).await.map_err(|e| (500, e))
+    }
+
+    async fn issue_in_family(&self, username: &str, family_id: &str) -> Result<String, (u16, String)> {
+        let token = generate_opaque_token(32);
+        let record = RefreshTokenRecord {
+            token_hash: hash_opaque_token(&token),
+            family_id: family_id.to_string(),
+            username: username.to_string(),
+            expires_at: now() + REFRESH_TOKEN_TTL_SECONDS,
+            used: false,
+            revoked: false,
+        };
+
+        match self.store.insert_refresh_token(&record).await {
+            Ok(_) => Ok(token),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    async fn revoke_after_reuse(&self, record: &RefreshTokenRecord) -> (u16, String) {
+        warn!(
+            "Refresh token reuse detected for user '{}', revoking token family",
+            record.username
+        );
+        if let Err(e) = self.store.revoke_token_family(&record.family_id).await {
+            return (500, e);
+        }
+        (401, "Refresh token has already been used".to_string())
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use log::error;
+use crate::dao::token_revocation_store::TokenRevocationStore;
+use crate::services::authentication::authentication_service::Claims;
+
+/// Keeps track of revoked access tokens.
+///
+/// Revocations are persisted through the store and mirrored in memory,
+/// so checking a token on every request never has to hit the database.
+#[derive(Clone)]
+pub struct TokenRevocationService {
+    store: Arc<dyn TokenRevocationStore>,
+    /// Revoked token ids mapped to their expiration
+    revoked_tokens: Arc<RwLock<HashMap<String, i64>>>,
+    /// Tokens of a user issued before this timestamp are revoked
+    user_cutoffs: Arc<RwLock<HashMap<String, i64>>>,
+}
+
+impl TokenRevocationService {
+    pub fn new(store: Arc<dyn TokenRevocationStore>) -> Self {
+        Self {
+            store,
+            revoked_tokens: Arc::new(RwLock::new(HashMap::new())),
+            user_cutoffs: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Replaces the in-memory cache with the revocations from the store
+    pub async fn reload(&self) -> Result<(), String> {
+        let revoked_tokens = self.store.load_revoked_tokens(now()).await?;
+        let user_cutoffs = self.store.load_user_cutoffs().await?;
+
+        *self.revoked_tokens.write().unwrap() = revoked_tokens.into_iter().collect();
+        *self.user_cutoffs.write().unwrap() = user_cutoffs.into_iter().collect();
+        Ok(())
+    }
+
+    /// Periodically reloads the cache, so that revocations made by other
+    /// server instances sharing the database are picked up as well
+    pub fn spawn_periodic_reload(&self, interval: Duration) {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = service.reload().await {
+                    error!("Failed to reload revoked tokens: {}", e);
+                }
+            }
+        });
+    }
+
+    /// A token is also revoked together with the session it was derived from
+    pub fn is_revoked(&self, claims: &Claims) -> bool {
+        let revoked_tokens = self.revoked_tokens.read().unwrap();
+        if revoked_tokens.contains_key(&claims.jti)
+            || claims.parent_jti.as_ref().is_some_and(|parent| revoked_tokens.contains_key(parent)) {
+            return true;
+        }
+        drop(revoked_tokens);
+
+        match self.user_cutoffs.read().unwrap().get(&claims.sub) {
+            // `iat` only has seconds, a token issued in the second of the cutoff may be older than it
+            Some(cutoff) => (claims.iat as i64) <= *cutoff,
+            None => false,
+        }
+    }
+
+    /// Keeps the token id revoked until `expires_at`
+    pub async fn revoke_token(&self, jti: &str, expires_at: i64) -> Result<(), String> {
+        self.store.revoke_token(jti, expires_at).await?;
+        self.revoked_tokens.write().unwrap().insert(jti.to_string(), expires_at);
+        Ok(())
+    }
+
+    /// Revokes every token issued to the user up until now, including the current second
+    pub async fn revoke_all_user_tokens(&self, username: &str) -> Result<(), String> {
+        let cutoff = now();
+        self.store.revoke_all_user_tokens(username, cutoff).await?;
+        self.user_cutoffs.write().unwrap().insert(username.to_string(), cutoff);
+        Ok(())
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
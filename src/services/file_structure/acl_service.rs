@@ -0,0 +1,181 @@
+use std::sync::Arc;
+use crate::dao::acl_store::AclStore;
+use crate::models::acl::acl_entry::{AclEntry, AclPermission, AclPrincipal};
+use crate::models::acl::acl_requests::AclView;
+
+/// Evaluates and edits the ACLs of the trees. Permissions are additive: a user has every
+/// permission of the entries on the path and of the inheriting entries on the paths above it
+/// that name the user or one of their groups.
+#[derive(Clone)]
+pub struct AclService {
+    store: Arc<dyn AclStore>,
+}
+
+impl AclService {
+    pub fn new(store: Arc<dyn AclStore>) -> Self {
+        Self { store }
+    }
+
+    pub async fn effective_permissions(
+        &self,
+        username: &str,
+        tree: &str,
+        path: &str
+    ) -> Result<Vec<AclPermission>, (u16, String)> {
+        let path = normalize_acl_path(path)?;
+        let entries = self.store
+            .get_entries(tree, &ancestor_paths(&path))
+            .await
+            .map_err(|e| (500, e))?;
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let groups = self.store.get_user_groups(username).await.map_err(|e| (500, e))?;
+
+        let mut permissions = Vec::new();
+        for entry in entries {
+            let applies = entry.path == path || entry.inherit;
+            let names_user = match &entry.principal {
+                AclPrincipal::User(name) => name == username,
+                AclPrincipal::Group(name) => groups.contains(name),
+            };
+            if applies && names_user {
+                for permission in entry.permissions {
+                    if !permissions.contains(&permission) {
+                        permissions.push(permission);
+                    }
+                }
+            }
+        }
+        Ok(permissions)
+    }
+
+    pub async fn check(
+        &self,
+        username: &str,
+        tree: &str,
+        path: &str,
+        permission: AclPermission
+    ) -> Result<(), (u16, String)> {
+        if self.effective_permissions(username, tree, path).await?.contains(&permission) {
+            return Ok(());
+        }
+        Err((403, format!("You don't have {} permission on '{}'", permission.as_str(), path)))
+    }
+
+    pub async fn view(&self, tree: &str, path: &str) -> Result<AclView, (u16, String)> {
+        let path = normalize_acl_path(path)?;
+        let entries = self.store
+            .get_entries(tree, &ancestor_paths(&path))
+            .await
+            .map_err(|e| (500, e))?;
+
+        let (entries, inherited) = entries.into_iter().partition(|entry| entry.path == path);
+        Ok(AclView {
+            path,
+            entries,
+            inherited: inherited.into_iter().filter(|entry: &AclEntry| entry.inherit).collect(),
+        })
+    }
+
+    pub async fn set_entry(&self, tree: &str, mut entry: AclEntry) -> Result<String, (u16, String)> {
+        entry.path = normalize_acl_path(&entry.path)?;
+        if entry.permissions.is_empty() {
+            return Err((400, "An entry needs at least one permission, remove it instead".to_string()));
+        }
+
+        match self.store.set_entry(tree, &entry).await {
+            Ok(_) => Ok(format!("Updated the access of {} '{}' to '{}'.", entry.principal.kind(), entry.principal.name(), entry.path)),
+            Err(e) if e == "Principal not found" => Err((404, format!(
+                "No {} named '{}'", entry.principal.kind(), entry.principal.name()
+            ))),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    pub async fn remove_entry(&self, tree: &str, path: &str, principal: &AclPrincipal) -> Result<String, (u16, String)> {
+        let path = normalize_acl_path(path)?;
+        match self.store.remove_entry(tree, &path, principal).await {
+            Ok(true) => Ok(format!("Removed the access of {} '{}' to '{}'.", principal.kind(), principal.name(), path)),
+            Ok(false) => Err((404, format!(
+                "There is no entry for {} '{}' on '{}'", principal.kind(), principal.name(), path
+            ))),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    /// Keeps the entries of a renamed file or directory
+    pub async fn move_path(&self, tree: &str, old_path: &str, new_path: &str) -> Result<(), (u16, String)> {
+        let old_path = normalize_acl_path(old_path)?;
+        let new_path = normalize_acl_path(new_path)?;
+        self.store.move_entries(tree, &old_path, &new_path).await.map_err(|e| (500, e))
+    }
+
+    /// Drops the entries of a deleted file or directory
+    pub async fn delete_path(&self, tree: &str, path: &str) -> Result<(), (u16, String)> {
+        let path = normalize_acl_path(path)?;
+        self.store.delete_entries(tree, &path).await.map_err(|e| (500, e))
+    }
+}
+
+/// The ACL of a tree applied to one user. File services check every operation against it when
+/// the user is not allowed into the tree as a whole.
+#[derive(Clone)]
+pub struct AclCheck {
+    service: AclService,
+    username: String,
+}
+
+impl AclCheck {
+    pub fn new(service: AclService, username: String) -> Self {
+        Self { service, username }
+    }
+
+    pub async fn check(&self, tree: &str, path: &str, permission: AclPermission) -> Result<(), (u16, String)> {
+        self.service.check(&self.username, tree, path, permission).await
+    }
+}
+
+/// Passes if there is no ACL to check
+pub async fn authorize(
+    acl: &Option<AclCheck>,
+    tree: &str,
+    path: &str,
+    permission: AclPermission
+) -> Result<(), (u16, String)> {
+    match acl {
+        Some(acl) => acl.check(tree, path, permission).await,
+        None => Ok(()),
+    }
+}
+
+/// Brings a path of a request into the form entries are stored with: relative to the
+/// tree, separated by single slashes and without `.` components
+pub fn normalize_acl_path(path: &str) -> Result<String, (u16, String)> {
+    let mut components = Vec::new();
+    for component in path.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => return Err((400, "Invalid path: directory traversal detected.".to_string())),
+            component => components.push(component),
+        }
+    }
+    Ok(components.join("/"))
+}
+
+/// The path and every path above it, up to the tree itself
+fn ancestor_paths(path: &str) -> Vec<String> {
+    let mut paths = vec![String::new()];
+    if path.is_empty() {
+        return paths;
+    }
+    let mut current = String::new();
+    for component in path.split('/') {
+        if !current.is_empty() {
+            current.push('/');
+        }
+        current.push_str(component);
+        paths.push(current.clone());
+    }
+    paths
+}
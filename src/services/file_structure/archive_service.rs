@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use flate2::read::GzDecoder;
+use log::info;
+use tempfile::TempDir;
+use crate::services::file_structure::file_service::{StagedFile, STAGING_DIR};
+
+/// Prefix of the folders archives are extracted to in the staging folder
+pub const EXTRACT_DIR_PREFIX: &str = "extract-";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveLimits {
+    /// Largest total size of the extracted files, however well the archive compresses them
+    pub max_size: u64,
+    /// Largest number of entries, files and directories
+    pub max_entries: usize,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_size: 1024 * 1024 * 1024,
+            max_entries: 10_000,
+        }
+    }
+}
+
+/// Room for the headers of an entry: a tar header with the padding of its data, or the zip
+/// headers of an entry with a long name
+const ENTRY_OVERHEAD: u64 = 4096;
+/// The end of a tar archive, padded to a full record
+const TAR_END_OVERHEAD: u64 = 10240;
+
+impl ArchiveLimits {
+    /// The largest archive that can be extracted within the limits, even without compression.
+    /// Uploads of larger ones are stopped while they are received.
+    pub fn max_archive_size(&self) -> u64 {
+        self.max_size
+            .saturating_add((self.max_entries as u64).saturating_mul(ENTRY_OVERHEAD))
+            .saturating_add(TAR_END_OVERHEAD)
+    }
+
+    /// Reads the optional `ARCHIVE_MAX_SIZE` and `ARCHIVE_MAX_ENTRIES` variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_size: env::var("ARCHIVE_MAX_SIZE")
+                .map(|v| v.parse().expect("ARCHIVE_MAX_SIZE must be a valid integer"))
+                .unwrap_or(defaults.max_size),
+            max_entries: env::var("ARCHIVE_MAX_ENTRIES")
+                .map(|v| v.parse().expect("ARCHIVE_MAX_ENTRIES must be a valid integer"))
+                .unwrap_or(defaults.max_entries),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+/// The files of an archive, extracted to a folder of the staging folder. What is not taken is
+/// deleted together with the folder when this is dropped.
+pub struct ExtractedArchive {
+    dir: TempDir,
+    /// The paths of the files inside the archive with their sizes, sorted by path
+    pub files: Vec<(String, u64)>,
+}
+
+impl ExtractedArchive {
+    /// Hands over an extracted file, to be moved into place like an upload
+    pub fn take_file(&self, name: &str, size: u64) -> io::Result<StagedFile> {
+        StagedFile::from_path(self.dir.path().join(name), size)
+    }
+}
+
+/// Extracts uploaded zip, tar and tar.gz archives. Every entry has to stay inside the folder it is
+/// extracted to, and the number of entries and the size of the extracted files are limited, so an
+/// archive that unpacks to far more than it weighs can't fill up the disk.
+pub struct ArchiveService {
+    root_dir: String,
+    limits: ArchiveLimits,
+}
+
+impl ArchiveService {
+    pub fn new(root_dir: String, limits: ArchiveLimits) -> Self {
+        Self { root_dir, limits }
+    }
+
+    /// Extracts the archive to the staging folder. The format is told by the content, not the name.
+    /// Links and other special entries are skipped, empty directories are not kept.
+    pub async fn extract(&self, archive: StagedFile) -> Result<ExtractedArchive, (u16, String)> {
+        let staging_dir = Path::new(&self.root_dir).join(STAGING_DIR);
+        if let Err(e) = tokio::fs::create_dir_all(&staging_dir).await {
+            return Err((500, format!("Error creating the staging folder: {}", e)));
+        }
+        let dir = tempfile::Builder::new()
+            .prefix(EXTRACT_DIR_PREFIX)
+            .tempdir_in(&staging_dir)
+            .map_err(|e| (500, format!("Error creating the extraction folder: {}", e)))?;
+
+        // The archive libraries only read synchronously
+        let limits = self.limits.clone();
+        let target = dir.path().to_path_buf();
+        let files = tokio::task::spawn_blocking(move || {
+            let mut extractor = Extractor { target, limits, entries: 0, extracted: 0, files: BTreeMap::new() };
+            extractor.extract(archive.path())?;
+            Ok::<_, (u16, String)>(extractor.files)
+        })
+            .await
+            .map_err(|e| (500, format!("Error extracting the archive: {}", e)))??;
+
+        info!("Extracted {} files from an archive", files.len());
+        Ok(ExtractedArchive { dir, files: files.into_iter().collect() })
+    }
+}
+
+/// Writes the entries of an archive below `target`, keeping count of the limits
+struct Extractor {
+    target: PathBuf,
+    limits: ArchiveLimits,
+    entries: usize,
+    /// Bytes written so far, including files replaced by later entries of the same name
+    extracted: u64,
+    files: BTreeMap<String, u64>,
+}
+
+impl Extractor {
+    fn extract(&mut self, archive_path: &Path) -> Result<(), (u16, String)> {
+        let mut archive = File::open(archive_path)
+            .map_err(|e| (500, format!("Error opening the archive: {}", e)))?;
+        match detect_format(&mut archive)? {
+            ArchiveFormat::Zip => self.extract_zip(archive),
+            ArchiveFormat::Tar => self.extract_tar(archive),
+            ArchiveFormat::TarGz => self.extract_tar(GzDecoder::new(archive)),
+        }
+    }
+
+    fn extract_zip(&mut self, archive: File) -> Result<(), (u16, String)> {
+        let mut zip = zip::ZipArchive::new(archive).map_err(|e| invalid_archive(e.to_string()))?;
+        // The central directory tells the number of entries before anything is extracted
+        if zip.len() > self.limits.max_entries {
+            return Err(too_many_entries(self.limits.max_entries));
+        }
+        for index in 0..zip.len() {
+            let mut entry = zip.by_index(index).map_err(|e| invalid_archive(e.to_string()))?;
+            let name = entry.name().to_string();
+            let is_link = entry.unix_mode().is_some_and(|mode| mode & 0o170000 == 0o120000);
+            let kind = match (entry.is_dir(), is_link) {
+                (true, _) => EntryKind::Directory,
+                (false, true) => EntryKind::Other,
+                (false, false) => EntryKind::File,
+            };
+            self.extract_entry(&name, kind, &mut entry)?;
+        }
+        Ok(())
+    }
+
+    /// The tar library holds long names and extension headers in memory, so what it reads is capped
+    /// as a whole like an upload of the archive would be, after decompressing
+    fn extract_tar<R: Read>(&mut self, archive: R) -> Result<(), (u16, String)> {
+        let max_size = self.limits.max_archive_size();
+        let mut tar = tar::Archive::new(archive.take(max_size.saturating_add(1)));
+        let result = self.extract_tar_entries(&mut tar);
+        if tar.into_inner().limit() == 0 {
+            return Err((413, format!("The archive is larger than {} bytes", max_size)));
+        }
+        result
+    }
+
+    fn extract_tar_entries<R: Read>(&mut self, tar: &mut tar::Archive<R>) -> Result<(), (u16, String)> {
+        let entries = tar.entries().map_err(|e| invalid_archive(e.to_string()))?;
+        for entry in entries {
+            let mut entry = entry.map_err(|e| invalid_archive(e.to_string()))?;
+            let name = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+            let entry_type = entry.header().entry_type();
+            let kind = if entry_type.is_file() {
+                EntryKind::File
+            } else if entry_type.is_dir() {
+                EntryKind::Directory
+            } else if entry_type.is_pax_global_extensions() || entry_type.is_pax_local_extensions() {
+                // Metadata for the entries, read by the tar library itself
+                continue;
+            } else {
+                EntryKind::Other
+            };
+            self.extract_entry(&name, kind, &mut entry)?;
+        }
+        Ok(())
+    }
+
+    fn extract_entry(&mut self, name: &str, kind: EntryKind, content: &mut impl Read) -> Result<(), (u16, String)> {
+        self.entries += 1;
+        if self.entries > self.limits.max_entries {
+            return Err(too_many_entries(self.limits.max_entries));
+        }
+        // Checked for every entry, also the ones that are skipped, so a bad archive is refused as a whole
+        let Some(path) = entry_path(name) else {
+            return Err((400, format!("The archive entry '{}' points outside of the folder", name)));
+        };
+        if kind != EntryKind::File {
+            return Ok(());
+        }
+        if path.is_empty() {
+            return Err(invalid_archive(format!("the file '{}' has no name", name)));
+        }
+
+        let abs_path = self.target.join(&path);
+        if let Some(parent) = abs_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| invalid_archive(format!("{}: {}", path, e)))?;
+        }
+        let mut file = File::create(&abs_path).map_err(|e| invalid_archive(format!("{}: {}", path, e)))?;
+
+        // Counts what is actually decompressed, the sizes in the headers can lie
+        let budget = self.limits.max_size.saturating_sub(self.extracted);
+        let size = io::copy(&mut content.take(budget + 1), &mut file)
+            .map_err(|e| invalid_archive(format!("{}: {}", path, e)))?;
+        if size > budget {
+            return Err((413, format!("The archive is larger than {} bytes when extracted", self.limits.max_size)));
+        }
+        file.sync_all().map_err(|e| (500, format!("Error writing {}: {}", path, e)))?;
+        self.extracted += size;
+        // A later entry of the same name replaces an earlier one
+        self.files.insert(path, size);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EntryKind {
+    File,
+    Directory,
+    /// Links, devices and the like, which are never extracted
+    Other,
+}
+
+/// The path of an entry inside the folder, or `None` if it is absolute or climbs out of it.
+/// Entries like `./` are the folder itself and have an empty path.
+fn entry_path(name: &str) -> Option<String> {
+    let has_drive = name.len() >= 2 && name.as_bytes()[1] == b':' && name.as_bytes()[0].is_ascii_alphabetic();
+    if name.starts_with(['/', '\\']) || has_drive {
+        return None;
+    }
+    let components = name
+        .split(['/', '\\'])
+        .filter(|component| !component.is_empty() && *component != ".")
+        .collect::<Vec<_>>();
+    if components.contains(&"..") {
+        return None;
+    }
+    Some(components.join("/"))
+}
+
+/// Tells the format by the first bytes of the archive
+fn detect_format(archive: &mut File) -> Result<ArchiveFormat, (u16, String)> {
+    let mut header = [0u8; 262];
+    let read = read_up_to(archive, &mut header).map_err(|e| (500, format!("Error reading the archive: {}", e)))?;
+    archive.seek(SeekFrom::Start(0)).map_err(|e| (500, format!("Error reading the archive: {}", e)))?;
+
+    let header = &header[..read];
+    if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+        Ok(ArchiveFormat::Zip)
+    } else if header.starts_with(&[0x1f, 0x8b]) {
+        Ok(ArchiveFormat::TarGz)
+    } else if header.len() >= 262 && &header[257..262] == b"ustar" {
+        Ok(ArchiveFormat::Tar)
+    } else {
+        Err((415, "Only zip, tar and tar.gz archives can be extracted".to_string()))
+    }
+}
+
+fn read_up_to(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buffer.len() {
+        match reader.read(&mut buffer[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
+
+fn invalid_archive(msg: String) -> (u16, String) {
+    (400, format!("Invalid archive: {}", msg))
+}
+
+fn too_many_entries(max_entries: usize) -> (u16, String) {
+    (413, format!("The archive has more than {} entries", max_entries))
+}
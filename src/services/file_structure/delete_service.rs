@@ -1,19 +1,28 @@
 use std::path::Path;
 use std::sync::Arc;
+use crate::models::acl::acl_entry::AclPermission;
+use crate::services::file_structure::acl_service::{authorize, AclCheck};
 use crate::services::file_structure::path_service::PathService;
 use crate::services::locking::directory_locking_manager::DirectoryLockManager;
 
 pub struct DeleteService {
     root_dir: String,
-    directory_lock_manager: DirectoryLockManager
+    directory_lock_manager: DirectoryLockManager,
+    acl: Option<AclCheck>
 }
 
 impl DeleteService {
     pub fn new(root_dir: String, directory_lock_manager: DirectoryLockManager) -> Self {
         Self { 
-            root_dir, directory_lock_manager
+            root_dir, directory_lock_manager, acl: None
         }
     }
+
+    /// Checks every deletion against the ACL of the tree
+    pub fn with_acl(mut self, acl: Option<AclCheck>) -> Self {
+        self.acl = acl;
+        self
+    }
     
     pub async fn delete_directory(
         &self,
@@ -21,6 +30,8 @@ impl DeleteService {
         path: &String, 
         dir_name: &String
     ) -> Result<String, (u16, String)> {
+        authorize(&self.acl, username, &format!("{}/{}", path, dir_name), AclPermission::Delete).await?;
+
         // Construct the path to the directory
         let dir_path = Path::new(&self.root_dir)
             .join(username)
@@ -71,6 +82,8 @@ impl DeleteService {
         path: &String, 
         filename: &String
     ) -> Result<String, (u16, String)> {
+        authorize(&self.acl, username, &format!("{}/{}", path, filename), AclPermission::Delete).await?;
+
         // Construct the path to the file
         let dir_path = Path::new(&self.root_dir)
             .join(username)
@@ -3,15 +3,22 @@ use std::path::{Path, PathBuf};
 use std::{fs, io};
 use log::error;
 use crate::models::file_structure::directory_tree::DirTree;
+use crate::models::shares::share::Share;
 use zip::ZipWriter;
 use zip::write::FileOptions;
 use walkdir::WalkDir;
+use crate::models::acl::acl_entry::AclPermission;
+use crate::services::file_structure::acl_service::{authorize, AclCheck};
 use crate::services::file_structure::path_service::PathService;
+use crate::services::file_structure::share_service::SHARED_WITH_ME;
 use crate::services::locking::directory_locking_manager::DirectoryLockManager;
 
 pub struct DirectoryService {
     root_dir: String,
-    directory_lock_manager: DirectoryLockManager
+    directory_lock_manager: DirectoryLockManager,
+    acl: Option<AclCheck>,
+    /// Shown under the `Shared with me` node of the root
+    shared: Vec<Share>
 }
 
 impl DirectoryService {
@@ -19,10 +26,50 @@ impl DirectoryService {
     pub fn new(root_dir: String, directory_lock_manager: DirectoryLockManager) -> Self {
         Self {
             root_dir,
-            directory_lock_manager
+            directory_lock_manager,
+            acl: None,
+            shared: Vec::new()
         }
     }
 
+    /// Checks every operation against the ACL of the tree
+    pub fn with_acl(mut self, acl: Option<AclCheck>) -> Self {
+        self.acl = acl;
+        self
+    }
+
+    /// What was shared with the user whose tree is built
+    pub fn with_shared(mut self, shared: Vec<Share>) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    /// The `Shared with me` node: shared files, and shared directories with their contents
+    pub fn build_shared_tree(&self) -> DirTree {
+        let mut files = Vec::new();
+        let mut dirs = Vec::new();
+        for share in &self.shared {
+            if !share.is_dir {
+                files.push(share.name.clone());
+                continue;
+            }
+            match self.build_dir_tree(&share.owner, Path::new(&share.path)) {
+                Ok(tree) => dirs.push(DirTree { name: share.name.clone(), ..tree }),
+                Err(e) => error!("Could not read share {} of {}: {}", share.name, share.owner, e),
+            }
+        }
+
+        DirTree { name: SHARED_WITH_ME.to_string(), files, dirs }
+    }
+
+    /// `build_dir_tree` for requests, it needs read permission on the directory
+    pub async fn read_dir_tree(&self, user: &String, path: &Path) -> Result<DirTree, (u16, String)> {
+        authorize(&self.acl, user, &path.to_string_lossy(), AclPermission::Read).await?;
+
+        self.build_dir_tree(user, path)
+            .map_err(|err| (404, format!("Error reading directory: {}", err)))
+    }
+
     pub fn build_dir_tree(&self, user: &String, path: &Path) -> io::Result<DirTree> {
         // Construct the full path from user + path
         let full_path = Path::new(&self.root_dir).join(user).join(path);
@@ -55,6 +102,10 @@ impl DirectoryService {
             }
         }
 
+        if path.as_os_str().is_empty() && !self.shared.is_empty() {
+            dirs.push(self.build_shared_tree());
+        }
+
         Ok(DirTree { name, files, dirs })
     }
 
@@ -64,6 +115,8 @@ impl DirectoryService {
         path: &String,
         name: &String
     ) -> Result<String, (u16, String)> {
+        authorize(&self.acl, user, &format!("{}/{}", path, name), AclPermission::Write).await?;
+
         let path = Path::new(&self.root_dir)
             .join(user)
             .join(path)
@@ -93,7 +146,17 @@ impl DirectoryService {
         }
     }
 
-    pub async fn download_directory_streamed(&self, dir_path: PathBuf) -> Result<Vec<u8>, (u16, String)> {
+    /// Zips the directory. Files the ACL doesn't let the user read are left out.
+    pub async fn download_directory_streamed(
+        &self,
+        user: &str,
+        path: &str,
+        name: &str
+    ) -> Result<Vec<u8>, (u16, String)> {
+        let relative_dir = format!("{}/{}", path, name);
+        authorize(&self.acl, user, &relative_dir, AclPermission::Read).await?;
+
+        let dir_path = Path::new(&self.root_dir).join(user).join(path).join(name);
         let path_service = PathService::new();
         let canonical = match path_service.canonicalize_path(&dir_path).await {
             Ok(res) => res,
@@ -107,6 +170,21 @@ impl DirectoryService {
 
         let lock_arc = self.directory_lock_manager.lock_for_path(canonical.clone()).await;
         let _guard = lock_arc.lock().await;
+
+        let mut files = Vec::new();
+        for entry in WalkDir::new(&dir_path) {
+            let entry = entry.unwrap();
+            if entry.path().is_file() {
+                let relative_path = entry.path().strip_prefix(&dir_path).unwrap().to_path_buf();
+                if self.acl.is_some() {
+                    let acl_path = format!("{}/{}", relative_dir, relative_path.to_string_lossy());
+                    if authorize(&self.acl, user, &acl_path, AclPermission::Read).await.is_err() {
+                        continue;
+                    }
+                }
+                files.push(relative_path);
+            }
+        }
         
         let mut temp_file = tempfile::NamedTempFile::new().unwrap();
 
@@ -115,17 +193,12 @@ impl DirectoryService {
             let mut zip = ZipWriter::new(&mut temp_file);
             let options = FileOptions::default();
 
-            for entry in WalkDir::new(&dir_path) {
-                let entry = entry.unwrap();
-                let file_path = entry.path();
-                if file_path.is_file() {
-                    let relative_path = file_path.strip_prefix(&dir_path).unwrap();
-                    let name_in_zip = relative_path.to_string_lossy();
+            for relative_path in files {
+                let name_in_zip = relative_path.to_string_lossy();
 
-                    zip.start_file(name_in_zip, options).unwrap();
-                    let bytes = fs::read(file_path).unwrap();
-                    zip.write_all(&bytes).unwrap();
-                }
+                zip.start_file(name_in_zip, options).unwrap();
+                let bytes = fs::read(dir_path.join(&relative_path)).unwrap();
+                zip.write_all(&bytes).unwrap();
             }
             zip.finish().unwrap();
         }
@@ -136,4 +209,4 @@ impl DirectoryService {
 
         Ok(data)
     }
-}
\ No newline at end of file
+}
@@ -1,52 +1,306 @@
+use std::fmt::Display;
+use std::io::ErrorKind;
 use std::path::{Path, PathBuf};
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
+use std::time::UNIX_EPOCH;
+use futures_util::{Stream, TryStreamExt};
+use log::{error, info, warn};
+use tempfile::TempPath;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use crate::models::acl::acl_entry::AclPermission;
+use crate::models::system_operations::conflict_policy::ConflictPolicy;
+use crate::services::file_structure::acl_service::{authorize, AclCheck};
+use crate::services::file_structure::archive_service::EXTRACT_DIR_PREFIX;
 use crate::services::file_structure::path_service::PathService;
 use crate::services::locking::directory_locking_manager::DirectoryLockManager;
 
+/// Uploads are streamed into this folder of the root directory first. It is on the same filesystem
+/// as the user and group folders, so finished uploads are moved into place atomically.
+pub const STAGING_DIR: &str = "_staging";
+/// How many numbered names are tried for a file that should not replace another
+const MAX_NAME_ATTEMPTS: usize = 1000;
+
+/// An upload in the staging folder. The file is deleted when this is dropped without being committed.
+#[derive(Debug)]
+pub struct StagedFile {
+    temp_path: TempPath,
+    pub size: u64,
+}
+
+impl StagedFile {
+    /// Takes over a file of the staging folder that was written some other way
+    pub(crate) fn from_path(path: PathBuf, size: u64) -> std::io::Result<Self> {
+        Ok(Self { temp_path: TempPath::try_from_path(path)?, size })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.temp_path
+    }
+
+    /// The ETag the file has once it is stored, moving it keeps its metadata
+    pub(crate) async fn etag(&self) -> std::io::Result<String> {
+        file_etag(&self.temp_path).await
+    }
+
+    /// Moves the file to `abs_path`, replacing what is there. The directory is synced afterwards,
+    /// so the new name survives a crash.
+    pub(crate) async fn persist(self, abs_path: &Path) -> std::io::Result<()> {
+        tokio::fs::rename(&self.temp_path, abs_path).await?;
+        // The file was moved, so there is nothing left to delete
+        let _ = self.temp_path.keep();
+        // The file is in place either way. Failing now would make callers clean up a stored file.
+        if let Some(parent) = abs_path.parent() {
+            if let Err(e) = sync_dir(parent).await {
+                warn!("Could not sync {:?} after storing {:?}: {}", parent, abs_path, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The ETag of a file, built from its size, modification time and inode. Every upload is moved
+/// into place as a new file, so a stored version keeps its ETag until it is replaced or changed.
+pub fn metadata_etag(metadata: &std::fs::Metadata) -> String {
+    let modified = metadata.modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_nanos())
+        .unwrap_or(0);
+    format!("\"{:x}-{:x}-{:x}\"", metadata.len(), modified, inode(metadata))
+}
+
+#[cfg(unix)]
+fn inode(metadata: &std::fs::Metadata) -> u64 {
+    std::os::unix::fs::MetadataExt::ino(metadata)
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &std::fs::Metadata) -> u64 {
+    0
+}
+
+/// A part of an uploaded name that stays inside its folder on every file system
+fn is_safe_component(component: &str) -> bool {
+    let bytes = component.as_bytes();
+    let has_drive = bytes.len() >= 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic();
+    component != ".." && !has_drive && !component.chars().any(char::is_control)
+}
+
+/// `metadata_etag` of the file at `abs_path`, without reading its content
+pub(crate) async fn file_etag(abs_path: &Path) -> std::io::Result<String> {
+    Ok(metadata_etag(&tokio::fs::metadata(abs_path).await?))
+}
+
+/// Creates an empty file named `name`, or `name (1)`, `name (2)`, ... if it is taken, for a write
+/// to replace. Creating fails instead of overwriting, so concurrent writes of the same name don't
+/// clobber each other.
+pub(crate) async fn reserve_free_name(dir: &Path, name: &str) -> Result<String, (u16, String)> {
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{}", extension)),
+        _ => (name, String::new()),
+    };
+    for attempt in 0..MAX_NAME_ATTEMPTS {
+        let candidate = match attempt {
+            0 => name.to_string(),
+            _ => format!("{} ({}){}", stem, attempt, extension),
+        };
+        match OpenOptions::new().write(true).create_new(true).open(dir.join(&candidate)).await {
+            Ok(_) => return Ok(candidate),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err((500, format!("Error creating file {}: {}", candidate, e))),
+        }
+    }
+    Err((409, format!("Too many files named '{}'", name)))
+}
+
+/// Flushes the entries of a directory to disk, e.g. after a rename. Directories can't be opened
+/// as files on Windows, so it is a no-op there.
+#[cfg(unix)]
+pub(crate) async fn sync_dir(dir: &Path) -> std::io::Result<()> {
+    File::open(dir).await?.sync_all().await
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn sync_dir(_dir: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
 pub struct FileService {
     root_dir: String,
-    directory_lock_manager: DirectoryLockManager
+    directory_lock_manager: DirectoryLockManager,
+    acl: Option<AclCheck>
 }
 
 impl FileService {
     pub fn new(root_dir: String, directory_lock_manager: DirectoryLockManager) -> Self {
-        Self { root_dir, directory_lock_manager }
+        Self { root_dir, directory_lock_manager, acl: None }
+    }
+
+    /// Checks every file operation against the ACL of the tree
+    pub fn with_acl(mut self, acl: Option<AclCheck>) -> Self {
+        self.acl = acl;
+        self
     }
 
-    pub fn sanitize_filename(&self, name: &str) -> String {
-        name.chars()
-            .filter(|c| *c != '/' && *c != '\\')
-            .collect()
+    /// For callers that work with absolute paths, `path` is relative to the tree
+    pub(crate) async fn check_access(
+        &self,
+        user_name: &str,
+        path: &str,
+        permission: AclPermission
+    ) -> Result<(), (u16, String)> {
+        authorize(&self.acl, user_name, path, permission).await
     }
 
-    pub(crate) async fn save_file_bytes_to_root_directory(
+    /// Splits the name of an uploaded file into the directories it is in and the filename. Files of
+    /// an uploaded folder carry their path inside the folder, like `holiday/day1/photo.jpg`. Returns
+    /// `None` for empty names, names that would leave the folder and names with a drive like `C:` or
+    /// control characters in any part.
+    pub fn sanitize_relative_path(&self, name: &str) -> Option<(String, String)> {
+        let mut components = name
+            .split(['/', '\\'])
+            .filter(|component| !component.is_empty() && *component != ".")
+            .collect::<Vec<_>>();
+        if !components.iter().all(|component| is_safe_component(component)) {
+            return None;
+        }
+        let filename = components.pop()?.to_string();
+        Some((components.join("/"), filename))
+    }
+
+    /// Streams an upload into a temporary file of the staging folder, so it never has to fit into
+    /// memory. Fails with `413` once the upload grows beyond `max_size`.
+    pub(crate) async fn stage_upload<S, B, E>(
         &self,
-        abs_path: &PathBuf,
-        file_bytes: &[u8],
-    ) -> Result<String, (u16, String)> {
-        
-        // Create (or overwrite) the file asynchronously
-        let mut file = match File::create(&abs_path).await {
-            Ok(f) => f,
-            Err(e) => {
-                return Err((500, format!(
-                    "Error creating file at {:?}: {}",
-                    abs_path, e
-                )));
+        mut stream: S,
+        max_size: Option<u64>
+    ) -> Result<StagedFile, (u16, String)>
+    where
+        S: Stream<Item = Result<B, E>> + Unpin,
+        B: AsRef<[u8]>,
+        E: Display,
+    {
+        let staging_dir = Path::new(&self.root_dir).join(STAGING_DIR);
+        if let Err(e) = tokio::fs::create_dir_all(&staging_dir).await {
+            return Err((500, format!("Error creating the staging folder: {}", e)));
+        }
+        let (file, temp_path) = match tempfile::Builder::new().prefix("upload-").tempfile_in(&staging_dir) {
+            Ok(temp_file) => temp_file.into_parts(),
+            Err(e) => return Err((500, format!("Error creating a staging file: {}", e))),
+        };
+
+        // From here on the staging file is deleted when `temp_path` is dropped, also when the
+        // client disconnects and this future is dropped mid-upload
+        let mut file = File::from_std(file);
+        let mut size = 0u64;
+        loop {
+            let chunk = match stream.try_next().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(e) => return Err((400, format!("The upload was interrupted: {}", e))),
+            };
+            size += chunk.as_ref().len() as u64;
+            if max_size.is_some_and(|max_size| size > max_size) {
+                return Err((413, "The file is too large".to_string()));
+            }
+            if let Err(e) = file.write_all(chunk.as_ref()).await {
+                return Err((500, format!("Error writing to the staging file: {}", e)));
             }
+        }
+        // On disk before it can replace anything, a crash must not leave an empty file behind
+        if let Err(e) = file.sync_all().await {
+            return Err((500, format!("Error writing to the staging file: {}", e)));
+        }
+
+        Ok(StagedFile { temp_path, size })
+    }
+
+    /// Deletes the staging files left behind by uploads that were cut off by a crash or restart.
+    /// Meant to run at startup, before any upload is staged. Returns how many there were.
+    pub async fn remove_orphaned_staged_files(&self) -> usize {
+        let Ok(mut entries) = tokio::fs::read_dir(Path::new(&self.root_dir).join(STAGING_DIR)).await else {
+            return 0;
         };
+        let mut removed = 0;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            // Other subfolders, like the one of the resumable uploads, manage themselves
+            let removal = match entry.file_type().await {
+                Ok(file_type) if file_type.is_file() => tokio::fs::remove_file(entry.path()).await,
+                Ok(file_type) if file_type.is_dir() && entry.file_name().to_string_lossy().starts_with(EXTRACT_DIR_PREFIX) => {
+                    tokio::fs::remove_dir_all(entry.path()).await
+                },
+                _ => continue,
+            };
+            match removal {
+                Ok(()) => removed += 1,
+                Err(e) => error!("Could not remove {:?}: {}", entry.path(), e),
+            }
+        }
+        if removed > 0 {
+            info!("Removed {} orphaned staging files", removed);
+        }
+        removed
+    }
 
-        let lock_arc = self.directory_lock_manager.lock_for_path(abs_path.clone()).await;
+    /// Moves a staged upload to `abs_path`. The staging folder is on the same filesystem, so readers
+    /// see either the old or the complete new file. An existing file is handled by `policy`.
+    /// Returns where the file was stored.
+    pub(crate) async fn commit_staged_upload(
+        &self,
+        staged: StagedFile,
+        abs_path: &Path,
+        policy: &ConflictPolicy
+    ) -> Result<PathBuf, (u16, String)> {
+        let lock_arc = self.directory_lock_manager.lock_for_path(abs_path.to_path_buf()).await;
         let _guard = lock_arc.lock().await;
-        // Write the entire byte slice to the file
-        if let Err(e) = file.write_all(file_bytes).await {
-            return Err((500, format!("Error writing to file {:?}: {}", abs_path, e)));
+
+        let stored_path = self.resolve_conflict(abs_path, policy).await?;
+        if let Err(e) = staged.persist(&stored_path).await {
+            if stored_path != abs_path {
+                let _ = tokio::fs::remove_file(&stored_path).await;
+            }
+            return Err((500, format!("Error saving file at {:?}: {}", stored_path, e)));
         }
 
-        println!("Successfully saved file to {:?}", abs_path);
+        info!("Successfully saved file to {:?}", stored_path);
+        Ok(stored_path)
+    }
 
-        Ok("Successfully saved file!".to_string())
+    /// Decides where a file written to `abs_path` goes if a file of that name exists. A numbered
+    /// name is reserved by creating an empty file for the write to replace. The caller has to hold
+    /// the lock of `abs_path`, so the file can't change between the check and the write.
+    pub(crate) async fn resolve_conflict(
+        &self,
+        abs_path: &Path,
+        policy: &ConflictPolicy
+    ) -> Result<PathBuf, (u16, String)> {
+        let name = abs_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        match policy {
+            ConflictPolicy::Overwrite => Ok(abs_path.to_path_buf()),
+            ConflictPolicy::KeepBoth => {
+                let dir = abs_path.parent().unwrap_or(Path::new(""));
+                let stored_name = reserve_free_name(dir, &name).await?;
+                Ok(dir.join(stored_name))
+            },
+            ConflictPolicy::Reject => match tokio::fs::symlink_metadata(abs_path).await {
+                Ok(_) => Err((409, format!("'{}' already exists", name))),
+                Err(_) => Ok(abs_path.to_path_buf()),
+            },
+            ConflictPolicy::IfMatch(etags) => {
+                let current = match file_etag(abs_path).await {
+                    Ok(etag) => etag,
+                    Err(e) if e.kind() == ErrorKind::NotFound => {
+                        return Err((412, format!("'{}' does not exist", name)));
+                    },
+                    Err(e) => return Err((500, format!("Error reading '{}': {}", name, e))),
+                };
+                // If-Match compares strongly, a weak ETag (`W/"..."`) never matches
+                if !etags.iter().any(|etag| etag == "*" || *etag == current) {
+                    return Err((412, format!("'{}' was changed in the meantime", name)));
+                }
+                Ok(abs_path.to_path_buf())
+            },
+        }
     }
 
     pub(crate) async fn read_file_from_any_directory(
@@ -55,6 +309,20 @@ impl FileService {
         path: &str,
         filename: &str
     ) -> Result<(Vec<u8>, String), (u16, String)> {
+        self.read_file_with_etag(user_name, path, filename)
+            .await
+            .map(|(contents, filename, _)| (contents, filename))
+    }
+
+    /// Like `read_file_from_any_directory`, but also returns the ETag of the version that was read
+    pub(crate) async fn read_file_with_etag(
+        &self,
+        user_name: &str,
+        path: &str,
+        filename: &str
+    ) -> Result<(Vec<u8>, String, String), (u16, String)> {
+        authorize(&self.acl, user_name, &format!("{}/{}", path, filename), AclPermission::Read).await?;
+
         let path_service = PathService::new();
         // Construct the full file path
         let canonical = match path_service.canonicalize_path(&Path::new(&self.root_dir)
@@ -72,10 +340,12 @@ impl FileService {
 
         let lock_arc = self.directory_lock_manager.lock_for_path(canonical.clone()).await;
         let _guard = lock_arc.lock().await;
-        // Use tokio::fs::read for asynchronous file reading
-        match tokio::fs::read(&canonical).await {
-            Ok(contents) => Ok((contents, filename.into())),
-            Err(_) => Err((404, format!("File '{}' not found", filename))),
-        }
+        let not_found = |_| (404, format!("File '{}' not found", filename));
+        // The ETag is taken from the opened file, so it belongs to the content that is read
+        let mut file = File::open(&canonical).await.map_err(not_found)?;
+        let etag = metadata_etag(&file.metadata().await.map_err(not_found)?);
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents).await.map_err(not_found)?;
+        Ok((contents, filename.into(), etag))
     }
 }
@@ -0,0 +1,115 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::error;
+use crate::dao::group_store::GroupStore;
+use crate::models::groups::group_access::GroupAccess;
+use crate::models::groups::group_requests::{GroupMember, GroupSummary};
+use crate::services::authentication::account_service::{validate_username, ARCHIVE_DIR};
+use crate::services::locking::directory_locking_manager::DirectoryLockManager;
+
+/// Directory under the root holding the folder of every group
+pub const GROUPS_DIR: &str = "_groups";
+
+/// The tree of a group, relative to the root. File services take it in place of a username.
+pub fn group_tree(group: &str) -> String {
+    format!("{}/{}", GROUPS_DIR, group)
+}
+
+pub struct GroupService<T: GroupStore> {
+    store: T,
+    root_dir: String,
+    directory_lock_manager: DirectoryLockManager
+}
+
+impl<T: GroupStore> GroupService<T> {
+    pub fn new(store: T, root_dir: String, directory_lock_manager: DirectoryLockManager) -> Self {
+        Self { store, root_dir, directory_lock_manager }
+    }
+
+    /// Creates the group together with its folder
+    pub async fn create_group(&self, name: &str) -> Result<String, (u16, String)> {
+        // Group names end up in paths just like usernames
+        validate_username(name).map_err(|(code, _)| (code, format!(
+            "Invalid group name '{}': use letters, digits, '.', '_' or '-', not starting with '.' or '_'",
+            name
+        )))?;
+
+        match self.store.create_group(name, now()).await {
+            Ok(true) => {}
+            Ok(false) => return Err((409, format!("Group '{}' already exists", name))),
+            Err(e) => return Err((500, e)),
+        }
+
+        let group_dir = Path::new(&self.root_dir).join(group_tree(name));
+        if let Err(e) = tokio::fs::create_dir_all(&group_dir).await {
+            error!("Could not create directory for group {}: {}", name, e);
+            // Don't leave behind a group without a folder
+            if let Err(e) = self.store.delete_group(name).await {
+                error!("Could not roll back group {}: {}", name, e);
+            }
+            return Err((500, "Could not create the group directory".to_string()));
+        }
+
+        Ok(format!("Group '{}' created successfully.", name))
+    }
+
+    /// Deletes the group and moves its folder to the archive
+    pub async fn delete_group(&self, name: &str) -> Result<String, (u16, String)> {
+        let group_dir = Path::new(&self.root_dir).join(group_tree(name));
+        let lock_arc = self.directory_lock_manager.lock_for_path(group_dir.clone()).await;
+        let _guard = lock_arc.lock().await;
+
+        match self.store.delete_group(name).await {
+            Ok(true) => {}
+            Ok(false) => return Err((404, format!("Group '{}' not found", name))),
+            Err(e) => return Err((500, e)),
+        }
+
+        if tokio::fs::metadata(&group_dir).await.is_ok() {
+            let archive_dir = Path::new(&self.root_dir).join(ARCHIVE_DIR);
+            let archived = archive_dir.join(format!("{}-{}-{}", GROUPS_DIR, name, now()));
+
+            let archive_result = match tokio::fs::create_dir_all(&archive_dir).await {
+                Ok(_) => tokio::fs::rename(&group_dir, &archived).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = archive_result {
+                error!("Could not archive directory of group {}: {}", name, e);
+                return Err((500, "Could not archive the group directory".to_string()));
+            }
+        }
+
+        Ok(format!("Group '{}' deleted successfully.", name))
+    }
+
+    pub async fn list_groups(&self, username: &str, include_all: bool) -> Result<Vec<GroupSummary>, (u16, String)> {
+        self.store.list_groups(username, include_all).await.map_err(|e| (500, e))
+    }
+
+    pub async fn list_members(&self, group: &str) -> Result<Vec<GroupMember>, (u16, String)> {
+        self.store.list_members(group).await.map_err(|e| (500, e))
+    }
+
+    pub async fn set_member(&self, group: &str, username: &str, access: GroupAccess) -> Result<String, (u16, String)> {
+        match self.store.set_member(group, username, access).await {
+            Ok(_) => Ok(format!("'{}' has {} access to group '{}'.", username, access.as_str(), group)),
+            Err(e) if e == "User not found" => Err((404, e)),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    pub async fn remove_member(&self, group: &str, username: &str) -> Result<String, (u16, String)> {
+        match self.store.remove_member(group, username).await {
+            Ok(true) => Ok(format!("'{}' removed from group '{}'.", username, group)),
+            Ok(false) => Err((404, format!("'{}' is not a member of group '{}'", username, group))),
+            Err(e) => Err((500, e)),
+        }
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
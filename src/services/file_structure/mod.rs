@@ -3,4 +3,10 @@ pub mod file_service;
 pub mod privilege_service;
 pub mod delete_service;
 pub mod rename_service;
-pub mod path_service;
\ No newline at end of file
+pub mod path_service;
+pub mod group_service;
+pub mod acl_service;
+pub mod share_service;
+pub mod share_link_service;
+pub mod tus_service;
+pub mod archive_service;
\ No newline at end of file
@@ -1,4 +1,7 @@
 use crate::dao::privilege_store::PrivilegeStore;
+use crate::models::authentication::auth_user::ADMIN_PRIVILEGE;
+use crate::models::groups::group_access::GroupAccess;
+use crate::services::file_structure::group_service::group_tree;
 
 pub struct PrivilegeService<T: PrivilegeStore> {
     store: T,
@@ -9,31 +12,64 @@ impl<T: PrivilegeStore> PrivilegeService<T> {
         Self { store }
     }
 
-    pub async fn check_privilege_status(
+    /// Looks up the role of the user and the privilege level that comes with it.
+    /// Roles missing from the `privilege_level` table get no privileges, failed lookups are errors.
+    pub async fn resolve_user_privilege(&self, user_name: &str) -> Result<(String, i32), String> {
+        let role = self.store.get_user_role(user_name).await?;
+        let privilege = self.store.get_privilege_level(&role).await?.unwrap_or(0);
+        Ok((role, privilege))
+    }
+
+    /// Returns the user whose tree a request operates on. Without an owner users work in
+    /// their own tree, another user's tree needs a higher privilege than that user has.
+    pub async fn resolve_tree_owner(&self, requester: &str, owner: Option<&str>) -> Result<String, (u16, String)> {
+        let owner = match owner.map(str::trim).filter(|owner| !owner.is_empty()) {
+            Some(owner) if owner != requester => owner,
+            _ => return Ok(requester.to_string()),
+        };
+
+        let (_, requester_privilege) = self.resolve_user_privilege(requester)
+            .await
+            .map_err(|e| (500, e))?;
+        let owner_privilege = match self.resolve_user_privilege(owner).await {
+            Ok((_, privilege)) => privilege,
+            Err(e) if e == "User not found" => return Err((404, format!("User '{}' not found", owner))),
+            Err(e) => return Err((500, e)),
+        };
+
+        if requester_privilege <= owner_privilege {
+            return Err((403, format!("You are not allowed to access the files of '{}'", owner)));
+        }
+        Ok(owner.to_string())
+    }
+
+    /// Returns the tree of the group if the user has at least the required access to it.
+    /// Admins can access every group.
+    pub async fn resolve_group_access(
         &self,
-        dir_name: &str,
         user_name: &str,
-    ) -> Result<(), String> {
-        let to_be_accessed = self.store.get_privilege_level(dir_name).await;
-        match to_be_accessed {  
-            Ok(_) => {},
-            Err(_) => return Err(format!("The role {} does not exist", dir_name).parse().unwrap())
-        }
-        let actual_privileges = self.store.get_privilege_level(user_name).await;
-        match actual_privileges {
-            Ok(_) => {},
-            Err(_) => return Err(format!("The role {} does not exist", user_name).parse().unwrap())
+        group: &str,
+        required: GroupAccess
+    ) -> Result<String, (u16, String)> {
+        match self.store.group_exists(group).await {
+            Ok(true) => {}
+            Ok(false) => return Err((404, format!("Group '{}' not found", group))),
+            Err(e) => return Err((500, e)),
         }
 
-        // Compare the route param to the user's token role
-        if actual_privileges < Ok(to_be_accessed?) {
-            // If they don't match, return 403
-            return Err(format!(
-                "Your token role is '{}', but you tried to access '{}'",
-                user_name, dir_name
-            ));
+        let (_, privilege) = self.resolve_user_privilege(user_name).await.map_err(|e| (500, e))?;
+        if privilege >= ADMIN_PRIVILEGE {
+            return Ok(group_tree(group));
         }
 
-        Ok(())
+        match self.store.get_group_access(group, user_name).await {
+            Ok(Some(access)) if access >= required => Ok(group_tree(group)),
+            Ok(Some(access)) => Err((403, format!(
+                "Your access to group '{}' is '{}', but '{}' is required",
+                group, access.as_str(), required.as_str()
+            ))),
+            Ok(None) => Err((403, format!("You are not a member of group '{}'", group))),
+            Err(e) => Err((500, e)),
+        }
     }
 }
\ No newline at end of file
@@ -2,16 +2,25 @@ use std::path::Path;
 use actix_web::http::StatusCode;
 use actix_web::HttpResponse;
 use log::error;
+use crate::models::acl::acl_entry::AclPermission;
+use crate::services::file_structure::acl_service::{authorize, normalize_acl_path, AclCheck};
 use crate::services::file_structure::path_service::PathService;
 
 pub struct RenameService {
-    root_dir: String
+    root_dir: String,
+    acl: Option<AclCheck>
 }
 
 impl RenameService {
     
     pub fn new(root_dir: String) -> Self {
-        Self { root_dir }
+        Self { root_dir, acl: None }
+    }
+
+    /// Checks every rename against the ACL of the tree
+    pub fn with_acl(mut self, acl: Option<AclCheck>) -> Self {
+        self.acl = acl;
+        self
     }
     
     pub async fn rename_directory(
@@ -21,6 +30,13 @@ impl RenameService {
         old_name: &String, 
         new_name: &String
     ) -> Result<String, (u16, String)>{
+        // Taking the item away from where it was needs the right to delete it, putting it at its new
+        // place the right to write into the directory it ends up in. The new name may point into another one.
+        authorize(&self.acl, username, &format!("{}/{}", path, old_name), AclPermission::Delete).await?;
+        let new_item = normalize_acl_path(&format!("{}/{}", path, new_name))?;
+        let new_parent = new_item.rsplit_once('/').map_or("", |(parent, _)| parent);
+        authorize(&self.acl, username, new_parent, AclPermission::Write).await?;
+
         let old_path = Path::new(&self.root_dir).join(username).join(path).join(old_name);
         let new_path = Path::new(&self.root_dir).join(username).join(path).join(new_name);
         
@@ -0,0 +1,290 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use bcrypt::{hash, verify};
+use log::{error, info};
+use crate::dao::share_link_store::ShareLinkStore;
+use crate::models::shares::share_link::{ShareLink, ShareLinkKind};
+use crate::services::authentication::opaque_token::{generate_opaque_token, hash_opaque_token};
+use crate::services::file_structure::acl_service::normalize_acl_path;
+use crate::services::file_structure::file_service::{reserve_free_name, StagedFile};
+use crate::services::file_structure::share_service::{is_plain_name, source_exists};
+
+const LINK_TOKEN_BYTES: usize = 32;
+
+#[cfg(not(test))]
+const PASSWORD_HASH_COST: u32 = bcrypt::DEFAULT_COST;
+#[cfg(test)]
+const PASSWORD_HASH_COST: u32 = 4;
+
+/// Public links to files and directories that can be downloaded without an account, and file drops
+/// that can be uploaded to. A link is shown once on creation and only the hash of its token is stored.
+#[derive(Clone)]
+pub struct ShareLinkService {
+    store: Arc<dyn ShareLinkStore>,
+    root_dir: String,
+}
+
+impl ShareLinkService {
+    pub fn new(store: Arc<dyn ShareLinkStore>, root_dir: String) -> Self {
+        Self { store, root_dir }
+    }
+
+    /// Returns the token of the new link together with the link
+    pub async fn create_link(
+        &self,
+        owner: &str,
+        path: &str,
+        expires_at: Option<i64>,
+        password: Option<&str>,
+        max_downloads: Option<i32>
+    ) -> Result<(String, ShareLink), (u16, String)> {
+        let path = normalize_acl_path(path)?;
+        if path.is_empty() {
+            return Err((400, "Share a file or directory, not the whole folder".to_string()));
+        }
+        let created_at = now();
+        if expires_at.is_some_and(|expires_at| expires_at <= created_at) {
+            return Err((400, "The expiration must be in the future".to_string()));
+        }
+        if max_downloads.is_some_and(|max_downloads| max_downloads < 1) {
+            return Err((400, "The maximum number of downloads must be at least 1".to_string()));
+        }
+        let password_hash = match password {
+            Some("") => return Err((400, "The password can't be empty".to_string())),
+            Some(password) => Some(hash(password, PASSWORD_HASH_COST).map_err(|e| (500, e.to_string()))?),
+            None => None,
+        };
+
+        let link = ShareLink {
+            max_downloads,
+            has_password: password_hash.is_some(),
+            password_hash,
+            ..self.new_link(ShareLinkKind::Download, owner, path, expires_at).await?
+        };
+        self.insert(link).await
+    }
+
+    /// Creates a file drop, a link that only allows uploading into the directory. Returns its token
+    /// together with the link.
+    pub async fn create_drop(
+        &self,
+        owner: &str,
+        path: &str,
+        expires_at: Option<i64>,
+        max_bytes: Option<i64>,
+        max_files: Option<i32>
+    ) -> Result<(String, ShareLink), (u16, String)> {
+        let path = normalize_acl_path(path)?;
+        if path.is_empty() {
+            return Err((400, "Choose a directory of your folder, not the whole folder".to_string()));
+        }
+        if expires_at.is_some_and(|expires_at| expires_at <= now()) {
+            return Err((400, "The expiration must be in the future".to_string()));
+        }
+        if max_bytes.is_some_and(|max_bytes| max_bytes < 1) || max_files.is_some_and(|max_files| max_files < 1) {
+            return Err((400, "The size and file limits must be at least 1".to_string()));
+        }
+
+        let link = ShareLink {
+            max_bytes,
+            max_files,
+            ..self.new_link(ShareLinkKind::Drop, owner, path, expires_at).await?
+        };
+        if !link.is_dir {
+            return Err((400, format!("'{}' is not a directory", link.path)));
+        }
+        self.insert(link).await
+    }
+
+    pub async fn list(&self, owner: &str) -> Result<Vec<ShareLink>, (u16, String)> {
+        self.store.list_links(owner).await.map_err(|e| (500, e))
+    }
+
+    pub async fn revoke(&self, owner: &str, id: &str) -> Result<String, (u16, String)> {
+        match self.store.revoke_link(owner, id).await {
+            Ok(true) => Ok(format!("Link '{}' revoked.", id)),
+            Ok(false) => Err((404, format!("Link '{}' not found", id))),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    /// Checks the token and the password of a download. It is only counted by `record_download`,
+    /// once the source could be read.
+    pub async fn open(&self, token: &str, password: Option<&str>, now: i64) -> Result<ShareLink, (u16, String)> {
+        let link = self.find_active(token, ShareLinkKind::Download, now).await?;
+        if link.max_downloads.is_some_and(|max_downloads| link.download_count >= max_downloads) {
+            return Err((410, "The link has reached its download limit".to_string()));
+        }
+        if let Some(password_hash) = &link.password_hash {
+            let Some(password) = password else {
+                return Err((401, "The link is protected by a password".to_string()));
+            };
+            if !verify(password, password_hash).map_err(|e| (500, e.to_string()))? {
+                return Err((401, "Wrong password".to_string()));
+            }
+        }
+
+        self.check_source(&link).await?;
+        Ok(link)
+    }
+
+    /// Counts a download of a link returned by `open`
+    pub async fn record_download(&self, link: ShareLink, now: i64) -> Result<ShareLink, (u16, String)> {
+        // Checked again by the store, another download may have used the link up meanwhile
+        if !self.store.record_download(&link.id, now).await.map_err(|e| (500, e))? {
+            return Err((410, "The link is no longer available".to_string()));
+        }
+        Ok(ShareLink { download_count: link.download_count + 1, ..link })
+    }
+
+    /// Checks that the file drop still accepts uploads and that its directory still exists
+    pub async fn open_drop(&self, token: &str, now: i64) -> Result<ShareLink, (u16, String)> {
+        let link = self.find_active(token, ShareLinkKind::Drop, now).await?;
+        if link.max_files.is_some_and(|max_files| link.upload_count >= max_files)
+            || link.max_bytes.is_some_and(|max_bytes| link.uploaded_bytes >= max_bytes) {
+            return Err((410, "The file drop is full".to_string()));
+        }
+        self.check_source(&link).await?;
+        Ok(link)
+    }
+
+    /// Stores a file uploaded into a file drop. Existing files are never overwritten, the upload
+    /// gets a free name instead. Returns the name the file was stored under.
+    pub async fn receive_file(
+        &self,
+        token: &str,
+        filename: &str,
+        staged_file: StagedFile,
+        now: i64
+    ) -> Result<String, (u16, String)> {
+        if !is_plain_name(filename) {
+            return Err((400, format!("Invalid file name '{}'", filename)));
+        }
+        let link = self.open_drop(token, now).await?;
+        let size = staged_file.size as i64;
+        if link.max_bytes.is_some_and(|max_bytes| link.uploaded_bytes + size > max_bytes) {
+            return Err((413, "The file is larger than the space left in the file drop".to_string()));
+        }
+
+        // Counted before the file is stored, so concurrent uploads can't exceed the caps together.
+        // Checked again by the store, other uploads may have filled the drop meanwhile.
+        if !self.store.record_upload(&link.id, size, now).await.map_err(|e| (500, e))? {
+            return Err((410, "The file drop is full".to_string()));
+        }
+
+        let dir = Path::new(&self.root_dir).join(&link.owner).join(&link.path);
+        let stored_name = match reserve_free_name(&dir, filename).await {
+            Ok(stored_name) => stored_name,
+            Err(e) => {
+                self.undo_upload(&link, size).await;
+                return Err(e);
+            }
+        };
+        if let Err(e) = staged_file.persist(&dir.join(&stored_name)).await {
+            let _ = tokio::fs::remove_file(dir.join(&stored_name)).await;
+            self.undo_upload(&link, size).await;
+            return Err((500, format!("Error saving file {}: {}", stored_name, e)));
+        }
+        info!("Received {} through file drop {} of {}", stored_name, link.id, link.owner);
+        Ok(stored_name)
+    }
+
+    /// Gives back the space and the file counted for an upload that could not be stored
+    async fn undo_upload(&self, link: &ShareLink, bytes: i64) {
+        if let Err(e) = self.store.undo_upload(&link.id, bytes).await {
+            error!("Could not take back an upload into file drop {}: {}", link.id, e);
+        }
+    }
+
+    /// A link of the kind whose token matches, unless it was revoked or has expired
+    async fn find_active(&self, token: &str, kind: ShareLinkKind, now: i64) -> Result<ShareLink, (u16, String)> {
+        let link = self.store
+            .find_link(&hash_opaque_token(token))
+            .await
+            .map_err(|e| (500, e))?
+            .filter(|link| !link.revoked && link.kind == kind)
+            .ok_or_else(|| (404, "Link not found".to_string()))?;
+
+        if link.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return Err((410, "The link has expired".to_string()));
+        }
+        Ok(link)
+    }
+
+    /// Keeps the links of a renamed file or directory
+    pub async fn move_path(&self, owner: &str, old_path: &str, new_path: &str) -> Result<(), (u16, String)> {
+        let old_path = normalize_acl_path(old_path)?;
+        let new_path = normalize_acl_path(new_path)?;
+        self.store.move_sources(owner, &old_path, &new_path).await.map_err(|e| (500, e))
+    }
+
+    /// Revokes the links of a deleted file or directory, so nothing created at its place later is exposed
+    pub async fn delete_path(&self, owner: &str, path: &str) -> Result<(), (u16, String)> {
+        let path = normalize_acl_path(path)?;
+        self.store.revoke_sources(owner, &path).await.map_err(|e| (500, e))
+    }
+
+    async fn check_source(&self, link: &ShareLink) -> Result<(), (u16, String)> {
+        let tree = Path::new(&self.root_dir).join(&link.owner);
+        if source_exists(&tree, &link.path, link.is_dir).await {
+            return Ok(());
+        }
+        match link.kind {
+            ShareLinkKind::Download => Err((404, "The shared file no longer exists".to_string())),
+            ShareLinkKind::Drop => Err((404, "The folder of the file drop no longer exists".to_string())),
+        }
+    }
+
+    async fn new_link(
+        &self,
+        kind: ShareLinkKind,
+        owner: &str,
+        path: String,
+        expires_at: Option<i64>
+    ) -> Result<ShareLink, (u16, String)> {
+        let metadata = tokio::fs::symlink_metadata(Path::new(&self.root_dir).join(owner).join(&path))
+            .await
+            .map_err(|_| (404, format!("'{}' not found", path)))?;
+        if !metadata.is_dir() && !metadata.is_file() {
+            return Err((400, format!("'{}' can't be shared", path)));
+        }
+
+        Ok(ShareLink {
+            id: generate_opaque_token(8),
+            kind,
+            owner: owner.to_string(),
+            path,
+            is_dir: metadata.is_dir(),
+            expires_at,
+            max_downloads: None,
+            download_count: 0,
+            max_bytes: None,
+            max_files: None,
+            uploaded_bytes: 0,
+            upload_count: 0,
+            password_hash: None,
+            has_password: false,
+            created_at: now(),
+            revoked: false,
+        })
+    }
+
+    async fn insert(&self, link: ShareLink) -> Result<(String, ShareLink), (u16, String)> {
+        let token = generate_opaque_token(LINK_TOKEN_BYTES);
+        self.store
+            .insert_link(&link, &hash_opaque_token(&token))
+            .await
+            .map_err(|e| (500, e))?;
+
+        info!("{} created {} link {} to {}", link.owner, link.kind.as_str(), link.id, link.path);
+        Ok((token, link))
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
@@ -0,0 +1,209 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::info;
+use crate::dao::share_store::ShareStore;
+use crate::models::shares::share::Share;
+use crate::services::file_structure::acl_service::normalize_acl_path;
+
+/// Virtual directory in the root of every user's tree holding what was shared with them.
+/// Paths below it are resolved to the owner's tree.
+pub const SHARED_WITH_ME: &str = "Shared with me";
+
+#[derive(Clone)]
+pub struct ShareService {
+    store: Arc<dyn ShareStore>,
+    root_dir: String,
+}
+
+impl ShareService {
+    pub fn new(store: Arc<dyn ShareStore>, root_dir: String) -> Self {
+        Self { store, root_dir }
+    }
+
+    pub async fn create_share(
+        &self,
+        owner: &str,
+        path: &str,
+        recipient: &str,
+        name: Option<&str>,
+        allow_upload: bool
+    ) -> Result<Share, (u16, String)> {
+        let path = normalize_acl_path(path)?;
+        if path.is_empty() {
+            return Err((400, "Share a file or directory, not the whole folder".to_string()));
+        }
+        if recipient == owner {
+            return Err((400, "You can't share with yourself".to_string()));
+        }
+        let name = name
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| path.rsplit('/').next().unwrap_or_default())
+            .to_string();
+        if !is_plain_name(&name) {
+            return Err((400, format!("Invalid share name '{}'", name)));
+        }
+
+        let metadata = tokio::fs::symlink_metadata(self.tree_path(owner).join(&path))
+            .await
+            .map_err(|_| (404, format!("'{}' not found", path)))?;
+        if !metadata.is_dir() && !metadata.is_file() {
+            return Err((400, format!("'{}' can't be shared", path)));
+        }
+
+        let mut share = Share {
+            id: 0,
+            owner: owner.to_string(),
+            recipient: recipient.to_string(),
+            name,
+            path,
+            file_id: source_identity(&metadata),
+            is_dir: metadata.is_dir(),
+            allow_upload,
+            created_at: now(),
+        };
+        share.id = match self.store.create_share(&share).await {
+            Ok(Some(id)) => id,
+            Ok(None) => return Err((409, format!("'{}' already has a share named '{}'", recipient, share.name))),
+            Err(e) if e == "User not found" => return Err((404, format!("User '{}' not found", recipient))),
+            Err(e) => return Err((500, e)),
+        };
+        info!("{} shared {} with {}", owner, share.path, recipient);
+        Ok(share)
+    }
+
+    /// The shares the user created, including the ones whose source is gone, so they can be revoked
+    pub async fn list_outgoing(&self, owner: &str) -> Result<Vec<Share>, (u16, String)> {
+        self.store.list_shares(owner, false).await.map_err(|e| (500, e))
+    }
+
+    /// The shares the user received whose sources still exist
+    pub async fn list_incoming(&self, recipient: &str) -> Result<Vec<Share>, (u16, String)> {
+        let shares = self.store.list_shares(recipient, true).await.map_err(|e| (500, e))?;
+        let mut existing = Vec::with_capacity(shares.len());
+        for share in shares {
+            if self.source_is_current(&share).await {
+                existing.push(share);
+            }
+        }
+        Ok(existing)
+    }
+
+    /// The share of the recipient with that name
+    pub async fn resolve(&self, recipient: &str, name: &str) -> Result<Share, (u16, String)> {
+        let share = self.store
+            .find_share(recipient, name)
+            .await
+            .map_err(|e| (500, e))?
+            .ok_or_else(|| (404, format!("Nothing named '{}' is shared with you", name)))?;
+
+        if !self.source_is_current(&share).await {
+            return Err((404, format!("The source of '{}' no longer exists", name)));
+        }
+        Ok(share)
+    }
+
+    pub async fn revoke(&self, username: &str, id: i64) -> Result<String, (u16, String)> {
+        match self.store.delete_share(id, username).await {
+            Ok(true) => Ok("Share removed successfully.".to_string()),
+            Ok(false) => Err((404, "Share not found".to_string())),
+            Err(e) => Err((500, e)),
+        }
+    }
+
+    /// Keeps the shares of a renamed file or directory
+    pub async fn move_path(&self, owner: &str, old_path: &str, new_path: &str) -> Result<(), (u16, String)> {
+        let old_path = normalize_acl_path(old_path)?;
+        let new_path = normalize_acl_path(new_path)?;
+        self.store.move_sources(owner, &old_path, &new_path).await.map_err(|e| (500, e))
+    }
+
+    /// Keeps the shares of a file that was replaced at its path, like by uploading it again
+    pub async fn replace_file(&self, owner: &str, path: &str) -> Result<(), (u16, String)> {
+        let path = normalize_acl_path(path)?;
+        let metadata = tokio::fs::symlink_metadata(self.tree_path(owner).join(&path))
+            .await
+            .map_err(|_| (404, format!("'{}' not found", path)))?;
+        self.store.refresh_sources(owner, &path, &source_identity(&metadata)).await.map_err(|e| (500, e))
+    }
+
+    /// Drops the shares of a deleted file or directory, so nothing created at its place later is shared
+    pub async fn delete_path(&self, owner: &str, path: &str) -> Result<(), (u16, String)> {
+        let path = normalize_acl_path(path)?;
+        self.store.delete_sources(owner, &path).await.map_err(|e| (500, e))
+    }
+
+    /// Whether the source is still the file or directory that was shared. A path alone isn't enough,
+    /// whatever was created at the place of a source that was removed is not shared.
+    async fn source_is_current(&self, share: &Share) -> bool {
+        tokio::fs::symlink_metadata(self.tree_path(&share.owner).join(&share.path))
+            .await
+            .is_ok_and(|metadata| {
+                metadata.is_dir() == share.is_dir && source_identity(&metadata) == share.file_id
+            })
+    }
+
+    fn tree_path(&self, owner: &str) -> PathBuf {
+        Path::new(&self.root_dir).join(owner)
+    }
+}
+
+/// Splits a normalized path of the user's own tree at the `Shared with me` node. Returns
+/// the path below the node, `None` if the path is somewhere else.
+pub fn strip_shared_prefix(path: &str) -> Option<&str> {
+    match path.strip_prefix(SHARED_WITH_ME) {
+        Some("") => Some(""),
+        Some(rest) => rest.strip_prefix('/'),
+        None => None,
+    }
+}
+
+/// Whether a path of a tree is at or below the `Shared with me` node. Nothing can be created there,
+/// the virtual node would hide it.
+pub fn is_reserved_path(path: &str) -> bool {
+    normalize_acl_path(path).is_ok_and(|path| strip_shared_prefix(&path).is_some())
+}
+
+/// Identifies a file or directory whatever its path: its device and inode, and its birth time where
+/// the file system records it, so an inode that was freed and reused isn't taken for the old item
+pub(crate) fn source_identity(metadata: &std::fs::Metadata) -> String {
+    let born = metadata.created()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_nanos())
+        .unwrap_or(0);
+    let (device, inode) = device_and_inode(metadata);
+    format!("{:x}:{:x}:{:x}", device, inode, born)
+}
+
+#[cfg(unix)]
+fn device_and_inode(metadata: &std::fs::Metadata) -> (u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.dev(), metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn device_and_inode(_metadata: &std::fs::Metadata) -> (u64, u64) {
+    (0, 0)
+}
+
+/// Whether the source of a link is still where it was shared from. A file that was replaced, e.g. by
+/// uploading it again, is still the source.
+pub(crate) async fn source_exists(tree: &Path, path: &str, is_dir: bool) -> bool {
+    tokio::fs::symlink_metadata(tree.join(path))
+        .await
+        .is_ok_and(|metadata| if is_dir { metadata.is_dir() } else { metadata.is_file() })
+}
+
+/// A single path component
+pub fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use futures_util::{Stream, TryStreamExt};
+use log::{error, info};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use crate::models::system_operations::tus_upload::TusUpload;
+use crate::services::authentication::opaque_token::generate_opaque_token;
+use crate::services::file_structure::file_service::{FileService, StagedFile, STAGING_DIR};
+use crate::services::locking::directory_locking_manager::DirectoryLockManager;
+
+/// Subfolder of the staging folder holding the unfinished resumable uploads
+const TUS_DIR: &str = "tus";
+const UPLOAD_ID_BYTES: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TusSettings {
+    /// Largest `Upload-Length` accepted
+    pub max_size: u64,
+    /// Unfinished uploads are deleted this long after they were created
+    pub expiration_seconds: i64,
+    /// How many unfinished uploads a user can have at a time
+    pub max_open_uploads: usize,
+}
+
+impl Default for TusSettings {
+    fn default() -> Self {
+        Self {
+            max_size: 10 * 1024 * 1024 * 1024,
+            expiration_seconds: 24 * 60 * 60,
+            max_open_uploads: 20,
+        }
+    }
+}
+
+impl TusSettings {
+    /// Reads the optional `TUS_MAX_SIZE`, `TUS_EXPIRATION_SECONDS` and `TUS_MAX_OPEN_UPLOADS` variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_size: env::var("TUS_MAX_SIZE")
+                .map(|v| v.parse().expect("TUS_MAX_SIZE must be a valid integer"))
+                .unwrap_or(defaults.max_size),
+            expiration_seconds: env::var("TUS_EXPIRATION_SECONDS")
+                .map(|v| v.parse().expect("TUS_EXPIRATION_SECONDS must be a valid integer"))
+                .unwrap_or(defaults.expiration_seconds),
+            max_open_uploads: env::var("TUS_MAX_OPEN_UPLOADS")
+                .map(|v| v.parse().expect("TUS_MAX_OPEN_UPLOADS must be a valid integer"))
+                .unwrap_or(defaults.max_open_uploads),
+        }
+    }
+}
+
+/// Resumable uploads following the tus 1.0 protocol. The data of an upload is appended to
+/// `_staging/tus/<id>` and its state kept in `_staging/tus/<id>.json`, so uploads survive restarts.
+/// Appending, finishing and deleting an upload happen under the lock of its data file.
+pub struct TusService {
+    root_dir: String,
+    directory_lock_manager: DirectoryLockManager,
+    settings: TusSettings,
+}
+
+impl TusService {
+    pub fn new(root_dir: String, directory_lock_manager: DirectoryLockManager, settings: TusSettings) -> Self {
+        Self { root_dir, directory_lock_manager, settings }
+    }
+
+    /// Starts an upload of `length` bytes. Like the fields of a regular upload, the `Upload-Metadata`
+    /// names the file with `filename` and where it goes with `path` and the optional `owner` or `group`.
+    pub async fn create(
+        &self,
+        username: &str,
+        length: u64,
+        metadata: &HashMap<String, String>,
+        now: i64
+    ) -> Result<TusUpload, (u16, String)> {
+        if length > self.settings.max_size {
+            return Err((413, "The upload is too large".to_string()));
+        }
+        let file_service = FileService::new(self.root_dir.clone(), self.directory_lock_manager.clone());
+        // Checked like the names of regular uploads, but a resumable upload is a single file, not a folder
+        let name = metadata
+            .get("filename")
+            .ok_or_else(|| (400, "A filename is required in the Upload-Metadata".to_string()))?;
+        let filename = match file_service.sanitize_relative_path(name) {
+            Some((folder, filename)) if folder.is_empty() => filename,
+            _ => return Err((400, format!("Invalid file name '{}'", name))),
+        };
+        let optional = |key: &str| metadata.get(key).map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+        self.remove_expired(now).await;
+
+        // One creation at a time, so concurrent ones can't exceed the cap together
+        let lock_arc = self.directory_lock_manager.lock_for_path(self.upload_dir()).await;
+        let _guard = lock_arc.lock().await;
+        if self.open_uploads(username).await >= self.settings.max_open_uploads {
+            return Err((429, "Too many unfinished uploads, finish or cancel some of them first".to_string()));
+        }
+
+        let upload = TusUpload {
+            id: generate_opaque_token(UPLOAD_ID_BYTES),
+            username: username.to_string(),
+            length,
+            path: metadata.get("path").map(|path| path.trim().to_string()).unwrap_or_default(),
+            filename,
+            owner: optional("owner"),
+            group: optional("group"),
+            created_at: now,
+            expires_at: now + self.settings.expiration_seconds,
+        };
+
+        let dir = self.upload_dir();
+        if let Err(e) = tokio::fs::create_dir_all(&dir).await {
+            return Err((500, format!("Error creating the upload folder: {}", e)));
+        }
+        if let Err(e) = tokio::fs::File::create(self.data_path(&upload.id)).await {
+            return Err((500, format!("Error creating the upload: {}", e)));
+        }
+        self.save_info(&upload).await?;
+
+        info!("{} started upload {} of {} bytes", username, upload.id, length);
+        Ok(upload)
+    }
+
+    /// The upload and how many of its bytes were received
+    pub async fn offset(&self, username: &str, id: &str, now: i64) -> Result<(TusUpload, u64), (u16, String)> {
+        let upload = self.load(username, id, now).await?;
+        let offset = self.received(id).await?;
+        Ok((upload, offset))
+    }
+
+    /// Appends the body of a `PATCH` request, which has to start at `offset`. What was received
+    /// is kept even if the request breaks off, so the client can resume from there. Once the last
+    /// byte arrived, the data is handed over and the upload forgotten.
+    pub async fn append<S, B, E>(
+        &self,
+        username: &str,
+        id: &str,
+        offset: u64,
+        mut stream: S,
+        now: i64
+    ) -> Result<(TusUpload, u64, Option<StagedFile>), (u16, String)>
+    where
+        S: Stream<Item = Result<B, E>> + Unpin,
+        B: AsRef<[u8]>,
+        E: Display,
+    {
+        let upload = self.load(username, id, now).await?;
+        // One request at a time, otherwise the offsets would get mixed up
+        let lock_arc = self.directory_lock_manager.lock_for_path(self.data_path(id)).await;
+        let _guard = lock_arc.lock().await;
+        // Another request may have finished, cancelled or expired the upload while this one waited
+        if self.read_info(id).await.is_none() {
+            return Err((404, "Upload not found".to_string()));
+        }
+
+        let mut received = self.received(id).await?;
+        if offset != received {
+            return Err((409, format!("The upload is at offset {}, not {}", received, offset)));
+        }
+
+        let mut file = match OpenOptions::new().append(true).open(self.data_path(id)).await {
+            Ok(file) => file,
+            Err(e) => return Err((500, format!("Error opening the upload: {}", e))),
+        };
+        let result = loop {
+            let chunk = match stream.try_next().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break Ok(()),
+                Err(e) => break Err((400, format!("The upload was interrupted: {}", e))),
+            };
+            let chunk = chunk.as_ref();
+            if received + chunk.len() as u64 > upload.length {
+                break Err((400, "The upload is longer than its Upload-Length".to_string()));
+            }
+            if let Err(e) = file.write_all(chunk).await {
+                break Err((500, format!("Error writing the upload: {}", e)));
+            }
+            received += chunk.len() as u64;
+        };
+        // The offset told to the client has to survive a crash, or it would resume with a gap
+        if let Err(e) = file.sync_data().await {
+            return Err((500, format!("Error writing the upload: {}", e)));
+        }
+
+        result?;
+
+        if received < upload.length {
+            return Ok((upload, received, None));
+        }
+        let staged = self.take_completed(&upload).await?;
+        Ok((upload, received, Some(staged)))
+    }
+
+    /// Hands over the data of a complete upload and forgets the upload. The lock of the upload has to be held.
+    async fn take_completed(&self, upload: &TusUpload) -> Result<StagedFile, (u16, String)> {
+        let staged = StagedFile::from_path(self.data_path(&upload.id), upload.length)
+            .map_err(|e| (500, format!("Error finishing the upload: {}", e)))?;
+        if let Err(e) = tokio::fs::remove_file(self.info_path(&upload.id)).await {
+            return Err((500, format!("Error finishing the upload: {}", e)));
+        }
+        Ok(staged)
+    }
+
+    pub async fn terminate(&self, username: &str, id: &str, now: i64) -> Result<(), (u16, String)> {
+        let upload = self.load(username, id, now).await?;
+        let lock_arc = self.directory_lock_manager.lock_for_path(self.data_path(id)).await;
+        let _guard = lock_arc.lock().await;
+        self.remove(&upload.id).await;
+        info!("{} terminated upload {}", username, id);
+        Ok(())
+    }
+
+    /// Deletes the uploads that have expired. Returns how many there were.
+    pub async fn remove_expired(&self, now: i64) -> usize {
+        let mut removed = 0;
+        for upload in self.list_uploads().await {
+            if upload.expires_at <= now && self.remove_if_expired(&upload.id, now).await {
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            info!("Removed {} expired uploads", removed);
+        }
+        removed
+    }
+
+    /// The upload if it belongs to the user and hasn't expired
+    async fn load(&self, username: &str, id: &str, now: i64) -> Result<TusUpload, (u16, String)> {
+        let upload = self.read_info(id)
+            .await
+            .filter(|upload| upload.username == username)
+            .ok_or_else(|| (404, "Upload not found".to_string()))?;
+        if upload.expires_at <= now {
+            self.remove_if_expired(id, now).await;
+            return Err((410, "The upload has expired".to_string()));
+        }
+        Ok(upload)
+    }
+
+    /// Deletes the upload under its lock, unless it was finished or removed meanwhile
+    async fn remove_if_expired(&self, id: &str, now: i64) -> bool {
+        let lock_arc = self.directory_lock_manager.lock_for_path(self.data_path(id)).await;
+        let _guard = lock_arc.lock().await;
+        if self.read_info(id).await.is_none_or(|upload| upload.expires_at > now) {
+            return false;
+        }
+        self.remove(id).await;
+        true
+    }
+
+    async fn open_uploads(&self, username: &str) -> usize {
+        self.list_uploads()
+            .await
+            .iter()
+            .filter(|upload| upload.username == username)
+            .count()
+    }
+
+    async fn list_uploads(&self) -> Vec<TusUpload> {
+        let Ok(mut entries) = tokio::fs::read_dir(self.upload_dir()).await else {
+            return Vec::new();
+        };
+        let mut uploads = Vec::new();
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(id) = name.strip_suffix(".json") else {
+                continue;
+            };
+            if let Some(upload) = self.read_info(id).await {
+                uploads.push(upload);
+            }
+        }
+        uploads
+    }
+
+    async fn read_info(&self, id: &str) -> Option<TusUpload> {
+        // Ids are generated hex tokens, anything else can't name an upload
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let json = tokio::fs::read(self.info_path(id)).await.ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+
+    async fn save_info(&self, upload: &TusUpload) -> Result<(), (u16, String)> {
+        let json = serde_json::to_vec(upload).map_err(|e| (500, e.to_string()))?;
+        tokio::fs::write(self.info_path(&upload.id), json)
+            .await
+            .map_err(|e| (500, format!("Error saving the upload: {}", e)))
+    }
+
+    async fn received(&self, id: &str) -> Result<u64, (u16, String)> {
+        tokio::fs::metadata(self.data_path(id))
+            .await
+            .map(|metadata| metadata.len())
+            .map_err(|_| (404, "Upload not found".to_string()))
+    }
+
+    async fn remove(&self, id: &str) {
+        for path in [self.info_path(id), self.data_path(id)] {
+            if let Err(e) = tokio::fs::remove_file(&path).await {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    error!("Could not remove {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+
+    fn upload_dir(&self) -> PathBuf {
+        Path::new(&self.root_dir).join(STAGING_DIR).join(TUS_DIR)
+    }
+
+    fn data_path(&self, id: &str) -> PathBuf {
+        self.upload_dir().join(id)
+    }
+
+    fn info_path(&self, id: &str) -> PathBuf {
+        self.upload_dir().join(format!("{}.json", id))
+    }
+}
+
+/// Parses an `Upload-Metadata` header: comma separated pairs of a key and an optional base64 value
+pub fn parse_upload_metadata(header: &str) -> Result<HashMap<String, String>, (u16, String)> {
+    let mut metadata = HashMap::new();
+    for pair in header.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+        let (key, value) = match pair.split_once(' ') {
+            Some((key, encoded)) => {
+                let decoded = STANDARD
+                    .decode(encoded.trim())
+                    .ok()
+                    .and_then(|bytes| String::from_utf8(bytes).ok())
+                    .ok_or_else(|| (400, format!("Invalid Upload-Metadata value for '{}'", key)))?;
+                (key, decoded)
+            },
+            None => (pair, String::new()),
+        };
+        metadata.insert(key.to_string(), value);
+    }
+    Ok(metadata)
+}
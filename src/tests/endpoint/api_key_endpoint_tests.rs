@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use actix_web::http::header::AUTHORIZATION;
+    use actix_web::{post, test, web, App, HttpResponse, Responder};
+    use async_trait::async_trait;
+    use crate::dao::api_key_store::ApiKeyStore;
+    use crate::endpoints::authentication::api_keys::create_api_key_handler;
+    use crate::endpoints::authentication::authentication::protected_resource_handler;
+    use crate::models::authentication::api_key::{ApiKeyIdentity, ApiKeyRecord, CreateApiKeyRequest};
+    use crate::models::authentication::auth_models::JwtAuth;
+    use crate::models::authentication::auth_user::ScopedUser;
+use crate::models::authentication::scope::FilesWrite;
+    use crate::services::authentication::api_key_service::ApiKeyService;
+    use crate::services::authentication::opaque_token::hash_opaque_token;
+    use crate::tests::test_structure::{get_global_test_env, test_app_config, test_authentication_service};
+
+    /// Knows a full key `fsk_full` and a read-only key `fsk_read`
+    struct StaticApiKeyStore;
+
+    #[async_trait]
+    impl ApiKeyStore for StaticApiKeyStore {
+        async fn insert_api_key(&self, _record: &ApiKeyRecord, _key_hash: &str) -> Result<(), String> {
+            Ok(())
+        }
+
+        async fn list_api_keys(&self, _username: &str) -> Result<Vec<ApiKeyRecord>, String> {
+            Ok(Vec::new())
+        }
+
+        async fn revoke_api_key(&self, _username: &str, _id: &str) -> Result<bool, String> {
+            Ok(false)
+        }
+
+        async fn revoke_all_api_keys(&self, _username: &str) -> Result<(), String> {
+            Ok(())
+        }
+
+        async fn find_api_key(&self, key_hash: &str) -> Result<Option<ApiKeyIdentity>, String> {
+            let read_only = if key_hash == hash_opaque_token("fsk_read") {
+                true
+            } else if key_hash == hash_opaque_token("fsk_full") {
+                false
+            } else {
+                return Ok(None);
+            };
+
+            Ok(Some(ApiKeyIdentity {
+                key: ApiKeyRecord {
+                    id: "key_id".to_string(),
+                    username: "test_user".to_string(),
+                    name: "backup".to_string(),
+                    key_prefix: "fsk_".to_string(),
+                    read_only,
+                    created_at: 0,
+                    expires_at: None,
+                    last_used_at: None,
+                    revoked: false,
+                },
+                role: "user".to_string(),
+                privilege: 1,
+                user_disabled: false,
+            }))
+        }
+
+        async fn touch_api_key(&self, _id: &str, _used_at: i64) -> Result<(), String> {
+            Ok(())
+        }
+    }
+
+    #[post("/upload")]
+    async fn upload(user: ScopedUser<FilesWrite>) -> impl Responder {
+        HttpResponse::Ok().body(user.username().to_string())
+    }
+
+    #[actix_web::test]
+    async fn test_api_key_authentication() {
+        let env = get_global_test_env().await;
+        let mut config = test_app_config(env.root_dir.path());
+        config.authentication_service = test_authentication_service()
+            .with_api_key_service(ApiKeyService::new(Arc::new(StaticApiKeyStore)));
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .service(
+                    web::scope("/api")
+                        .wrap(JwtAuth)
+                        .service(web::resource("/protected").route(web::get().to(protected_resource_handler)))
+                        .service(upload)
+                )
+        ).await;
+
+        // Errors of the middleware are not turned into responses by the test service
+        macro_rules! status {
+            ($req:expr) => {
+                match test::try_call_service(&app, $req).await {
+                    Ok(resp) => resp.status().as_u16(),
+                    Err(e) => e.as_response_error().status_code().as_u16(),
+                }
+            };
+        }
+
+        let request = |method: test::TestRequest, uri: &str, key: &str| method
+            .uri(uri)
+            .insert_header((AUTHORIZATION, format!("ApiKey {}", key)))
+            .to_request();
+
+        assert_eq!(status!(request(test::TestRequest::get(), "/api/protected", "fsk_full")), 200);
+        assert_eq!(status!(request(test::TestRequest::post(), "/api/upload", "fsk_full")), 200);
+
+        // A read-only key can read, but not write
+        assert_eq!(status!(request(test::TestRequest::get(), "/api/protected", "fsk_read")), 200);
+        assert_eq!(status!(request(test::TestRequest::post(), "/api/upload", "fsk_read")), 403);
+
+        assert_eq!(status!(request(test::TestRequest::get(), "/api/protected", "fsk_unknown")), 401);
+    }
+
+    #[actix_web::test]
+    async fn test_restricted_tokens_cannot_create_api_keys() {
+        let env = get_global_test_env().await;
+        let authentication_service = test_authentication_service();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_app_config(env.root_dir.path())))
+                .service(web::scope("/api").service(create_api_key_handler))
+        ).await;
+
+        let session = authentication_service.generate_token("test_user".to_string(), "user".to_string(), 1).unwrap();
+        let claims = authentication_service.validate_token(&session).unwrap();
+        let restricted = authentication_service.restrict_token(&claims, vec!["account".to_string()], None).unwrap();
+
+        let req = test::TestRequest::post()
+            .uri("/api/account/api-keys")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", restricted)))
+            .set_json(CreateApiKeyRequest { name: "backup".to_string(), expires_at: None, read_only: false })
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status().as_u16(), 403);
+    }
+}
@@ -4,15 +4,11 @@ mod tests {
     use std::fs;
     use std::fs::File;
     use std::io::Write;
-    use std::sync::Arc;
     use actix_web::http::header::AUTHORIZATION;
-    use crate::app_config::AppConfig;
     use crate::endpoints::system_operations::delete::{delete_file, delete_user_directory};
     use crate::models::authentication::auth_models::JwtAuth;
     use crate::models::system_operations::delete_file_request::DeleteEntityRequest;
-    use crate::services::authentication::authentication_service::{generate_jwt};
-    use crate::services::locking::directory_locking_manager::DirectoryLockManager;
-    use crate::tests::test_structure::get_global_test_env;
+    use crate::tests::test_structure::{generate_test_token, get_global_test_env, test_app_config};
 
     /// Test the `/directory/delete` endpoint when the target directory exists.
     #[actix_web::test]
@@ -31,9 +27,11 @@ mod tests {
         let payload = DeleteEntityRequest {
             name: dir_to_delete.to_string(),
             path: sub_path.to_string(),
+            owner: None,
+            group: None,
         };
 
-        let token = generate_jwt("test_user".to_string()).expect("failed to generate token");
+        let token = generate_test_token("test_user");
         // Create a test request.
         let req = test::TestRequest::post()
             .uri("/directory/delete")
@@ -41,10 +39,7 @@ mod tests {
             .set_json(&payload)
             .to_request();
 
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
         // Initialize an Actix Web App with the delete_user_directory endpoint.
         let app = test::init_service(
             App::new()
@@ -77,19 +72,18 @@ mod tests {
         let payload = DeleteEntityRequest {
             name: dir_name.to_string(),
             path: sub_path.to_string(),
+            owner: None,
+            group: None,
         };
 
-        let token = generate_jwt("test_user".to_string()).expect("failed to generate token");
+        let token = generate_test_token("test_user");
         let req = test::TestRequest::post()
             .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
             .uri("/directory/delete")
             .set_json(&payload)
             .to_request();
         
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
         let app = test::init_service(
             App::new()
             .app_data(web::Data::new(config))
@@ -118,19 +112,18 @@ mod tests {
         let payload = DeleteEntityRequest {
             name: name.to_string(),
             path: sub_path.to_string(),
+            owner: None,
+            group: None,
         };
 
-        let token = generate_jwt("test_user".to_string()).expect("failed to generate token");
+        let token = generate_test_token("test_user");
         let req = test::TestRequest::post()
             .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
             .uri("/directory/delete")
             .set_json(&payload)
             .to_request();
 
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(config))
@@ -163,19 +156,18 @@ mod tests {
         let payload = DeleteEntityRequest {
             name: filename.to_string(),
             path: sub_path.to_string(),
+            owner: None,
+            group: None,
         };
 
-        let token = generate_jwt("test_user".to_string()).expect("failed to generate token");
+        let token = generate_test_token("test_user");
         let req = test::TestRequest::post()
             .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
             .uri("/file/delete")
             .set_json(&payload)
             .to_request();
 
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(config))
@@ -206,19 +198,18 @@ mod tests {
         let payload = DeleteEntityRequest {
             name: filename.to_string(),
             path: sub_path.to_string(),
+            owner: None,
+            group: None,
         };
 
-        let token = generate_jwt("test_user".to_string()).expect("failed to generate token");
+        let token = generate_test_token("test_user");
         let req = test::TestRequest::post()
             .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
             .uri("/file/delete")
             .set_json(&payload)
             .to_request();
 
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(config))
@@ -247,19 +238,18 @@ mod tests {
         let payload = DeleteEntityRequest {
             name: name.to_string(),
             path: sub_path.to_string(),
+            owner: None,
+            group: None,
         };
 
-        let token = generate_jwt("test_user".to_string()).expect("failed to generate token");
+        let token = generate_test_token("test_user");
         let req = test::TestRequest::post()
             .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
             .uri("/file/delete")
             .set_json(&payload)
             .to_request();
 
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(config))
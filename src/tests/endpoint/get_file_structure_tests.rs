@@ -2,17 +2,13 @@
 mod tests {
     use std::fs;
     use std::fs::File;
-    use std::sync::Arc;
     use actix_web::http::header::AUTHORIZATION;
     use actix_web::{test, web, App};
-    use crate::app_config::AppConfig;
     use crate::endpoints::system_operations::get_file_structure::get_user_directory;
     use crate::models::authentication::auth_models::JwtAuth;
     use crate::models::file_structure::directory_tree::DirTree;
     use crate::models::file_structure::file_structure_request::FileStructureRequest;
-    use crate::services::authentication::authentication_service::generate_jwt;
-    use crate::services::locking::directory_locking_manager::DirectoryLockManager;
-    use crate::tests::test_structure::get_global_test_env;
+    use crate::tests::test_structure::{generate_test_token, get_global_test_env, test_app_config};
     
     #[actix_web::test]
     async fn test_get_structure_request() {
@@ -31,19 +27,18 @@ mod tests {
 
         let payload = FileStructureRequest {
             path: sub_path.to_string(),
+            owner: None,
+            group: None,
         };
 
-        let token = generate_jwt("test_user".to_string()).expect("failed to generate token");
+        let token = generate_test_token("test_user");
         let req = test::TestRequest::post()
             .uri("/structure")
             .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
             .set_json(&payload)
             .to_request();
 
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(config))
@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::AUTHORIZATION;
+    use actix_web::{test, web, App};
+    use crate::endpoints::authentication::authentication::logout_handler;
+    use crate::endpoints::system_operations::download::download_file_from_user_directory;
+    use crate::models::authentication::auth_models::JwtAuth;
+    use crate::models::system_operations::download_file_request::DownloadEntityRequest;
+    use crate::tests::test_structure::{generate_test_token, get_global_test_env, test_app_config};
+
+    #[actix_web::test]
+    async fn test_logout_revokes_token() {
+        let env = get_global_test_env().await;
+        let test_root = env.root_dir.path();
+
+        let config = test_app_config(test_root);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .wrap(JwtAuth)
+                .service(logout_handler)
+                .service(download_file_from_user_directory)
+        ).await;
+
+        let token = generate_test_token("test_user");
+        let other_token = generate_test_token("test_user");
+        let payload = DownloadEntityRequest {
+            path: "".to_string(),
+            name: "test_file.txt".to_string(),
+            owner: None,
+            group: None,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/logout")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        // The logged out token is refused from now on
+        let req = test::TestRequest::post()
+            .uri("/download")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+            .set_json(&payload)
+            .to_request();
+        let resp = test::try_call_service(&app, req).await;
+        assert_eq!(resp.unwrap_err().as_response_error().status_code(), 401);
+
+        // Other sessions of the same user are unaffected
+        let req = test::TestRequest::post()
+            .uri("/download")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", other_token)))
+            .set_json(&payload)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+    }
+}
@@ -2,4 +2,10 @@ mod delete_endpoints_tests;
 mod download_endpoint_tests;
 mod get_file_structure_tests;
 mod rename_endpoint_tests;
-mod upload_endpoint_tests;
\ No newline at end of file
+mod upload_endpoint_tests;
+mod logout_endpoint_tests;
+mod privilege_guard_tests;
+mod user_administration_endpoint_tests;
+mod api_key_endpoint_tests;
+
+mod tus_endpoint_tests;
\ No newline at end of file
@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::AUTHORIZATION;
+    use actix_web::{get, test, web, App, HttpResponse, Responder};
+    use crate::models::authentication::auth_user::{AdminUser, ScopedUser, ADMIN_PRIVILEGE};
+    use crate::models::authentication::scope::{FilesRead, FilesWrite};
+    use crate::tests::test_structure::{generate_test_token, get_global_test_env, test_app_config, test_authentication_service};
+
+    #[get("/admin-only")]
+    async fn admin_only(admin: AdminUser) -> impl Responder {
+        HttpResponse::Ok().body(format!("Hello, {}", admin.0.username()))
+    }
+
+    #[get("/read")]
+    async fn read(user: ScopedUser<FilesRead>) -> impl Responder {
+        HttpResponse::Ok().body(user.username().to_string())
+    }
+
+    #[get("/write")]
+    async fn write(user: ScopedUser<FilesWrite>) -> impl Responder {
+        HttpResponse::Ok().body(user.username().to_string())
+    }
+
+    #[actix_web::test]
+    async fn test_require_privilege() {
+        let env = get_global_test_env().await;
+        let config = test_app_config(env.root_dir.path());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .service(admin_only)
+        ).await;
+
+        let admin_token = test_authentication_service()
+            .generate_token("admin".to_string(), "admin".to_string(), ADMIN_PRIVILEGE)
+            .expect("failed to generate token");
+        let req = test::TestRequest::get()
+            .uri("/admin-only")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", admin_token)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body = test::read_body(resp).await;
+        assert_eq!(std::str::from_utf8(&body).unwrap(), "Hello, admin");
+
+        // A regular user is authenticated but not privileged enough
+        let req = test::TestRequest::get()
+            .uri("/admin-only")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", generate_test_token("test_user"))))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 403);
+
+        // Without a token the request is not authenticated at all
+        let req = test::TestRequest::get().uri("/admin-only").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[actix_web::test]
+    async fn test_scoped_token() {
+        let env = get_global_test_env().await;
+        let config = test_app_config(env.root_dir.path());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .service(admin_only)
+                .service(read)
+                .service(write)
+        ).await;
+
+        let authentication_service = test_authentication_service();
+        let admin_token = authentication_service
+            .generate_token("admin".to_string(), "admin".to_string(), ADMIN_PRIVILEGE)
+            .expect("failed to generate token");
+        let admin_claims = authentication_service.validate_token(&admin_token).unwrap();
+        let read_only_token = authentication_service
+            .restrict_token(&admin_claims, vec!["files:read".to_string()], None)
+            .expect("failed to restrict token");
+
+        let request = |uri: &str, token: &str| test::TestRequest::get()
+            .uri(uri)
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+            .to_request();
+
+        assert_eq!(test::call_service(&app, request("/read", &admin_token)).await.status(), 200);
+        assert_eq!(test::call_service(&app, request("/write", &admin_token)).await.status(), 200);
+        assert_eq!(test::call_service(&app, request("/read", &read_only_token)).await.status(), 200);
+        assert_eq!(test::call_service(&app, request("/write", &read_only_token)).await.status(), 403);
+        // The restricted token doesn't carry the privileges of the admin along
+        assert_eq!(test::call_service(&app, request("/admin-only", &read_only_token)).await.status(), 403);
+    }
+}
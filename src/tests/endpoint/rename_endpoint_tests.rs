@@ -3,15 +3,11 @@ mod tests {
     use std::fs::File;
     use std::io::Write;
     use std::path::Path;
-    use std::sync::Arc;
     use actix_web::{test, App, web, http::header::AUTHORIZATION};
     use tokio::fs;
-    use crate::app_config::AppConfig;
     use crate::models::system_operations::rename_item_request::RenameItemRequest;
     use crate::endpoints::system_operations::rename::{rename_directory};
-    use crate::services::authentication::authentication_service::generate_jwt;
-    use crate::services::locking::directory_locking_manager::DirectoryLockManager;
-    use crate::tests::test_structure::get_global_test_env;
+    use crate::tests::test_structure::{generate_test_token, get_global_test_env, test_app_config};
     
 
     #[actix_web::test]
@@ -39,10 +35,12 @@ mod tests {
             path: sub_path.to_string(),
             old_name: old_dir_name.to_string(),
             new_name: new_dir_name.to_string(),
+            owner: None,
+            group: None,
         };
 
         // 4. Create and sign a JWT token (assuming you have some utility for that)
-        let token = generate_jwt(username.to_string()).expect("failed to generate token");
+        let token = generate_test_token(username);
 
         // 5. Build the Actix test request
         let req = test::TestRequest::post()
@@ -52,10 +50,7 @@ mod tests {
             .to_request();
 
         // 6. Initialize the Actix test application with your config + service
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
 
         let app = test::init_service(
             App::new()
@@ -102,10 +97,12 @@ mod tests {
             path: sub_path.to_string(),
             old_name: old_dir_name.to_string(),
             new_name: new_dir_name.to_string(),
+            owner: None,
+            group: None,
         };
 
         // 3. Create token and request
-        let token = generate_jwt(username.to_string()).expect("failed to generate token");
+        let token = generate_test_token(username);
         let req = test::TestRequest::post()
             .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
             .uri("/directory/rename")
@@ -113,10 +110,7 @@ mod tests {
             .to_request();
 
         // 4. Initialize Actix app
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(config))
@@ -174,10 +168,12 @@ mod tests {
             path: sub_path.to_string(),
             old_name: old_file_name.to_string(),
             new_name: new_file_name.to_string(),
+            owner: None,
+            group: None,
         };
 
         // 4. Create token and request
-        let token = generate_jwt(username.to_string()).expect("failed to generate token");
+        let token = generate_test_token(username);
         let req = test::TestRequest::post()
             .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
             .uri("/directory/rename")
@@ -185,10 +181,7 @@ mod tests {
             .to_request();
 
         // 5. Initialize Actix app
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(config))
@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use actix_web::{test, web, App, http::header, http::StatusCode};
+    use tempfile::tempdir;
+    use crate::endpoints::system_operations::tus::{
+        tus_append_handler, tus_create_handler, tus_offset_handler, tus_options_handler, tus_terminate_handler
+    };
+    use crate::tests::test_structure::{generate_test_token, test_app_config};
+
+    macro_rules! tus_app {
+        ($root:expr) => {
+            test::init_service(
+                App::new()
+                    .app_data(web::Data::new(test_app_config($root)))
+                    .service(
+                        web::scope("/api/tus")
+                            .service(tus_options_handler)
+                            .service(tus_create_handler)
+                            .service(tus_offset_handler)
+                            .service(tus_append_handler)
+                            .service(tus_terminate_handler)
+                    )
+            ).await
+        };
+    }
+
+    fn tus_request(request: test::TestRequest, uri: &str, token: &str) -> test::TestRequest {
+        request
+            .uri(uri)
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .insert_header(("Tus-Resumable", "1.0.0"))
+    }
+
+    fn chunk(uri: &str, token: &str, offset: usize, data: &'static str) -> test::TestRequest {
+        tus_request(test::TestRequest::patch(), uri, token)
+            .insert_header((header::CONTENT_TYPE, "application/offset+octet-stream"))
+            .insert_header(("Upload-Offset", offset.to_string()))
+            .set_payload(data)
+    }
+
+    #[actix_web::test]
+    async fn test_resumable_upload() {
+        let root = tempdir().unwrap();
+        let app = tus_app!(root.path());
+        let token = generate_test_token("test_user");
+
+        // filename "notes.txt", path "docs"
+        let req = tus_request(test::TestRequest::post(), "/api/tus", &token)
+            .insert_header(("Upload-Length", "11"))
+            .insert_header(("Upload-Metadata", "filename bm90ZXMudHh0,path ZG9jcw=="))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        assert!(resp.headers().contains_key("Upload-Expires"));
+        let location = resp.headers().get(header::LOCATION).unwrap().to_str().unwrap().to_string();
+
+        let resp = test::call_service(&app, chunk(&location, &token, 0, "hello ").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert_eq!(resp.headers().get("Upload-Offset").unwrap(), "6");
+
+        let resp = test::call_service(&app, chunk(&location, &token, 0, "hello ").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+        let req = tus_request(test::TestRequest::default().method(actix_web::http::Method::HEAD), &location, &token).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("Upload-Offset").unwrap(), "6");
+        assert_eq!(resp.headers().get("Upload-Length").unwrap(), "11");
+        assert!(!root.path().join("test_user/docs/notes.txt").exists());
+
+        let resp = test::call_service(&app, chunk(&location, &token, 6, "world").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert_eq!(fs::read_to_string(root.path().join("test_user/docs/notes.txt")).unwrap(), "hello world");
+
+        let req = tus_request(test::TestRequest::default().method(actix_web::http::Method::HEAD), &location, &token).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_terminate_upload() {
+        let root = tempdir().unwrap();
+        let app = tus_app!(root.path());
+        let token = generate_test_token("test_user");
+
+        let req = tus_request(test::TestRequest::post(), "/api/tus", &token)
+            .insert_header(("Upload-Length", "5"))
+            .insert_header(("Upload-Metadata", "filename bm90ZXMudHh0"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let location = resp.headers().get(header::LOCATION).unwrap().to_str().unwrap().to_string();
+
+        let other_token = generate_test_token("other_user");
+        let req = tus_request(test::TestRequest::delete(), &location, &other_token).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+
+        let req = tus_request(test::TestRequest::delete(), &location, &token).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+        let resp = test::call_service(&app, chunk(&location, &token, 0, "hello").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_protocol_checks() {
+        let root = tempdir().unwrap();
+        let app = tus_app!(root.path());
+        let token = generate_test_token("test_user");
+
+        let req = test::TestRequest::default()
+            .method(actix_web::http::Method::OPTIONS)
+            .uri("/api/tus")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        assert_eq!(resp.headers().get("Tus-Version").unwrap(), "1.0.0");
+
+        let req = test::TestRequest::post()
+            .uri("/api/tus")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .insert_header(("Upload-Length", "5"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::PRECONDITION_FAILED);
+
+        let req = tus_request(test::TestRequest::post(), "/api/tus", &token)
+            .insert_header(("Upload-Length", "5"))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+
+        let req = tus_request(test::TestRequest::patch(), "/api/tus/abcdef", &token)
+            .insert_header(("Upload-Offset", "0"))
+            .set_payload("hello")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+}
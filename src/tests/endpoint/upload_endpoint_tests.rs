@@ -1,15 +1,11 @@
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
     use std::fs;
     use std::io::{Cursor, Read};
     use actix_web::{test, web, App, http::header, http::StatusCode};
     use multipart::client::lazy::Multipart;
-    use crate::app_config::AppConfig;
-    use crate::endpoints::system_operations::upload::upload_file_from_user_directory;
-    use crate::services::authentication::authentication_service::generate_jwt;
-    use crate::services::locking::directory_locking_manager::DirectoryLockManager;
-    use crate::tests::test_structure::get_global_test_env;
+    use crate::endpoints::system_operations::upload::{upload_archive_handler, upload_file_from_user_directory};
+    use crate::tests::test_structure::{generate_test_token, get_global_test_env, test_app_config};
 
     #[actix_web::test]
     async fn test_upload_file_success() {
@@ -20,7 +16,7 @@ mod tests {
         let file_name = "example.txt";
         let file_content = "Hello, world!";
 
-        let token = generate_jwt(username.to_string()).expect("failed to generate token");
+        let token = generate_test_token(username);
 
         let mut form = Multipart::new();
         form.add_stream(
@@ -45,10 +41,7 @@ mod tests {
         prepared_form.read_to_end(&mut form_bytes).unwrap();
 
         // 4. Create the Actix test application
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(config))
@@ -67,11 +60,10 @@ mod tests {
         // 6. Call the service and verify
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), StatusCode::OK, "Upload should succeed");
-        let body = test::read_body(resp).await;
-        assert_eq!(
-            std::str::from_utf8(&body).unwrap(),
-            "File uploaded successfully"
-        );
+        let results: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(results[0]["status"], 200);
+        assert_eq!(results[0]["stored_as"], format!("{}/{}", subdir, file_name));
+        assert_eq!(results[0]["size"], file_content.len());
 
         // 7. Confirm file was actually written
         let expected_path = test_root.join(username).join(subdir).join(file_name);
@@ -93,7 +85,7 @@ mod tests {
         let file_name = "example.txt";
         let file_content = "Hello, world!";
 
-        let token = generate_jwt(username.to_string()).expect("failed to generate token");
+        let token = generate_test_token(username);
 
         // Only send the "file" field, not "path"
         let mut form = Multipart::new();
@@ -109,10 +101,7 @@ mod tests {
         let mut form_bytes = Vec::new();
         prepared_form.read_to_end(&mut form_bytes).unwrap();
 
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(config))
@@ -145,7 +134,7 @@ mod tests {
         let username = "test_user";
         let subdir = "some/subdir";
 
-        let token = generate_jwt(username.to_string()).expect("failed to generate token");
+        let token = generate_test_token(username);
 
         // Only send the "path" field, not "file"
         let mut form = Multipart::new();
@@ -161,10 +150,7 @@ mod tests {
         let mut form_bytes = Vec::new();
         prepared_form.read_to_end(&mut form_bytes).unwrap();
 
-        let config = AppConfig {
-            root_dir: Arc::new(test_root.to_str().unwrap().to_string()),
-            directory_lock_manager: DirectoryLockManager::new()
-        };
+        let config = test_app_config(test_root);
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(config))
@@ -190,4 +176,203 @@ mod tests {
         );
     }
 
+    /// A multipart body with a file and any number of text fields, and its content type
+    fn upload_form(fields: &[(&str, &str)], file_name: &str, file_content: &'static str) -> (String, Vec<u8>) {
+        let mut form = Multipart::new();
+        for (name, value) in fields {
+            form.add_stream(name.to_string(), Cursor::new(value.to_string()), None::<&str>, None);
+        }
+        form.add_stream("file", Cursor::new(file_content), Some(file_name.to_string()), None);
+        let mut prepared_form = form.prepare().unwrap();
+        let content_type = format!("multipart/form-data; boundary={}", prepared_form.boundary());
+        let mut form_bytes = Vec::new();
+        prepared_form.read_to_end(&mut form_bytes).unwrap();
+        (content_type, form_bytes)
+    }
+
+    #[actix_web::test]
+    async fn test_upload_file_conflicts() {
+        let test_root = tempfile::tempdir().unwrap();
+        let token = generate_test_token("test_user");
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_app_config(test_root.path())))
+                .service(upload_file_from_user_directory)
+        )
+            .await;
+        let upload = |uri: &str, fields: &[(&str, &str)], content: &'static str| {
+            let (content_type, form_bytes) = upload_form(fields, "report.txt", content);
+            test::TestRequest::post()
+                .uri(uri)
+                .insert_header((header::CONTENT_TYPE, content_type))
+                .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+                .set_payload(form_bytes)
+        };
+
+        let resp = test::call_service(&app, upload("/upload", &[("path", "docs")], "v1").to_request()).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let results: serde_json::Value = test::read_body_json(resp).await;
+        let etag = results[0]["etag"].as_str().unwrap().to_string();
+
+        let req = upload("/upload?conflict=reject", &[("path", "docs")], "v2").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CONFLICT);
+
+        let req = upload("/upload", &[("path", "docs"), ("conflict", "keep-both")], "v2").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let results: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(results[0]["stored_as"], "docs/report (1).txt");
+
+        let req = upload("/upload?conflict=if-match", &[("path", "docs")], "v3").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::PRECONDITION_REQUIRED);
+
+        let req = upload("/upload?conflict=if-match", &[("path", "docs")], "v3")
+            .insert_header((header::IF_MATCH, etag.clone()))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+        // The file changed, the ETag of the first version no longer matches
+        let req = upload("/upload?conflict=if-match", &[("path", "docs")], "v4")
+            .insert_header((header::IF_MATCH, etag))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::PRECONDITION_FAILED);
+        let docs = test_root.path().join("test_user").join("docs");
+        assert_eq!(fs::read_to_string(docs.join("report.txt")).unwrap(), "v3");
+        assert_eq!(fs::read_to_string(docs.join("report (1).txt")).unwrap(), "v2");
+
+        let req = upload("/upload?conflict=replace", &[("path", "docs")], "v5").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_upload_text_field_too_long() {
+        let test_root = tempfile::tempdir().unwrap();
+        let token = generate_test_token("test_user");
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_app_config(test_root.path())))
+                .service(upload_file_from_user_directory)
+        )
+            .await;
+
+        let path = "docs/".repeat(1000);
+        let (content_type, form_bytes) = upload_form(&[("path", &path)], "report.txt", "v1");
+        let req = test::TestRequest::post()
+            .uri("/upload")
+            .insert_header((header::CONTENT_TYPE, content_type))
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .set_payload(form_bytes)
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(!test_root.path().join("test_user").join("docs").exists());
+    }
+
+    #[actix_web::test]
+    async fn test_upload_folder() {
+        let test_root = tempfile::tempdir().unwrap();
+        let token = generate_test_token("test_user");
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_app_config(test_root.path())))
+                .service(upload_file_from_user_directory)
+        )
+            .await;
+
+        // Browsers send the files of a dropped folder with their webkitRelativePath as the filename
+        let mut form = Multipart::new();
+        form.add_stream("path", Cursor::new("pictures"), None::<&str>, None);
+        form.add_stream("file", Cursor::new("first"), Some("holiday/day1/beach.jpg"), None);
+        form.add_stream("file", Cursor::new("second"), Some("holiday/day2/hotel.jpg"), None);
+        form.add_stream("file", Cursor::new("evil"), Some("holiday/../../escape.txt"), None);
+        form.add_stream("file", Cursor::new("third"), Some("notes.txt"), None);
+        let mut prepared_form = form.prepare().unwrap();
+        let content_type = format!("multipart/form-data; boundary={}", prepared_form.boundary());
+        let mut form_bytes = Vec::new();
+        prepared_form.read_to_end(&mut form_bytes).unwrap();
+
+        let req = test::TestRequest::post()
+            .uri("/upload")
+            .insert_header((header::CONTENT_TYPE, content_type))
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .set_payload(form_bytes)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // Some files were stored and one wasn't
+        assert_eq!(resp.status(), StatusCode::MULTI_STATUS);
+        let results: serde_json::Value = test::read_body_json(resp).await;
+        let results = results.as_array().unwrap();
+        assert_eq!(results.len(), 4);
+        let result = |name: &str| results.iter().find(|result| result["name"] == name).unwrap();
+        assert_eq!(result("holiday/day1/beach.jpg")["stored_as"], "pictures/holiday/day1/beach.jpg");
+        assert_eq!(result("holiday/day2/hotel.jpg")["stored_as"], "pictures/holiday/day2/hotel.jpg");
+        assert_eq!(result("holiday/day2/hotel.jpg")["size"], 6);
+        assert_eq!(result("holiday/../../escape.txt")["status"], 400);
+        assert!(result("holiday/../../escape.txt").get("stored_as").is_none());
+        assert_eq!(result("notes.txt")["status"], 200);
+
+        let tree = test_root.path().join("test_user");
+        assert_eq!(fs::read_to_string(tree.join("pictures/holiday/day1/beach.jpg")).unwrap(), "first");
+        assert_eq!(fs::read_to_string(tree.join("pictures/holiday/day2/hotel.jpg")).unwrap(), "second");
+        assert_eq!(fs::read_to_string(tree.join("pictures/notes.txt")).unwrap(), "third");
+        assert!(!test_root.path().join("escape.txt").exists());
+
+        // The folder itself can't leave the tree either
+        let (content_type, form_bytes) = upload_form(&[("path", "pictures/../../other")], "notes.txt", "evil");
+        let req = test::TestRequest::post()
+            .uri("/upload")
+            .insert_header((header::CONTENT_TYPE, content_type))
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .set_payload(form_bytes)
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+        assert!(!test_root.path().join("other").exists());
+    }
+
+    #[actix_web::test]
+    async fn test_upload_archive() {
+        let test_root = tempfile::tempdir().unwrap();
+        let token = generate_test_token("test_user");
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_app_config(test_root.path())))
+                .service(upload_archive_handler)
+        )
+            .await;
+        let docs = test_root.path().join("test_user").join("docs");
+        fs::create_dir_all(&docs).unwrap();
+        fs::write(docs.join("notes.txt"), "existing").unwrap();
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in [("notes.txt", "extracted"), ("2024/report.txt", "report")] {
+            zip.start_file(name, zip::write::FileOptions::default()).unwrap();
+            std::io::Write::write_all(&mut zip, content.as_bytes()).unwrap();
+        }
+        let archive = zip.finish().unwrap().into_inner();
+
+        let mut form = Multipart::new();
+        form.add_stream("path", Cursor::new("docs"), None::<&str>, None);
+        form.add_stream("file", Cursor::new(archive), Some("docs.zip"), None);
+        let mut prepared_form = form.prepare().unwrap();
+        let content_type = format!("multipart/form-data; boundary={}", prepared_form.boundary());
+        let mut form_bytes = Vec::new();
+        prepared_form.read_to_end(&mut form_bytes).unwrap();
+
+        let req = test::TestRequest::post()
+            .uri("/upload/archive?conflict=keep-both")
+            .insert_header((header::CONTENT_TYPE, content_type))
+            .insert_header((header::AUTHORIZATION, format!("Bearer {}", token)))
+            .set_payload(form_bytes)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let results: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(results[0]["stored_as"], "docs/2024/report.txt");
+        assert_eq!(results[1]["stored_as"], "docs/notes (1).txt");
+
+        assert_eq!(fs::read_to_string(docs.join("notes.txt")).unwrap(), "existing");
+        assert_eq!(fs::read_to_string(docs.join("notes (1).txt")).unwrap(), "extracted");
+        assert_eq!(fs::read_to_string(docs.join("2024/report.txt")).unwrap(), "report");
+        assert_eq!(fs::read_dir(test_root.path().join("_staging")).unwrap().count(), 0);
+    }
 }
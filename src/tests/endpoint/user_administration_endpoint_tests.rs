@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod tests {
+    use actix_web::http::header::AUTHORIZATION;
+    use actix_web::{test, web, App};
+    use serde_json::json;
+    use crate::endpoints::authentication::user_administration::{list_users_handler, set_disabled_handler};
+    use crate::models::authentication::auth_user::ADMIN_PRIVILEGE;
+    use crate::tests::test_structure::{generate_test_token, get_global_test_env, test_app_config, test_authentication_service};
+
+    #[actix_web::test]
+    async fn test_user_administration_requires_admin() {
+        let env = get_global_test_env().await;
+        let config = test_app_config(env.root_dir.path());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .service(
+                    web::scope("/admin/users")
+                        .service(list_users_handler)
+                        .service(set_disabled_handler)
+                )
+        ).await;
+
+        let req = test::TestRequest::get()
+            .uri("/admin/users")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", generate_test_token("test_user"))))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 403);
+
+        let req = test::TestRequest::post()
+            .uri("/admin/users/admin/disabled")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", generate_test_token("test_user"))))
+            .set_json(json!({ "disabled": true }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 403);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_cannot_disable_themselves() {
+        let env = get_global_test_env().await;
+        let config = test_app_config(env.root_dir.path());
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .service(web::scope("/admin/users").service(set_disabled_handler))
+        ).await;
+
+        let admin_token = test_authentication_service()
+            .generate_token("admin".to_string(), "admin".to_string(), ADMIN_PRIVILEGE)
+            .expect("failed to generate token");
+        let req = test::TestRequest::post()
+            .uri("/admin/users/admin/disabled")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", admin_token)))
+            .set_json(json!({ "disabled": true }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+}
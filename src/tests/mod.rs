@@ -1,3 +1,4 @@
 pub mod service;
 pub mod endpoint;
+#[cfg(test)]
 pub mod test_structure;
\ No newline at end of file
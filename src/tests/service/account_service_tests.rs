@@ -0,0 +1,245 @@
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use mockall::mock;
+    use mockall::predicate::*;
+    use tempfile::tempdir;
+    use crate::dao::user_store::UserStore;
+    use crate::models::authentication::user_administration::UserSummary;
+    use crate::services::authentication::account_service::{validate_username, AccountService, ARCHIVE_DIR, EXTERNAL_PASSWORD_HASH};
+    use crate::services::locking::directory_locking_manager::DirectoryLockManager;
+
+    mock! {
+        pub UserStoreMock {}
+
+        #[async_trait]
+        impl UserStore for UserStoreMock {
+            async fn create_user(&self, username: &str, password_hash: &str, role: &str) -> Result<bool, String>;
+            async fn upsert_external_user(&self, username: &str, password_hash: &str, role: &str) -> Result<Option<bool>, String>;
+            async fn get_password_hash(&self, username: &str) -> Result<Option<String>, String>;
+            async fn update_password_hash(&self, username: &str, password_hash: &str) -> Result<(), String>;
+            async fn delete_user(&self, username: &str) -> Result<(), String>;
+            async fn is_registration_enabled(&self) -> Result<bool, String>;
+            async fn set_registration_enabled(&self, enabled: bool) -> Result<(), String>;
+            async fn list_users(&self) -> Result<Vec<UserSummary>, String>;
+            async fn set_user_role(&self, username: &str, role: &str) -> Result<(), String>;
+            async fn set_user_disabled(&self, username: &str, disabled: bool) -> Result<(), String>;
+            async fn role_exists(&self, role: &str) -> Result<bool, String>;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_creates_user_and_directory() {
+        let root = tempdir().unwrap();
+        let mut mock_store = MockUserStoreMock::new();
+        mock_store.expect_is_registration_enabled().returning(|| Ok(true));
+        mock_store.expect_create_user()
+            .withf(|username, hash, role| {
+                username == "new_user" && role == "user" && bcrypt::verify("password123", hash).unwrap()
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(true));
+
+        let service = AccountService::new(
+            mock_store,
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+
+        assert!(service.register("new_user", "password123").await.is_ok());
+        assert!(root.path().join("new_user").is_dir());
+    }
+
+    #[tokio::test]
+    async fn test_register_rejected() {
+        let root = tempdir().unwrap();
+        let mut mock_store = MockUserStoreMock::new();
+        mock_store.expect_is_registration_enabled().returning(|| Ok(true));
+        mock_store.expect_create_user()
+            .with(eq("taken"), always(), always())
+            .returning(|_, _, _| Ok(false));
+
+        let service = AccountService::new(
+            mock_store,
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+
+        assert_eq!(service.register("taken", "password123").await.unwrap_err().0, 409);
+        assert_eq!(service.register("short_pw", "short").await.unwrap_err().0, 400);
+        assert_eq!(service.register("../escape", "password123").await.unwrap_err().0, 400);
+
+        let mut closed_store = MockUserStoreMock::new();
+        closed_store.expect_is_registration_enabled().returning(|| Ok(false));
+        closed_store.expect_create_user().never();
+        let service = AccountService::new(
+            closed_store,
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+        assert_eq!(service.register("new_user", "password123").await.unwrap_err().0, 403);
+    }
+
+    #[tokio::test]
+    async fn test_change_password_requires_old_password() {
+        let root = tempdir().unwrap();
+        let old_hash = bcrypt::hash("old_password", 4).unwrap();
+        let mut mock_store = MockUserStoreMock::new();
+        mock_store.expect_get_password_hash()
+            .returning(move |_| Ok(Some(old_hash.clone())));
+        mock_store.expect_update_password_hash()
+            .withf(|username, hash| username == "test_user" && bcrypt::verify("new_password", hash).unwrap())
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let service = AccountService::new(
+            mock_store,
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+
+        let wrong = service.change_password("test_user", "wrong_password", "new_password").await;
+        assert_eq!(wrong.unwrap_err().0, 401);
+        assert!(service.change_password("test_user", "old_password", "new_password").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_delete_account_archives_directory() {
+        let root = tempdir().unwrap();
+        std::fs::create_dir(root.path().join("test_user")).unwrap();
+        std::fs::write(root.path().join("test_user").join("photo.png"), b"data").unwrap();
+        let hash = bcrypt::hash("password123", 4).unwrap();
+        let mut mock_store = MockUserStoreMock::new();
+        mock_store.expect_get_password_hash()
+            .returning(move |_| Ok(Some(hash.clone())));
+        mock_store.expect_delete_user()
+            .with(eq("test_user"))
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = AccountService::new(
+            mock_store,
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+
+        assert!(service.delete_account("test_user", "password123").await.is_ok());
+        assert!(!root.path().join("test_user").exists());
+
+        let archived: Vec<_> = std::fs::read_dir(root.path().join(ARCHIVE_DIR)).unwrap().collect();
+        assert_eq!(archived.len(), 1);
+        assert!(archived[0].as_ref().unwrap().path().join("photo.png").exists());
+    }
+
+    #[tokio::test]
+    async fn test_admin_role_management() {
+        let root = tempdir().unwrap();
+        let mut mock_store = MockUserStoreMock::new();
+        mock_store.expect_role_exists()
+            .with(eq("family"))
+            .returning(|_| Ok(true));
+        mock_store.expect_role_exists()
+            .with(eq("superuser"))
+            .returning(|_| Ok(false));
+        mock_store.expect_create_user()
+            .with(eq("cousin"), always(), eq("family"))
+            .times(1)
+            .returning(|_, _, _| Ok(true));
+        mock_store.expect_set_user_role()
+            .with(eq("cousin"), eq("family"))
+            .returning(|_, _| Ok(()));
+        mock_store.expect_set_user_role()
+            .with(eq("ghost"), eq("family"))
+            .returning(|_, _| Err("User not found".to_string()));
+
+        let service = AccountService::new(
+            mock_store,
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+
+        // Admins can create users even when registration is disabled
+        assert!(service.create_account_with_role("cousin", "password123", "family").await.is_ok());
+        assert!(root.path().join("cousin").is_dir());
+        assert_eq!(service.create_account_with_role("other", "password123", "superuser").await.unwrap_err().0, 400);
+
+        assert!(service.change_role("cousin", "family").await.is_ok());
+        assert_eq!(service.change_role("cousin", "superuser").await.unwrap_err().0, 400);
+        assert_eq!(service.change_role("ghost", "family").await.unwrap_err().0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_admin_reset_password_and_disable() {
+        let root = tempdir().unwrap();
+        let mut mock_store = MockUserStoreMock::new();
+        mock_store.expect_update_password_hash()
+            .withf(|username, hash| username == "cousin" && bcrypt::verify("new_password", hash).unwrap())
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock_store.expect_set_user_disabled()
+            .with(eq("cousin"), eq(true))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock_store.expect_set_user_disabled()
+            .with(eq("ghost"), always())
+            .returning(|_, _| Err("User not found".to_string()));
+
+        let service = AccountService::new(
+            mock_store,
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+
+        assert_eq!(service.reset_password("cousin", "short").await.unwrap_err().0, 400);
+        assert!(service.reset_password("cousin", "new_password").await.is_ok());
+        assert!(service.set_disabled("cousin", true).await.is_ok());
+        assert_eq!(service.set_disabled("ghost", true).await.unwrap_err().0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_provision_external_account() {
+        let root = tempdir().unwrap();
+        let mut mock_store = MockUserStoreMock::new();
+        mock_store.expect_role_exists().returning(|role| Ok(role != "wizard"));
+        mock_store.expect_upsert_external_user()
+            .with(eq("jdoe"), eq(EXTERNAL_PASSWORD_HASH), eq("admin"))
+            .times(1)
+            .returning(|_, _, _| Ok(Some(false)));
+        mock_store.expect_upsert_external_user()
+            .with(eq("gone"), always(), always())
+            .returning(|_, _, _| Ok(Some(true)));
+        mock_store.expect_upsert_external_user()
+            .with(eq("admin"), always(), always())
+            .returning(|_, _, _| Ok(None));
+        mock_store.expect_get_password_hash()
+            .returning(|_| Ok(Some(EXTERNAL_PASSWORD_HASH.to_string())));
+
+        let service = AccountService::new(
+            mock_store,
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+
+        assert!(service.provision_external_account("jdoe", "admin").await.is_ok());
+        assert!(root.path().join("jdoe").is_dir());
+        assert_eq!(service.provision_external_account("gone", "user").await.unwrap_err().0, 403);
+        // The directory can't take over a local account of the same name
+        assert_eq!(service.provision_external_account("admin", "admin").await.unwrap_err().0, 403);
+        assert!(!root.path().join("admin").exists());
+        assert_eq!(service.provision_external_account("jdoe", "wizard").await.unwrap_err().0, 400);
+        assert_eq!(service.provision_external_account("../etc", "user").await.unwrap_err().0, 400);
+        // The directory owns the password
+        assert_eq!(service.change_password("jdoe", "anything", "new_password").await.unwrap_err().0, 400);
+    }
+
+    #[test]
+    fn test_validate_username() {
+        assert!(validate_username("adonev").is_ok());
+        assert!(validate_username("john.doe-2").is_ok());
+        assert!(validate_username("").is_err());
+        assert!(validate_username("_groups").is_err());
+        assert!(validate_username(".staging").is_err());
+        assert!(validate_username("a/b").is_err());
+        assert!(validate_username(&"a".repeat(51)).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::Arc;
+    use async_trait::async_trait;
+    use mockall::mock;
+    use mockall::predicate::*;
+    use tempfile::tempdir;
+    use crate::dao::acl_store::AclStore;
+    use crate::models::acl::acl_entry::{AclEntry, AclPermission, AclPrincipal};
+    use crate::services::file_structure::acl_service::{normalize_acl_path, AclCheck, AclService};
+    use crate::services::file_structure::delete_service::DeleteService;
+use crate::services::file_structure::rename_service::RenameService;
+    use crate::services::locking::directory_locking_manager::DirectoryLockManager;
+
+    mock! {
+        pub AclStoreMock {}
+
+        #[async_trait]
+        impl AclStore for AclStoreMock {
+            async fn get_entries(&self, tree: &str, paths: &[String]) -> Result<Vec<AclEntry>, String>;
+            async fn get_user_groups(&self, username: &str) -> Result<Vec<String>, String>;
+            async fn set_entry(&self, tree: &str, entry: &AclEntry) -> Result<(), String>;
+            async fn remove_entry(&self, tree: &str, path: &str, principal: &AclPrincipal) -> Result<bool, String>;
+            async fn move_entries(&self, tree: &str, old_path: &str, new_path: &str) -> Result<(), String>;
+            async fn delete_entries(&self, tree: &str, path: &str) -> Result<(), String>;
+        }
+    }
+
+    fn entry(path: &str, principal: AclPrincipal, permissions: Vec<AclPermission>, inherit: bool) -> AclEntry {
+        AclEntry { path: path.to_string(), principal, permissions, inherit }
+    }
+
+    /// alice's tree: bob may read the holiday pictures, the family group may also add to
+    /// them, and carol may only see the folder itself
+    fn holiday_store() -> MockAclStoreMock {
+        let entries = [
+            entry("pictures/holiday", AclPrincipal::User("bob".to_string()), vec![AclPermission::Read], true),
+            entry("pictures/holiday", AclPrincipal::Group("family".to_string()), vec![AclPermission::Read, AclPermission::Write], true),
+            entry("pictures/holiday", AclPrincipal::User("carol".to_string()), vec![AclPermission::Read], false),
+        ];
+
+        let mut mock_store = MockAclStoreMock::new();
+        mock_store.expect_get_entries()
+            .with(eq("alice"), always())
+            .returning(move |_, paths| Ok(entries.iter().filter(|e| paths.contains(&e.path)).cloned().collect()));
+        mock_store.expect_get_user_groups()
+            .returning(|username| Ok(if username == "dave" { vec!["family".to_string()] } else { Vec::new() }));
+        mock_store
+    }
+
+    #[tokio::test]
+    async fn test_effective_permissions() {
+        let service = AclService::new(Arc::new(holiday_store()));
+
+        assert_eq!(service.effective_permissions("bob", "alice", "pictures/holiday").await.unwrap(), vec![AclPermission::Read]);
+        assert_eq!(service.effective_permissions("bob", "alice", "/pictures//holiday/day1/beach.png").await.unwrap(), vec![AclPermission::Read]);
+        assert!(service.effective_permissions("bob", "alice", "pictures").await.unwrap().is_empty());
+        assert!(service.effective_permissions("bob", "alice", "pictures/holiday_2023").await.unwrap().is_empty());
+
+        // Through the group
+        let permissions = service.effective_permissions("dave", "alice", "pictures/holiday/beach.png").await.unwrap();
+        assert_eq!(permissions, vec![AclPermission::Read, AclPermission::Write]);
+
+        // Entries that don't inherit only apply to their own path
+        assert_eq!(service.effective_permissions("carol", "alice", "pictures/holiday").await.unwrap(), vec![AclPermission::Read]);
+        assert!(service.effective_permissions("carol", "alice", "pictures/holiday/beach.png").await.unwrap().is_empty());
+
+        assert!(service.check("bob", "alice", "pictures/holiday/beach.png", AclPermission::Read).await.is_ok());
+        assert_eq!(service.check("bob", "alice", "pictures/holiday/beach.png", AclPermission::Delete).await.unwrap_err().0, 403);
+        assert_eq!(service.check("bob", "alice", "pictures/holiday/../secret", AclPermission::Read).await.unwrap_err().0, 400);
+    }
+
+    #[tokio::test]
+    async fn test_view_and_edit() {
+        let mut mock_store = holiday_store();
+        mock_store.expect_set_entry()
+            .withf(|tree, entry| tree == "alice" && entry.path == "pictures/holiday")
+            .returning(|_, entry| match entry.principal.name() {
+                "ghost" => Err("Principal not found".to_string()),
+                _ => Ok(()),
+            });
+        mock_store.expect_remove_entry()
+            .returning(|_, _, principal| Ok(principal == &AclPrincipal::User("bob".to_string())));
+        let service = AclService::new(Arc::new(mock_store));
+
+        let view = service.view("alice", "pictures/holiday/beach.png").await.unwrap();
+        assert_eq!(view.path, "pictures/holiday/beach.png");
+        assert!(view.entries.is_empty());
+        assert_eq!(view.inherited.len(), 2);
+        assert_eq!(service.view("alice", "pictures/holiday").await.unwrap().entries.len(), 3);
+
+        let grant = |name: &str, permissions| entry("/pictures/holiday/", AclPrincipal::User(name.to_string()), permissions, true);
+        assert!(service.set_entry("alice", grant("erin", vec![AclPermission::Read])).await.is_ok());
+        assert_eq!(service.set_entry("alice", grant("ghost", vec![AclPermission::Read])).await.unwrap_err().0, 404);
+        assert_eq!(service.set_entry("alice", grant("erin", Vec::new())).await.unwrap_err().0, 400);
+
+        assert!(service.remove_entry("alice", "pictures/holiday", &AclPrincipal::User("bob".to_string())).await.is_ok());
+        assert_eq!(service.remove_entry("alice", "pictures/holiday", &AclPrincipal::User("erin".to_string())).await.unwrap_err().0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_services_enforce_the_acl() {
+        let root = tempdir().unwrap();
+        let holiday = root.path().join("alice").join("pictures").join("holiday");
+        fs::create_dir_all(&holiday).unwrap();
+        fs::write(holiday.join("beach.png"), b"sand").unwrap();
+
+        let mut mock_store = MockAclStoreMock::new();
+        mock_store.expect_get_entries()
+            .returning(|_, _| Ok(vec![entry(
+                "pictures/holiday",
+                AclPrincipal::User("bob".to_string()),
+                vec![AclPermission::Read, AclPermission::Delete],
+                true
+            )]));
+        mock_store.expect_get_user_groups().returning(|_| Ok(Vec::new()));
+        let acl_service = AclService::new(Arc::new(mock_store));
+
+        let delete_service = |username: &str| DeleteService::new(
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        ).with_acl(Some(AclCheck::new(acl_service.clone(), username.to_string())));
+
+        let result = delete_service("mallory")
+            .delete_file(&"alice".to_string(), &"pictures/holiday".to_string(), &"beach.png".to_string())
+            .await;
+        assert_eq!(result.unwrap_err().0, 403);
+        assert!(holiday.join("beach.png").exists());
+
+        let result = delete_service("bob")
+            .delete_file(&"alice".to_string(), &"pictures/holiday".to_string(), &"beach.png".to_string())
+            .await;
+        assert!(result.is_ok());
+        assert!(!holiday.join("beach.png").exists());
+    }
+
+    #[tokio::test]
+    async fn test_rename_needs_delete() {
+        let root = tempdir().unwrap();
+        let holiday = root.path().join("alice").join("pictures").join("holiday");
+        fs::create_dir_all(&holiday).unwrap();
+        fs::write(holiday.join("beach.png"), b"sand").unwrap();
+
+        // dave may add to the holiday pictures through the family group, erin may also take them away
+        let erin = entry(
+            "pictures/holiday",
+            AclPrincipal::User("erin".to_string()),
+            vec![AclPermission::Read, AclPermission::Write, AclPermission::Delete],
+            true
+        );
+        let mut mock_store = MockAclStoreMock::new();
+        let entries = [
+            entry("pictures/holiday", AclPrincipal::Group("family".to_string()), vec![AclPermission::Read, AclPermission::Write], true),
+            erin,
+        ];
+        mock_store.expect_get_entries()
+            .returning(move |_, paths| Ok(entries.iter().filter(|e| paths.contains(&e.path)).cloned().collect()));
+        mock_store.expect_get_user_groups()
+            .returning(|username| Ok(if username == "dave" { vec!["family".to_string()] } else { Vec::new() }));
+        let acl_service = AclService::new(Arc::new(mock_store));
+
+        let rename = |username: &str, path: &str, old_name: &str, new_name: &str| {
+            let rename_service = RenameService::new(root.path().to_str().unwrap().to_string())
+                .with_acl(Some(AclCheck::new(acl_service.clone(), username.to_string())));
+            let (path, old_name, new_name) = (path.to_string(), old_name.to_string(), new_name.to_string());
+            async move { rename_service.rename_directory(&"alice".to_string(), &path, &old_name, &new_name).await }
+        };
+
+        // Write alone doesn't allow taking a file away, neither by renaming nor by moving it
+        assert_eq!(rename("dave", "pictures/holiday", "beach.png", "sunny.png").await.unwrap_err().0, 403);
+        assert_eq!(rename("dave", "pictures/holiday", "beach.png", "../beach.png").await.unwrap_err().0, 403);
+        assert!(holiday.join("beach.png").exists());
+
+        // Moving needs write access where the item ends up, which can't be outside of the tree
+        assert_eq!(rename("erin", "pictures/holiday", "beach.png", "../../../beach.png").await.unwrap_err().0, 400);
+        assert_eq!(rename("erin", "pictures", "holiday", "archive/holiday").await.unwrap_err().0, 403);
+        assert!(rename("erin", "pictures/holiday", "beach.png", "sunny.png").await.is_ok());
+        assert!(holiday.join("sunny.png").exists());
+    }
+
+    #[test]
+    fn test_normalize_acl_path() {
+        assert_eq!(normalize_acl_path("").unwrap(), "");
+        assert_eq!(normalize_acl_path("/").unwrap(), "");
+        assert_eq!(normalize_acl_path("./pictures//holiday/").unwrap(), "pictures/holiday");
+        assert_eq!(normalize_acl_path("pictures\\holiday").unwrap(), "pictures/holiday");
+        assert_eq!(normalize_acl_path("pictures/../secret").unwrap_err().0, 400);
+    }
+}
@@ -0,0 +1,149 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use async_trait::async_trait;
+    use mockall::mock;
+    use mockall::predicate::*;
+    use crate::dao::api_key_store::ApiKeyStore;
+    use crate::models::authentication::api_key::{ApiKeyIdentity, ApiKeyRecord};
+    use crate::services::authentication::api_key_service::ApiKeyService;
+    use crate::services::authentication::opaque_token::hash_opaque_token;
+
+    mock! {
+        pub ApiKeyStoreMock {}
+
+        #[async_trait]
+        impl ApiKeyStore for ApiKeyStoreMock {
+            async fn insert_api_key(&self, record: &ApiKeyRecord, key_hash: &str) -> Result<(), String>;
+            async fn list_api_keys(&self, username: &str) -> Result<Vec<ApiKeyRecord>, String>;
+            async fn revoke_api_key(&self, username: &str, id: &str) -> Result<bool, String>;
+            async fn revoke_all_api_keys(&self, username: &str) -> Result<(), String>;
+            async fn find_api_key(&self, key_hash: &str) -> Result<Option<ApiKeyIdentity>, String>;
+            async fn touch_api_key(&self, id: &str, used_at: i64) -> Result<(), String>;
+        }
+    }
+
+    fn now() -> i64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+    }
+
+    fn identity(read_only: bool) -> ApiKeyIdentity {
+        ApiKeyIdentity {
+            key: ApiKeyRecord {
+                id: "key_id".to_string(),
+                username: "test_user".to_string(),
+                name: "backup".to_string(),
+                key_prefix: "fsk_12345678".to_string(),
+                read_only,
+                created_at: now() - 60,
+                expires_at: None,
+                last_used_at: None,
+                revoked: false,
+            },
+            role: "user".to_string(),
+            privilege: 1,
+            user_disabled: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_stores_only_the_hash() {
+        let mut mock_store = MockApiKeyStoreMock::new();
+        mock_store.expect_insert_api_key()
+            .withf(|record, key_hash| {
+                record.username == "test_user" && record.name == "backup" && record.read_only && key_hash.len() == 64
+            })
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let api_key_service = ApiKeyService::new(Arc::new(mock_store));
+
+        let (key, record) = api_key_service.create("test_user", " backup ", None, true).await.unwrap();
+        assert!(key.starts_with("fsk_"));
+        assert!(key.starts_with(&record.key_prefix));
+        assert!(!record.key_prefix.contains(&key[12..]));
+
+        assert_eq!(api_key_service.create("test_user", "", None, false).await.unwrap_err().0, 400);
+        assert_eq!(api_key_service.create("test_user", "old", Some(now() - 1), false).await.unwrap_err().0, 400);
+    }
+
+    #[tokio::test]
+    async fn test_authenticate() {
+        let mut mock_store = MockApiKeyStoreMock::new();
+        mock_store.expect_find_api_key()
+            .with(eq(hash_opaque_token("fsk_valid")))
+            .returning(|_| Ok(Some(identity(true))));
+        mock_store.expect_find_api_key()
+            .with(eq(hash_opaque_token("fsk_revoked")))
+            .returning(|_| {
+                let mut identity = identity(false);
+                identity.key.revoked = true;
+                Ok(Some(identity))
+            });
+        mock_store.expect_find_api_key()
+            .with(eq(hash_opaque_token("fsk_expired")))
+            .returning(|_| {
+                let mut identity = identity(false);
+                identity.key.expires_at = Some(now() - 1);
+                Ok(Some(identity))
+            });
+        mock_store.expect_find_api_key()
+            .with(eq(hash_opaque_token("fsk_disabled")))
+            .returning(|_| {
+                let mut identity = identity(false);
+                identity.user_disabled = true;
+                Ok(Some(identity))
+            });
+        mock_store.expect_find_api_key()
+            .returning(|_| Ok(None));
+        mock_store.expect_touch_api_key()
+            .with(eq("key_id"), always())
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let api_key_service = ApiKeyService::new(Arc::new(mock_store));
+
+        let claims = api_key_service.authenticate("fsk_valid").await.unwrap();
+        assert_eq!(claims.sub, "test_user");
+        assert_eq!(claims.role, "user");
+        assert_eq!(claims.api_key_id, Some("key_id".to_string()));
+        assert_eq!(claims.scopes, vec!["files:read".to_string()]);
+
+        assert!(api_key_service.authenticate("fsk_revoked").await.is_err());
+        assert!(api_key_service.authenticate("fsk_expired").await.is_err());
+        assert!(api_key_service.authenticate("fsk_disabled").await.is_err());
+        assert!(api_key_service.authenticate("fsk_unknown").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_revoke_unknown_key() {
+        let mut mock_store = MockApiKeyStoreMock::new();
+        mock_store.expect_revoke_api_key()
+            .with(eq("test_user"), eq("key_id"))
+            .returning(|_, _| Ok(true));
+        mock_store.expect_revoke_api_key()
+            .returning(|_, _| Ok(false));
+
+        let api_key_service = ApiKeyService::new(Arc::new(mock_store));
+
+        assert!(api_key_service.revoke("test_user", "key_id").await.is_ok());
+        assert_eq!(api_key_service.revoke("test_user", "other_id").await.unwrap_err().0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_all_keys() {
+        let mut mock_store = MockApiKeyStoreMock::new();
+        mock_store.expect_revoke_all_api_keys()
+            .with(eq("test_user"))
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_store.expect_revoke_all_api_keys()
+            .returning(|_| Err("connection refused".to_string()));
+
+        let api_key_service = ApiKeyService::new(Arc::new(mock_store));
+
+        assert!(api_key_service.revoke_all("test_user").await.is_ok());
+        assert_eq!(api_key_service.revoke_all("other_user").await.unwrap_err().0, 500);
+    }
+}
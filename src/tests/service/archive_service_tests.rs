@@ -0,0 +1,173 @@
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Cursor, Write};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use futures_util::stream;
+    use tempfile::{tempdir, TempDir};
+    use zip::write::FileOptions;
+    use crate::services::file_structure::archive_service::{ArchiveLimits, ArchiveService, ExtractedArchive};
+    use crate::services::file_structure::file_service::{FileService, STAGING_DIR};
+    use crate::services::locking::directory_locking_manager::DirectoryLockManager;
+
+    fn zip_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            if name.ends_with('/') {
+                zip.add_directory(*name, FileOptions::default()).unwrap();
+            } else {
+                zip.start_file(*name, FileOptions::default()).unwrap();
+                zip.write_all(content).unwrap();
+            }
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// Names are written as they are, the tar library would refuse the malicious ones
+    fn tar_archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut tar = tar::Builder::new(Vec::new());
+        for (name, content) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            if name.ends_with('/') {
+                header.set_entry_type(tar::EntryType::Directory);
+            }
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append(&header, *content).unwrap();
+        }
+        tar.into_inner().unwrap()
+    }
+
+    async fn extract(root: &TempDir, archive: Vec<u8>, limits: ArchiveLimits) -> Result<ExtractedArchive, (u16, String)> {
+        let root_dir = root.path().to_str().unwrap().to_string();
+        let file_service = FileService::new(root_dir.clone(), DirectoryLockManager::new());
+        let staged = file_service
+            .stage_upload(stream::iter([Ok::<_, io::Error>(archive)]), None)
+            .await
+            .unwrap();
+        ArchiveService::new(root_dir, limits).extract(staged).await
+    }
+
+    fn staging_entries(root: &TempDir) -> usize {
+        std::fs::read_dir(root.path().join(STAGING_DIR)).unwrap().count()
+    }
+
+    #[tokio::test]
+    async fn test_extract_archives() {
+        let root = tempdir().unwrap();
+        let entries: &[(&str, &[u8])] = &[("./", b""), ("holiday/", b""), ("holiday/beach.jpg", b"sand"), ("notes.txt", b"notes")];
+
+        let zip = extract(&root, zip_archive(entries), ArchiveLimits::default()).await.unwrap();
+        assert_eq!(zip.files, vec![("holiday/beach.jpg".to_string(), 4), ("notes.txt".to_string(), 5)]);
+
+        let tar = extract(&root, tar_archive(entries), ArchiveLimits::default()).await.unwrap();
+        assert_eq!(tar.files, zip.files);
+
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&tar_archive(entries)).unwrap();
+        let tar_gz = extract(&root, gz.finish().unwrap(), ArchiveLimits::default()).await.unwrap();
+        assert_eq!(tar_gz.files, zip.files);
+
+        let target = root.path().join("beach.jpg");
+        tar_gz.take_file("holiday/beach.jpg", 4).unwrap().persist(&target).await.unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"sand");
+
+        // Only the extraction folders are left, the uploaded archives are gone
+        assert_eq!(staging_entries(&root), 3);
+        drop((zip, tar, tar_gz));
+        assert_eq!(staging_entries(&root), 0);
+    }
+
+    #[tokio::test]
+    async fn test_refuse_entries_outside_of_the_folder() {
+        let root = tempdir().unwrap();
+
+        let zip_slip = zip_archive(&[("docs/report.txt", b"ok"), ("docs/../../escape.txt", b"evil")]);
+        assert_eq!(extract(&root, zip_slip, ArchiveLimits::default()).await.err().unwrap().0, 400);
+
+        let absolute = tar_archive(&[("/etc/cron.d/evil", b"evil")]);
+        assert_eq!(extract(&root, absolute, ArchiveLimits::default()).await.err().unwrap().0, 400);
+
+        let drive = zip_archive(&[("C:\\Windows\\evil.dll", b"evil")]);
+        assert_eq!(extract(&root, drive, ArchiveLimits::default()).await.err().unwrap().0, 400);
+
+        assert!(!root.path().join("escape.txt").exists());
+        assert_eq!(staging_entries(&root), 0);
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let root = tempdir().unwrap();
+        let limits = ArchiveLimits { max_size: 1000, max_entries: 3 };
+
+        // Compresses to a few bytes, but is far larger when extracted
+        let bomb = zip_archive(&[("zeros.bin", &[0u8; 100_000])]);
+        assert!(bomb.len() < 1000);
+        assert_eq!(extract(&root, bomb, limits.clone()).await.err().unwrap().0, 413);
+
+        let spread = tar_archive(&[("a.bin", &[0u8; 600]), ("b.bin", &[0u8; 600])]);
+        assert_eq!(extract(&root, spread, limits.clone()).await.err().unwrap().0, 413);
+
+        let many = [("a", &b""[..]), ("b", b""), ("c", b""), ("d", b"")];
+        assert_eq!(extract(&root, zip_archive(&many), limits.clone()).await.err().unwrap().0, 413);
+        assert_eq!(extract(&root, tar_archive(&many), limits.clone()).await.err().unwrap().0, 413);
+        assert!(extract(&root, tar_archive(&many[..3]), limits.clone()).await.is_ok());
+
+        // An archive that extracts to the limits fits into the upload size derived from them
+        let largest = tar_archive(&[("a.bin", &[0u8; 600]), ("b.bin", &[0u8; 399]), ("c.bin", &[0u8; 1])]);
+        assert!(largest.len() as u64 <= limits.max_archive_size());
+        assert!(extract(&root, largest, limits.clone()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_limit_long_names() {
+        let root = tempdir().unwrap();
+        let limits = ArchiveLimits { max_size: 1000, max_entries: 3 };
+
+        // A GNU long name is read into memory before its entry, it must not be larger than the archive may be
+        let name = vec![b'a'; 2 * limits.max_archive_size() as usize];
+        let mut tar = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.as_old_mut().name[..13].copy_from_slice(b"././@LongLink");
+        header.set_entry_type(tar::EntryType::GNULongName);
+        header.set_size(name.len() as u64);
+        header.set_cksum();
+        tar.append(&header, &name[..]).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        tar.append_data(&mut header, "notes.txt", &b"notes"[..]).unwrap();
+        let archive = tar.into_inner().unwrap();
+        assert_eq!(extract(&root, archive.clone(), limits.clone()).await.err().unwrap().0, 413);
+
+        // Also after decompressing, a compressed archive is read as far as the plain one
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&archive).unwrap();
+        let compressed = gz.finish().unwrap();
+        assert!((compressed.len() as u64) < limits.max_archive_size());
+        assert_eq!(extract(&root, compressed, limits).await.err().unwrap().0, 413);
+        assert_eq!(staging_entries(&root), 0);
+    }
+
+    #[tokio::test]
+    async fn test_skip_links_and_refuse_other_formats() {
+        let root = tempdir().unwrap();
+
+        let mut tar = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        tar.append_link(&mut header, "passwd", "/etc/passwd").unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        tar.append_data(&mut header, "notes.txt", &b"notes"[..]).unwrap();
+        let extracted = extract(&root, tar.into_inner().unwrap(), ArchiveLimits::default()).await.unwrap();
+        assert_eq!(extracted.files, vec![("notes.txt".to_string(), 5)]);
+
+        let not_an_archive = b"just some text, not an archive".to_vec();
+        assert_eq!(extract(&root, not_an_archive, ArchiveLimits::default()).await.err().unwrap().0, 415);
+    }
+}
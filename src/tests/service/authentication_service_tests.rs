@@ -0,0 +1,184 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use actix_web::http::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use crate::services::authentication::authentication_service::{AuthenticationService, Claims, TokenSettings};
+    use crate::services::authentication::key_ring::KeyRing;
+    use crate::services::authentication::token_revocation_service::TokenRevocationService;
+    use crate::tests::test_structure::NoopTokenRevocationStore;
+
+    fn service_with(settings: TokenSettings) -> AuthenticationService {
+        AuthenticationService::with_keys(
+            Some(b"secret"),
+            KeyRing::default(),
+            None,
+            settings,
+            TokenRevocationService::new(Arc::new(NoopTokenRevocationStore))
+        )
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap());
+        headers
+    }
+
+    fn now() -> usize {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as usize
+    }
+
+    #[tokio::test]
+    async fn test_generated_token_is_accepted() {
+        let service = service_with(TokenSettings::default());
+        let token = service.generate_token("test_user".to_string(), "user".to_string(), 1).unwrap();
+
+        let claims = service.authenticate(&bearer(&token)).await.unwrap();
+
+        assert_eq!(claims.sub, "test_user");
+        assert_eq!(claims.role, "user");
+        assert_eq!(claims.privilege, 1);
+    }
+
+    #[test]
+    fn test_token_signed_with_other_secret_is_rejected() {
+        let service = service_with(TokenSettings::default());
+        let other = AuthenticationService::with_keys(
+            Some(b"other_secret"),
+            KeyRing::default(),
+            None,
+            TokenSettings::default(),
+            TokenRevocationService::new(Arc::new(NoopTokenRevocationStore))
+        );
+        let token = other.generate_token("test_user".to_string(), "user".to_string(), 1).unwrap();
+
+        assert!(service.validate_token(&token).is_err());
+    }
+
+    #[test]
+    fn test_issuer_and_audience_are_enforced() {
+        let settings = TokenSettings {
+            issuer: Some("file-server".to_string()),
+            audience: Some("family-drive".to_string()),
+            ..TokenSettings::default()
+        };
+        let service = service_with(settings.clone());
+        let token = service.generate_token("test_user".to_string(), "user".to_string(), 1).unwrap();
+        assert!(service.validate_token(&token).is_ok());
+
+        let other_audience = service_with(TokenSettings {
+            audience: Some("someone-else".to_string()),
+            ..settings.clone()
+        });
+        assert!(other_audience.validate_token(&token).is_err());
+
+        let without_issuer = service_with(TokenSettings::default());
+        let token = without_issuer.generate_token("test_user".to_string(), "user".to_string(), 1).unwrap();
+        assert!(service.validate_token(&token).is_err());
+    }
+
+    #[test]
+    fn test_expiration_respects_leeway() {
+        let service = service_with(TokenSettings { leeway: 30, ..TokenSettings::default() });
+        let claims = |exp: usize| Claims {
+            sub: "test_user".to_string(),
+            exp,
+            iat: now() - 3600,
+            jti: "jti".to_string(),
+            role: "user".to_string(),
+            privilege: 1,
+            iss: None,
+            aud: None,
+            mfa_pending: false,
+            api_key_id: None,
+            parent_jti: None,
+            scopes: Vec::new(),
+        };
+        let sign = |c: &Claims| encode(&Header::default(), c, &EncodingKey::from_secret(b"secret")).unwrap();
+
+        // Expired a few seconds ago, but within the allowed clock skew
+        assert!(service.validate_token(&sign(&claims(now() - 5))).is_ok());
+        assert!(service.validate_token(&sign(&claims(now() - 120))).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mfa_pending_token_is_no_access_token() {
+        let service = service_with(TokenSettings::default());
+        let mfa_token = service.generate_mfa_pending_token("test_user".to_string()).unwrap();
+        let access_token = service.generate_token("test_user".to_string(), "user".to_string(), 1).unwrap();
+
+        assert_eq!(service.authenticate(&bearer(&mfa_token)).await.unwrap_err(), "MFA verification required");
+        assert_eq!(service.validate_mfa_pending_token(&mfa_token).unwrap().sub, "test_user");
+
+        // And an access token can't be used to skip the password step
+        assert!(service.validate_mfa_pending_token(&access_token).is_err());
+        assert!(!service.authenticate(&bearer(&access_token)).await.unwrap().mfa_pending);
+    }
+
+    #[test]
+    fn test_restrict_token() {
+        let service = service_with(TokenSettings::default());
+        let token = service.generate_token("test_user".to_string(), "user".to_string(), 1).unwrap();
+        let claims = service.validate_token(&token).unwrap();
+        assert!(claims.has_scope("files:write"));
+        assert!(!claims.has_scope("admin"));
+
+        let restricted = service.restrict_token(&claims, vec!["files:read".to_string()], Some(60)).unwrap();
+        let restricted_claims = service.validate_token(&restricted).unwrap();
+        assert_eq!(restricted_claims.scopes, vec!["files:read".to_string()]);
+        assert_eq!(restricted_claims.exp - restricted_claims.iat, 60);
+        assert_eq!(restricted_claims.parent_jti, Some(claims.jti.clone()));
+        assert!(claims.is_session());
+        assert!(!restricted_claims.is_session());
+
+        // A restricted token can't be widened again
+        assert_eq!(service.restrict_token(&restricted_claims, vec!["files:write".to_string()], None).unwrap_err().0, 403);
+        assert_eq!(service.restrict_token(&claims, vec!["admin".to_string()], None).unwrap_err().0, 403);
+        assert_eq!(service.restrict_token(&claims, vec!["files:everything".to_string()], None).unwrap_err().0, 400);
+        assert_eq!(service.restrict_token(&claims, Vec::new(), None).unwrap_err().0, 400);
+        assert_eq!(service.restrict_token(&claims, vec!["files:read".to_string()], Some(u64::MAX)).unwrap_err().0, 400);
+    }
+
+    #[tokio::test]
+    async fn test_revoked_token_is_rejected() {
+        let service = service_with(TokenSettings::default());
+        let token = service.generate_token("test_user".to_string(), "user".to_string(), 1).unwrap();
+        let claims = service.validate_token(&token).unwrap();
+
+        let restricted = service.restrict_token(&claims, vec!["files:read".to_string()], None).unwrap();
+        let restricted_claims = service.validate_token(&restricted).unwrap();
+        let derived = service.restrict_token(&restricted_claims, vec!["files:read".to_string()], None).unwrap();
+        assert_eq!(service.validate_token(&derived).unwrap().parent_jti, Some(claims.jti.clone()));
+
+        service.revoke_token(&claims).await.unwrap();
+
+        assert_eq!(service.validate_token(&token).unwrap_err(), "Token has been revoked");
+        // The tokens derived from the session go with it
+        assert_eq!(service.validate_token(&restricted).unwrap_err(), "Token has been revoked");
+        assert_eq!(service.validate_token(&derived).unwrap_err(), "Token has been revoked");
+    }
+
+    #[tokio::test]
+    async fn test_revoke_all_includes_the_current_second() {
+        let service = service_with(TokenSettings::default());
+        let token = service.generate_token("test_user".to_string(), "user".to_string(), 1).unwrap();
+
+        service.revoke_all_user_tokens("test_user").await.unwrap();
+
+        assert_eq!(service.validate_token(&token).unwrap_err(), "Token has been revoked");
+    }
+
+    #[tokio::test]
+    async fn test_malformed_authorization_header() {
+        let service = service_with(TokenSettings::default());
+
+        assert_eq!(service.authenticate(&HeaderMap::new()).await.unwrap_err(), "Missing Authorization header");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Basic abc"));
+        assert_eq!(service.authenticate(&headers).await.unwrap_err(), "Expected 'Bearer <token>'");
+
+        assert_eq!(service.authenticate(&bearer("")).await.unwrap_err(), "No token after 'Bearer '");
+    }
+}
@@ -7,6 +7,7 @@ mod tests {
     use mockall::predicate::*;
     use mockall::mock;
     use crate::dao::privilege_store::PrivilegeStore;
+    use crate::models::groups::group_access::GroupAccess;
     use crate::services::file_structure::directory_service::DirectoryService;
     use crate::services::file_structure::path_service::PathService;
     use crate::services::file_structure::privilege_service::PrivilegeService;
@@ -19,7 +20,11 @@ mod tests {
         
         #[async_trait]
         impl PrivilegeStore for PrivilegeStoreMock {
-            async fn get_privilege_level(&self, role: &str) -> Result<i32, String>;
+            async fn get_privilege_level(&self, role: &str) -> Result<Option<i32>, String>;
+            async fn get_user_role(&self, username: &str) -> Result<String, String>;
+            async fn is_user_disabled(&self, username: &str) -> Result<bool, String>;
+            async fn group_exists(&self, group: &str) -> Result<bool, String>;
+            async fn get_group_access(&self, group: &str, username: &str) -> Result<Option<GroupAccess>, String>;
         }
     }
 
@@ -48,28 +53,33 @@ mod tests {
     #[tokio::test]
     async fn test_check_privilege_status() {
         let mut mock_store = MockPrivilegeStoreMock::new();
+        for (user, role) in [("root", "admin"), ("test_user", "user")] {
+            mock_store.expect_get_user_role()
+                .with(eq(user))
+                .returning(move |_| Ok(role.to_string()));
+        }
 
         // Set up expectation for the call with "admin"
         mock_store.expect_get_privilege_level()
             .with(eq("admin"))
-            .returning(|_| Ok(999));
+            .returning(|_| Ok(Some(999)));
 
         // Set up expectation for the call with "user"
         mock_store.expect_get_privilege_level()
             .with(eq("user"))
-            .returning(|_| Ok(111));
+            .returning(|_| Ok(Some(111)));
 
         let privilege_service = PrivilegeService::new(mock_store);
-        // Test equal privileges
-        assert!(privilege_service.check_privilege_status("user", "user").await.is_ok());
+        assert_eq!(privilege_service.resolve_user_privilege("root").await, Ok(("admin".to_string(), 999)));
+        assert_eq!(privilege_service.resolve_user_privilege("test_user").await, Ok(("user".to_string(), 111)));
 
-        // Test higher privileges (assuming privilege levels: admin > user > guest)
-        assert!(privilege_service.check_privilege_status("user", "admin").await.is_ok());
+        // Test higher privileges
+        assert_eq!(privilege_service.resolve_tree_owner("root", Some("test_user")).await, Ok("test_user".to_string()));
 
         // Test lower privileges
-        let result = privilege_service.check_privilege_status("admin", "user").await;
+        let result = privilege_service.resolve_tree_owner("test_user", Some("root")).await;
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Your token role is 'user'"));
+        assert!(result.unwrap_err().1.contains("not allowed to access the files of 'root'"));
     }
 
     #[tokio::test]
@@ -113,25 +123,135 @@ mod tests {
     #[tokio::test]
     async fn test_privilege_edge_cases() {
         let mut mock_store = MockPrivilegeStoreMock::new();
+        for (user, role) in [("newbie", "nonexistent"), ("test_user", "user")] {
+            mock_store.expect_get_user_role()
+                .with(eq(user))
+                .returning(move |_| Ok(role.to_string()));
+        }
+        mock_store.expect_get_user_role()
+            .with(eq(""))
+            .returning(|_| Err("User not found".to_string()));
         mock_store.expect_get_privilege_level()
             .with(eq("user"))
-            .returning(|_| Ok(111));
-
-        mock_store.expect_get_privilege_level()
-            .with(eq(""))
-            .returning(|_| Err("".parse().unwrap()));
+            .returning(|_| Ok(Some(111)));
 
         mock_store.expect_get_privilege_level()
             .with(eq("nonexistent"))
-            .returning(|_| Err("e".parse().unwrap()));
+            .returning(|_| Ok(None));
 
         let privilege_service = PrivilegeService::new(mock_store);
         // Test non-existent role
-        let result = privilege_service.check_privilege_status("nonexistent", "user").await;
-        assert!(result.is_err());
+        assert_eq!(privilege_service.resolve_user_privilege("newbie").await, Ok(("nonexistent".to_string(), 0)));
+        let result = privilege_service.resolve_tree_owner("newbie", Some("test_user")).await;
+        assert_eq!(result.unwrap_err().0, 403);
 
         // Test empty strings
-        let result = privilege_service.check_privilege_status("", "").await;
-        assert!(result.is_err());
+        assert!(privilege_service.resolve_user_privilege("").await.is_err());
+        assert_eq!(privilege_service.resolve_tree_owner("test_user", Some("")).await, Ok("test_user".to_string()));
+        assert_eq!(privilege_service.resolve_tree_owner("", Some("test_user")).await.unwrap_err().0, 500);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_user_privilege() {
+        let mut mock_store = MockPrivilegeStoreMock::new();
+        mock_store.expect_get_user_role()
+            .with(eq("admin"))
+            .returning(|_| Ok("admin".to_string()));
+        mock_store.expect_get_user_role()
+            .with(eq("test_user"))
+            .returning(|_| Ok("unlisted".to_string()));
+        mock_store.expect_get_user_role()
+            .with(eq("ghost"))
+            .returning(|_| Err("User not found".to_string()));
+        mock_store.expect_get_privilege_level()
+            .with(eq("admin"))
+            .returning(|_| Ok(Some(999)));
+        mock_store.expect_get_user_role()
+            .with(eq("unreachable"))
+            .returning(|_| Ok("user".to_string()));
+        mock_store.expect_get_privilege_level()
+            .with(eq("unlisted"))
+            .returning(|_| Ok(None));
+        mock_store.expect_get_privilege_level()
+            .with(eq("user"))
+            .returning(|_| Err("connection refused".to_string()));
+
+        let privilege_service = PrivilegeService::new(mock_store);
+
+        assert_eq!(privilege_service.resolve_user_privilege("admin").await, Ok(("admin".to_string(), 999)));
+        // Roles without an entry in the privilege table get no privileges
+        assert_eq!(privilege_service.resolve_user_privilege("test_user").await, Ok(("unlisted".to_string(), 0)));
+        assert!(privilege_service.resolve_user_privilege("ghost").await.is_err());
+        // A failed lookup must not pass for a role without privileges
+        assert!(privilege_service.resolve_user_privilege("unreachable").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_tree_owner() {
+        let mut mock_store = MockPrivilegeStoreMock::new();
+        for (user, role) in [("admin", "admin"), ("alice", "user"), ("bob", "user")] {
+            mock_store.expect_get_user_role()
+                .with(eq(user))
+                .returning(move |_| Ok(role.to_string()));
+        }
+        mock_store.expect_get_user_role()
+            .with(eq("ghost"))
+            .returning(|_| Err("User not found".to_string()));
+        mock_store.expect_get_privilege_level()
+            .with(eq("admin"))
+            .returning(|_| Ok(Some(999)));
+        mock_store.expect_get_privilege_level()
+            .with(eq("user"))
+            .returning(|_| Ok(Some(1)));
+
+        let privilege_service = PrivilegeService::new(mock_store);
+
+        // Without an owner, or with their own name, users stay in their own tree
+        assert_eq!(privilege_service.resolve_tree_owner("alice", None).await, Ok("alice".to_string()));
+        assert_eq!(privilege_service.resolve_tree_owner("alice", Some("")).await, Ok("alice".to_string()));
+        assert_eq!(privilege_service.resolve_tree_owner("alice", Some("alice")).await, Ok("alice".to_string()));
+
+        assert_eq!(privilege_service.resolve_tree_owner("admin", Some("alice")).await, Ok("alice".to_string()));
+        // An equal privilege is not enough
+        assert_eq!(privilege_service.resolve_tree_owner("alice", Some("bob")).await.unwrap_err().0, 403);
+        assert_eq!(privilege_service.resolve_tree_owner("alice", Some("admin")).await.unwrap_err().0, 403);
+        assert_eq!(privilege_service.resolve_tree_owner("admin", Some("ghost")).await.unwrap_err().0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_group_access() {
+        let mut mock_store = MockPrivilegeStoreMock::new();
+        mock_store.expect_group_exists().returning(|group| Ok(group == "team"));
+        for (user, role) in [("admin", "admin"), ("reader", "user"), ("writer", "user"), ("outsider", "user")] {
+            mock_store.expect_get_user_role()
+                .with(eq(user))
+                .returning(move |_| Ok(role.to_string()));
+        }
+        mock_store.expect_get_privilege_level()
+            .with(eq("admin"))
+            .returning(|_| Ok(Some(999)));
+        mock_store.expect_get_privilege_level()
+            .with(eq("user"))
+            .returning(|_| Ok(Some(1)));
+        mock_store.expect_get_group_access()
+            .returning(|_, username| Ok(match username {
+                "reader" => Some(GroupAccess::Read),
+                "writer" => Some(GroupAccess::Write),
+                _ => None,
+            }));
+
+        let privilege_service = PrivilegeService::new(mock_store);
+
+        assert_eq!(
+            privilege_service.resolve_group_access("reader", "team", GroupAccess::Read).await,
+            Ok("_groups/team".to_string())
+        );
+        assert_eq!(privilege_service.resolve_group_access("reader", "team", GroupAccess::Write).await.unwrap_err().0, 403);
+        assert!(privilege_service.resolve_group_access("writer", "team", GroupAccess::Write).await.is_ok());
+        assert_eq!(privilege_service.resolve_group_access("writer", "team", GroupAccess::Manage).await.unwrap_err().0, 403);
+        assert_eq!(privilege_service.resolve_group_access("outsider", "team", GroupAccess::Read).await.unwrap_err().0, 403);
+        // Admins manage every group without being a member
+        assert!(privilege_service.resolve_group_access("admin", "team", GroupAccess::Manage).await.is_ok());
+        assert_eq!(privilege_service.resolve_group_access("admin", "other", GroupAccess::Read).await.unwrap_err().0, 404);
     }
-}
\ No newline at end of file
+}
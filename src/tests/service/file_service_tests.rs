@@ -4,10 +4,12 @@ mod tests {
     use std::io;
     use std::io::Write;
     use std::path::Path;
+    use futures_util::stream;
     use tempfile::{tempdir, TempDir};
     use tokio::fs;
     use tokio::sync::OnceCell;
-    use crate::services::file_structure::file_service::FileService;
+    use crate::models::system_operations::conflict_policy::ConflictPolicy;
+    use crate::services::file_structure::file_service::{file_etag, FileService, STAGING_DIR};
     use crate::services::locking::directory_locking_manager::DirectoryLockManager;
 
     struct TestEnv {
@@ -71,12 +73,13 @@ mod tests {
             root,
             DirectoryLockManager::new()
         );
-        let res = file_service.sanitize_filename("valid_name.txt");
-        assert_eq!(res, "valid_name.txt");
-        let res = file_service.sanitize_filename("back\\\\slashes\\\\.txt");
-        assert_eq!(res, "backslashes.txt");
-        let res = file_service.sanitize_filename("valid/_name.txt");
-        assert_eq!(res, "valid_name.txt");
+        // Separators are no longer dropped from names, they split off the folder the file is in
+        let res = file_service.sanitize_relative_path("valid_name.txt");
+        assert_eq!(res, Some((String::new(), "valid_name.txt".to_string())));
+        let res = file_service.sanitize_relative_path("back\\\\slashes\\\\.txt");
+        assert_eq!(res, Some(("back/slashes".to_string(), ".txt".to_string())));
+        let res = file_service.sanitize_relative_path("valid/_name.txt");
+        assert_eq!(res, Some(("valid".to_string(), "_name.txt".to_string())));
     }
 
     #[tokio::test]
@@ -121,7 +124,7 @@ mod tests {
     }
     
     #[tokio::test]
-    async fn test_save_file_bytes_to_root_directory_success() {
+    async fn test_commit_staged_upload_success() {
         let env = get_global_test_env().await;
         let root = env.root_dir.path().to_str().unwrap().to_string();
         let user = &env.username;
@@ -141,14 +144,17 @@ mod tests {
         // The content we want to write
         let file_content = b"Hello from test_save_file_bytes";
 
-        // Call the method
+        // Stage the content in two chunks, then move it into place
+        let chunks = stream::iter([Ok::<_, io::Error>(&file_content[..10]), Ok(&file_content[10..])]);
+        let staged = file_service.stage_upload(chunks, None).await.expect("Failed to stage the upload");
+        assert_eq!(staged.size, file_content.len() as u64);
         let result = file_service
-            .save_file_bytes_to_root_directory(&new_file_path, file_content)
+            .commit_staged_upload(staged, &new_file_path, &ConflictPolicy::Overwrite)
             .await;
 
         // Verify success
-        assert!(result.is_ok(), "Expected Ok from save_file_bytes_to_root_directory");
-        assert_eq!(result.unwrap(), "Successfully saved file!".to_string());
+        assert!(result.is_ok(), "Expected Ok from commit_staged_upload");
+        assert_eq!(result.unwrap(), new_file_path);
 
         // Check the file actually exists and contains the data
         let saved_contents = fs::read_to_string(&new_file_path)
@@ -157,7 +163,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_save_file_bytes_to_root_directory_failure_no_parent_dir() {
+    async fn test_commit_staged_upload_failure_no_parent_dir() {
         let env = get_global_test_env().await;
         let root = env.root_dir.path().to_str().unwrap().to_string();
         let user = &env.username;
@@ -175,8 +181,10 @@ mod tests {
             .join("file_should_fail.txt");
 
         // Attempt to save; this should fail because "nonexistent_subdir" doesn't exist
+        let chunks = stream::iter([Ok::<_, io::Error>(b"some data")]);
+        let staged = file_service.stage_upload(chunks, None).await.expect("Failed to stage the upload");
         let result = file_service
-            .save_file_bytes_to_root_directory(&no_such_dir_path, b"some data")
+            .commit_staged_upload(staged, &no_such_dir_path, &ConflictPolicy::Overwrite)
             .await;
 
         // We expect an error
@@ -184,12 +192,143 @@ mod tests {
         let err_msg = result.err().unwrap();
         assert_eq!(err_msg.0, 500);
         assert!(
-            err_msg.1.contains("Error creating file"),
-            "Expected error creating file error; got: {}",
+            err_msg.1.contains("Error saving file"),
+            "Expected error saving file error; got: {}",
             err_msg.1
         );
 
         // Confirm the file was not created
         assert!(!no_such_dir_path.exists(), "File should not exist after failure");
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_stage_upload_cleans_up_failed_uploads() {
+        let root = tempdir().unwrap();
+        let file_service = FileService::new(
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+        let staging_dir = root.path().join(STAGING_DIR);
+
+        // The client disconnects in the middle of the upload
+        let chunks = stream::iter([Ok(&b"first chunk"[..]), Err(io::Error::from(io::ErrorKind::ConnectionReset))]);
+        assert_eq!(file_service.stage_upload(chunks, None).await.unwrap_err().0, 400);
+
+        let chunks = stream::iter([Ok::<_, io::Error>(&b"0123456789"[..]), Ok(&b"too much"[..])]);
+        assert_eq!(file_service.stage_upload(chunks, Some(12)).await.unwrap_err().0, 413);
+
+        // Staged files that are dropped without being committed are deleted as well
+        let chunks = stream::iter([Ok::<_, io::Error>(b"never committed")]);
+        drop(file_service.stage_upload(chunks, None).await.unwrap());
+
+        assert_eq!(std::fs::read_dir(&staging_dir).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_remove_orphaned_staged_files() {
+        let root = tempdir().unwrap();
+        let file_service = FileService::new(
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+        assert_eq!(file_service.remove_orphaned_staged_files().await, 0);
+
+        // Left behind by a crash in the middle of two uploads
+        let staging_dir = root.path().join(STAGING_DIR);
+        create_dir(&staging_dir).unwrap();
+        File::create(staging_dir.join("upload-a1b2c3")).unwrap().write_all(b"half an upl").unwrap();
+        File::create(staging_dir.join("upload-d4e5f6")).unwrap();
+        create_dir(staging_dir.join("tus")).unwrap();
+        File::create(staging_dir.join("tus").join("0a1b2c")).unwrap();
+
+        assert_eq!(file_service.remove_orphaned_staged_files().await, 2);
+        assert!(!staging_dir.join("upload-a1b2c3").exists());
+        assert!(staging_dir.join("tus").join("0a1b2c").exists());
+    }
+
+    #[tokio::test]
+    async fn test_commit_staged_upload_conflicts() {
+        let root = tempdir().unwrap();
+        let file_service = FileService::new(
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+        let target = root.path().join("photo.jpg");
+        std::fs::write(&target, b"original").unwrap();
+        let stage = |content: &'static [u8]| file_service.stage_upload(stream::iter([Ok::<_, io::Error>(content)]), None);
+
+        let stored = file_service
+            .commit_staged_upload(stage(b"second").await.unwrap(), &target, &ConflictPolicy::KeepBoth)
+            .await
+            .unwrap();
+        assert_eq!(stored, root.path().join("photo (1).jpg"));
+        let stored = file_service
+            .commit_staged_upload(stage(b"third").await.unwrap(), &target, &ConflictPolicy::KeepBoth)
+            .await
+            .unwrap();
+        assert_eq!(stored, root.path().join("photo (2).jpg"));
+        assert_eq!(std::fs::read(&stored).unwrap(), b"third");
+
+        let rejected = file_service
+            .commit_staged_upload(stage(b"fourth").await.unwrap(), &target, &ConflictPolicy::Reject)
+            .await;
+        assert_eq!(rejected.unwrap_err().0, 409);
+        let stored = file_service
+            .commit_staged_upload(stage(b"new").await.unwrap(), &root.path().join("new.jpg"), &ConflictPolicy::Reject)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(stored).unwrap(), b"new");
+
+        // Someone else changed the file since the client downloaded it
+        let original = file_etag(&target).await.unwrap();
+        let stale = ConflictPolicy::IfMatch(vec!["\"1-0-0\"".to_string()]);
+        let result = file_service.commit_staged_upload(stage(b"edited").await.unwrap(), &target, &stale).await;
+        assert_eq!(result.unwrap_err().0, 412);
+        assert_eq!(std::fs::read(&target).unwrap(), b"original");
+
+        // Weak ETags never match
+        let weak = ConflictPolicy::IfMatch(vec![format!("W/{}", original)]);
+        let result = file_service.commit_staged_upload(stage(b"edited").await.unwrap(), &target, &weak).await;
+        assert_eq!(result.unwrap_err().0, 412);
+
+        let current = ConflictPolicy::IfMatch(vec![original.clone()]);
+        file_service.commit_staged_upload(stage(b"edited").await.unwrap(), &target, &current).await.unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"edited");
+        assert_ne!(file_etag(&target).await.unwrap(), original);
+
+        let missing = ConflictPolicy::IfMatch(vec!["*".to_string()]);
+        let result = file_service
+            .commit_staged_upload(stage(b"edited").await.unwrap(), &root.path().join("missing.jpg"), &missing)
+            .await;
+        assert_eq!(result.unwrap_err().0, 412);
+
+        // Nothing is left in the staging folder
+        assert_eq!(std::fs::read_dir(root.path().join(STAGING_DIR)).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_parse_conflict_policy() {
+        assert_eq!(ConflictPolicy::parse("keep-both", None), Some(ConflictPolicy::KeepBoth));
+        assert_eq!(
+            ConflictPolicy::parse("if-match", Some("\"abc\", W/\"def\"")),
+            Some(ConflictPolicy::IfMatch(vec!["\"abc\"".to_string(), "W/\"def\"".to_string()]))
+        );
+        assert_eq!(ConflictPolicy::parse("if-match", None), None);
+        assert_eq!(ConflictPolicy::parse("replace", None), None);
+    }
+
+    #[test]
+    fn test_sanitize_relative_path() {
+        let file_service = FileService::new("root".to_string(), DirectoryLockManager::new());
+        let split = |name: &str| file_service.sanitize_relative_path(name);
+        assert_eq!(split("photo.jpg"), Some((String::new(), "photo.jpg".to_string())));
+        assert_eq!(split("holiday/day1/photo.jpg"), Some(("holiday/day1".to_string(), "photo.jpg".to_string())));
+        assert_eq!(split("\\holiday\\.\\photo.jpg"), Some(("holiday".to_string(), "photo.jpg".to_string())));
+        assert_eq!(split("holiday/../../photo.jpg"), None);
+        assert_eq!(split("/"), None);
+        assert_eq!(split("C:\\Windows\\evil.dll"), None);
+        assert_eq!(split("holiday/c:photo.jpg"), None);
+        assert_eq!(split("holiday/photo\n.jpg"), None);
+        assert_eq!(split("notes: draft.txt"), Some((String::new(), "notes: draft.txt".to_string())));
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use mockall::mock;
+    use mockall::predicate::*;
+    use tempfile::tempdir;
+    use crate::dao::group_store::GroupStore;
+    use crate::models::groups::group_access::GroupAccess;
+    use crate::models::groups::group_requests::{GroupMember, GroupSummary};
+    use crate::services::authentication::account_service::ARCHIVE_DIR;
+    use crate::services::file_structure::group_service::{GroupService, GROUPS_DIR};
+    use crate::services::locking::directory_locking_manager::DirectoryLockManager;
+
+    mock! {
+        pub GroupStoreMock {}
+
+        #[async_trait]
+        impl GroupStore for GroupStoreMock {
+            async fn create_group(&self, name: &str, created_at: i64) -> Result<bool, String>;
+            async fn delete_group(&self, name: &str) -> Result<bool, String>;
+            async fn list_groups(&self, username: &str, include_all: bool) -> Result<Vec<GroupSummary>, String>;
+            async fn list_members(&self, group: &str) -> Result<Vec<GroupMember>, String>;
+            async fn set_member(&self, group: &str, username: &str, access: GroupAccess) -> Result<(), String>;
+            async fn remove_member(&self, group: &str, username: &str) -> Result<bool, String>;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_and_delete_group() {
+        let root = tempdir().unwrap();
+        let mut mock_store = MockGroupStoreMock::new();
+        mock_store.expect_create_group()
+            .with(eq("team"), always())
+            .returning(|_, _| Ok(true));
+        mock_store.expect_create_group()
+            .with(eq("taken"), always())
+            .returning(|_, _| Ok(false));
+        mock_store.expect_delete_group()
+            .with(eq("team"))
+            .returning(|_| Ok(true));
+        mock_store.expect_delete_group()
+            .with(eq("ghost"))
+            .returning(|_| Ok(false));
+
+        let service = GroupService::new(
+            mock_store,
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+
+        assert!(service.create_group("team").await.is_ok());
+        assert!(root.path().join(GROUPS_DIR).join("team").is_dir());
+        assert_eq!(service.create_group("taken").await.unwrap_err().0, 409);
+        assert_eq!(service.create_group("../escape").await.unwrap_err().0, 400);
+        assert_eq!(service.create_group("").await.unwrap_err().0, 400);
+
+        // The folder is archived rather than deleted
+        std::fs::write(root.path().join(GROUPS_DIR).join("team").join("notes.txt"), b"keep me").unwrap();
+        assert!(service.delete_group("team").await.is_ok());
+        assert!(!root.path().join(GROUPS_DIR).join("team").exists());
+        let archived: Vec<_> = std::fs::read_dir(root.path().join(ARCHIVE_DIR)).unwrap().collect();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(service.delete_group("ghost").await.unwrap_err().0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_membership() {
+        let root = tempdir().unwrap();
+        let mut mock_store = MockGroupStoreMock::new();
+        mock_store.expect_set_member()
+            .with(eq("team"), eq("alice"), eq(GroupAccess::Write))
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        mock_store.expect_set_member()
+            .with(eq("team"), eq("ghost"), always())
+            .returning(|_, _, _| Err("User not found".to_string()));
+        mock_store.expect_remove_member()
+            .returning(|_, username| Ok(username == "alice"));
+
+        let service = GroupService::new(
+            mock_store,
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        );
+
+        assert!(service.set_member("team", "alice", GroupAccess::Write).await.is_ok());
+        assert_eq!(service.set_member("team", "ghost", GroupAccess::Read).await.unwrap_err().0, 404);
+        assert!(service.remove_member("team", "alice").await.is_ok());
+        assert_eq!(service.remove_member("team", "bob").await.unwrap_err().0, 404);
+    }
+
+    #[test]
+    fn test_access_levels() {
+        assert!(GroupAccess::Read < GroupAccess::Write);
+        assert!(GroupAccess::Write < GroupAccess::Manage);
+        assert_eq!(GroupAccess::parse("manage"), Some(GroupAccess::Manage));
+        assert_eq!(GroupAccess::parse("owner"), None);
+        assert_eq!(serde_json::to_string(&GroupAccess::Write).unwrap(), "\"write\"");
+    }
+}
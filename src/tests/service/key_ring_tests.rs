@@ -0,0 +1,229 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use actix_web::http::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+    use async_trait::async_trait;
+    use jsonwebtoken::{decode_header, encode, Algorithm, EncodingKey, Header};
+    use jsonwebtoken::jwk::AlgorithmParameters;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use tempfile::tempdir;
+    use crate::dao::privilege_store::PrivilegeStore;
+    use crate::models::groups::group_access::GroupAccess;
+    use crate::services::authentication::authentication_service::{AuthenticationService, Claims, TokenSettings};
+    use crate::services::authentication::key_ring::KeyRing;
+    use crate::services::authentication::token_revocation_service::TokenRevocationService;
+    use crate::tests::test_structure::NoopTokenRevocationStore;
+
+    fn now() -> i64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+    }
+
+    fn write_ed25519_key(dir: &Path, name: &str) {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let pem = pem::encode(&pem::Pem::new("PRIVATE KEY", pkcs8.as_ref().to_vec()));
+        fs::write(dir.join(name), pem).unwrap();
+    }
+
+    /// Writes the public key in SPKI format and returns the private key in PKCS#8
+    fn write_ed25519_public_key(dir: &Path, name: &str) -> Vec<u8> {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let mut spki = vec![0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+        spki.extend_from_slice(key_pair.public_key().as_ref());
+        fs::write(dir.join(name), pem::encode(&pem::Pem::new("PUBLIC KEY", spki))).unwrap();
+        pkcs8.as_ref().to_vec()
+    }
+
+    /// "alice" is a regular user, "mallory" is disabled
+    struct StaticPrivilegeStore;
+
+    #[async_trait]
+    impl PrivilegeStore for StaticPrivilegeStore {
+        async fn get_privilege_level(&self, role: &str) -> Result<Option<i32>, String> {
+            Ok((role == "user").then_some(1))
+        }
+
+        async fn get_user_role(&self, username: &str) -> Result<String, String> {
+            match username {
+                "alice" | "mallory" => Ok("user".to_string()),
+                _ => Err("User not found".to_string()),
+            }
+        }
+
+        async fn is_user_disabled(&self, username: &str) -> Result<bool, String> {
+            self.get_user_role(username).await.map(|_| username == "mallory")
+        }
+
+        async fn group_exists(&self, _group: &str) -> Result<bool, String> {
+            Ok(false)
+        }
+
+        async fn get_group_access(&self, _group: &str, _username: &str) -> Result<Option<GroupAccess>, String> {
+            Ok(None)
+        }
+    }
+
+    fn service_with(key_ring: KeyRing, secret: Option<&[u8]>) -> AuthenticationService {
+        AuthenticationService::with_keys(
+            secret,
+            key_ring,
+            None,
+            TokenSettings::default(),
+            TokenRevocationService::new(Arc::new(NoopTokenRevocationStore))
+        )
+    }
+
+    #[test]
+    fn test_rotation_schedule() {
+        let dir = tempdir().unwrap();
+        write_ed25519_key(dir.path(), "old.pem");
+        write_ed25519_key(dir.path(), "current.pem");
+        write_ed25519_key(dir.path(), "next.pem");
+        let manifest = format!(r#"{{ "keys": [
+            {{ "kid": "old", "algorithm": "EdDSA", "private_key": "old.pem", "activate_at": 0, "retire_at": {} }},
+            {{ "kid": "current", "algorithm": "EdDSA", "private_key": "current.pem", "activate_at": 100 }},
+            {{ "kid": "next", "algorithm": "EdDSA", "private_key": "next.pem", "activate_at": {} }}
+        ] }}"#, now() - 10, now() + 3600);
+        fs::write(dir.path().join("keys.json"), manifest).unwrap();
+
+        let key_ring = KeyRing::load_manifest(&dir.path().join("keys.json")).unwrap();
+
+        // The newest key that is already active signs
+        assert_eq!(key_ring.active_signing_key(now()).unwrap().kid, "current");
+        assert_eq!(key_ring.active_signing_key(now() + 7200).unwrap().kid, "next");
+        // Retired keys can't be used to verify anymore
+        assert!(key_ring.verification_key("old", now()).is_none());
+        assert!(key_ring.verification_key("next", now()).is_some());
+
+        // The upcoming key is published ahead of time, the retired one is gone
+        let mut kids: Vec<String> = key_ring.jwks(now())
+            .keys
+            .into_iter()
+            .map(|jwk| jwk.common.key_id.unwrap())
+            .collect();
+        kids.sort();
+        assert_eq!(kids, vec!["current", "next"]);
+    }
+
+    #[test]
+    fn test_tokens_signed_with_active_key() {
+        let dir = tempdir().unwrap();
+        write_ed25519_key(dir.path(), "key.pem");
+        fs::write(
+            dir.path().join("keys.json"),
+            r#"{ "keys": [ { "kid": "k1", "algorithm": "EdDSA", "private_key": "key.pem" } ] }"#
+        ).unwrap();
+        let key_ring = KeyRing::load_manifest(&dir.path().join("keys.json")).unwrap();
+        let service = service_with(key_ring, Some(b"secret"));
+
+        let token = service.generate_token("test_user".to_string(), "user".to_string(), 1).unwrap();
+        let header = decode_header(&token).unwrap();
+        assert_eq!(header.kid.as_deref(), Some("k1"));
+        assert_eq!(service.validate_token(&token).unwrap().sub, "test_user");
+
+        let jwks = service.jwks();
+        assert_eq!(jwks.keys.len(), 1);
+        assert!(matches!(jwks.keys[0].algorithm, AlgorithmParameters::OctetKeyPair(_)));
+
+        // HS256 tokens are still accepted as a fallback while the secret is configured
+        let hs256_only = service_with(KeyRing::default(), Some(b"secret"));
+        let legacy_token = hs256_only.generate_token("test_user".to_string(), "user".to_string(), 1).unwrap();
+        assert!(service.validate_token(&legacy_token).is_ok());
+
+        // But not once the secret is gone
+        let key_ring = KeyRing::load_manifest(&dir.path().join("keys.json")).unwrap();
+        let asymmetric_only = service_with(key_ring, None);
+        assert!(asymmetric_only.validate_token(&legacy_token).is_err());
+        assert!(asymmetric_only.validate_token(&token).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_manifest() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("keys.json"),
+            r#"{ "keys": [ { "kid": "k1", "algorithm": "HS512", "private_key": "key.pem" } ] }"#
+        ).unwrap();
+
+        assert!(KeyRing::load_manifest(&dir.path().join("keys.json")).is_err());
+        assert!(KeyRing::load_manifest(&dir.path().join("missing.json")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tokens_of_other_services() {
+        let dir = tempdir().unwrap();
+        let private_key = write_ed25519_public_key(dir.path(), "media.pub.pem");
+        fs::write(
+            dir.path().join("keys.json"),
+            r#"{ "keys": [ { "kid": "media", "algorithm": "EdDSA", "public_key": "media.pub.pem",
+                "issuer": "media-center", "audience": "file-server" } ] }"#
+        ).unwrap();
+        let key_ring = KeyRing::load_manifest(&dir.path().join("keys.json")).unwrap();
+        let service = service_with(key_ring, None).with_privilege_store(Arc::new(StaticPrivilegeStore));
+
+        let sign = |sub: &str, aud: &str| {
+            let claims = Claims {
+                sub: sub.to_string(),
+                exp: now() as usize + 60,
+                iat: now() as usize,
+                jti: "jti".to_string(),
+                role: "admin".to_string(),
+                privilege: 999,
+                iss: Some("media-center".to_string()),
+                aud: Some(aud.to_string()),
+                mfa_pending: false,
+                api_key_id: None,
+                parent_jti: None,
+                scopes: vec!["admin".to_string()],
+            };
+            let mut header = Header::new(Algorithm::EdDSA);
+            header.kid = Some("media".to_string());
+            let token = encode(&header, &claims, &EncodingKey::from_ed_der(&private_key)).unwrap();
+            let mut headers = HeaderMap::new();
+            headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap());
+            headers
+        };
+
+        // The user is taken from the token, what they may do from their account
+        let claims = service.authenticate(&sign("alice", "file-server")).await.unwrap();
+        assert_eq!(claims.sub, "alice");
+        assert_eq!(claims.role, "user");
+        assert_eq!(claims.privilege, 1);
+        assert!(!claims.has_scope("admin"));
+
+        // Standard tokens carry none of our own claims
+        let standard = serde_json::json!({
+            "sub": "alice",
+            "exp": now() + 60,
+            "iss": "media-center",
+            "aud": ["file-server"],
+        });
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some("media".to_string());
+        let token = encode(&header, &standard, &EncodingKey::from_ed_der(&private_key)).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap());
+        let claims = service.authenticate(&headers).await.unwrap();
+        assert_eq!((claims.sub.as_str(), claims.role.as_str(), claims.privilege), ("alice", "user", 1));
+        assert!(!claims.jti.is_empty());
+
+        assert!(service.authenticate(&sign("alice", "someone-else")).await.is_err());
+        assert!(service.authenticate(&sign("mallory", "file-server")).await.is_err());
+        assert!(service.authenticate(&sign("ghost", "file-server")).await.is_err());
+
+        // Without a store to look the users up, the tokens are not accepted at all
+        let key_ring = KeyRing::load_manifest(&dir.path().join("keys.json")).unwrap();
+        assert!(service_with(key_ring, None).authenticate(&sign("alice", "file-server")).await.is_err());
+
+        // Keys of other services need to know whose tokens they verify
+        fs::write(
+            dir.path().join("keys.json"),
+            r#"{ "keys": [ { "kid": "media", "algorithm": "EdDSA", "public_key": "media.pub.pem" } ] }"#
+        ).unwrap();
+        assert!(KeyRing::load_manifest(&dir.path().join("keys.json")).is_err());
+    }
+}
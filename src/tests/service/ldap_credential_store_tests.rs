@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::dao::credential_store::CredentialStore;
+    use crate::dao::ldap_credential_store::LdapCredentialStore;
+    use crate::services::authentication::ldap_settings::{parse_group_roles, LdapSettings};
+
+    fn settings() -> LdapSettings {
+        LdapSettings {
+            url: std::env::var("LDAP_TEST_URL").unwrap_or_else(|_| "ldap://localhost:3893".to_string()),
+            base_dn: "dc=example,dc=com".to_string(),
+            user_attribute: "uid".to_string(),
+            group_attribute: "memberOf".to_string(),
+            bind_dn: Some("cn=svc-fileserver,ou=svcaccts,ou=users,dc=example,dc=com".to_string()),
+            bind_password: Some("serviceaccount".to_string()),
+            group_roles: parse_group_roles(
+                "admin:cn=admins,ou=groups,dc=example,dc=com;user:cn=staff,ou=groups,dc=example,dc=com"
+            ).unwrap(),
+            default_role: None,
+            starttls: false,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn test_parse_group_roles() {
+        let mapping = parse_group_roles(" admin:cn=admins,dc=example,dc=com ; user:cn=staff,dc=example,dc=com;").unwrap();
+        assert_eq!(mapping, vec![
+            ("cn=admins,dc=example,dc=com".to_string(), "admin".to_string()),
+            ("cn=staff,dc=example,dc=com".to_string(), "user".to_string()),
+        ]);
+        assert!(parse_group_roles("").unwrap().is_empty());
+        assert!(parse_group_roles("cn=admins,dc=example,dc=com").is_err());
+        assert!(parse_group_roles("admin:").is_err());
+    }
+
+    #[test]
+    fn test_role_for_groups() {
+        let mut settings = settings();
+        let admin_and_staff = vec![
+            "cn=staff,ou=groups,dc=example,dc=com".to_string(),
+            "CN=Admins, OU=Groups, DC=example, DC=com".to_string(),
+        ];
+
+        // The first mapping wins, regardless of the order of the groups
+        assert_eq!(settings.role_for_groups(&admin_and_staff), Some("admin".to_string()));
+        assert_eq!(settings.role_for_groups(&["cn=staff,ou=groups,dc=example,dc=com".to_string()]), Some("user".to_string()));
+        assert_eq!(settings.role_for_groups(&["cn=contractors,ou=groups,dc=example,dc=com".to_string()]), None);
+
+        settings.default_role = Some("user".to_string());
+        assert_eq!(settings.role_for_groups(&[]), Some("user".to_string()));
+    }
+
+    #[test]
+    fn test_user_lookup_is_escaped() {
+        let settings = settings();
+        assert_eq!(settings.user_filter("alice"), "(uid=alice)");
+        assert_eq!(settings.user_filter("*)(uid=*"), "(uid=\\2a\\29\\28uid=\\2a)");
+        assert_eq!(settings.user_dn("alice"), "uid=alice,dc=example,dc=com");
+        assert_eq!(settings.user_dn("a,b"), "uid=a\\2cb,dc=example,dc=com");
+    }
+
+    #[tokio::test]
+    async fn test_empty_password_is_rejected_without_connecting() {
+        let mut settings = settings();
+        settings.url = "ldap://127.0.0.1:1".to_string();
+        let store = LdapCredentialStore::new(settings);
+
+        assert_eq!(store.verify_credentials("alice", "").await.unwrap_err(), "Invalid credentials");
+    }
+
+    // Needs a directory server loaded with ldap/glauth.cfg, run with `cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore]
+    async fn test_bind_against_glauth() {
+        let store = LdapCredentialStore::new(settings());
+
+        let alice = store.verify_credentials("alice", "password123").await.unwrap();
+        assert_eq!(alice.username, "alice");
+        assert_eq!(alice.role, Some("admin".to_string()));
+
+        let bob = store.verify_credentials("bob", "password123").await.unwrap();
+        assert_eq!(bob.role, Some("user".to_string()));
+
+        assert!(store.verify_credentials("alice", "wrong_password").await.is_err());
+        assert!(store.verify_credentials("nobody", "password123").await.is_err());
+        // carol is only in an unmapped group
+        assert!(store.verify_credentials("carol", "password123").await.is_err());
+    }
+}
@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod tests {
+    use crate::services::authentication::login_throttle::{LoginThrottle, ThrottlePolicy};
+
+    fn policy() -> ThrottlePolicy {
+        ThrottlePolicy {
+            free_attempts: 2,
+            base_delay: 1,
+            max_delay: 8,
+            lockout_threshold: 6,
+            lockout_duration: 600,
+            reset_after: 3600,
+        }
+    }
+
+    #[test]
+    fn test_exponential_backoff_and_lockout() {
+        let throttle = LoginThrottle::new(policy(), policy());
+        let now = 1_000;
+
+        // The first failures are free
+        throttle.record_failure("test_user", None, now);
+        assert_eq!(throttle.check("test_user", None, now), Ok(()));
+
+        // Then the delay doubles with every failure
+        throttle.record_failure("test_user", None, now);
+        assert_eq!(throttle.check("test_user", None, now), Err(1));
+        throttle.record_failure("test_user", None, now);
+        assert_eq!(throttle.check("test_user", None, now), Err(2));
+        throttle.record_failure("test_user", None, now);
+        assert_eq!(throttle.check("test_user", None, now), Err(4));
+        assert_eq!(throttle.check("test_user", None, now + 4), Ok(()));
+
+        // Capped by the maximal delay
+        throttle.record_failure("test_user", None, now);
+        assert_eq!(throttle.check("test_user", None, now), Err(8));
+
+        // Until the account is locked out
+        throttle.record_failure("test_user", None, now);
+        assert_eq!(throttle.check("test_user", None, now), Err(600));
+
+        // Other users are not affected
+        assert_eq!(throttle.check("admin", None, now), Ok(()));
+    }
+
+    #[test]
+    fn test_ip_tracking() {
+        let throttle = LoginThrottle::new(policy(), policy());
+        let now = 1_000;
+
+        // Spraying different usernames from one IP still gets the IP blocked
+        for username in ["a", "b", "c"] {
+            throttle.record_failure(username, Some("10.0.0.1"), now);
+        }
+        assert_eq!(throttle.check("d", Some("10.0.0.1"), now), Err(2));
+        assert_eq!(throttle.check("d", Some("10.0.0.2"), now), Ok(()));
+
+        // A successful login does not clear the IP
+        throttle.record_success("a");
+        assert_eq!(throttle.check("a", Some("10.0.0.1"), now), Err(2));
+    }
+
+    #[test]
+    fn test_failures_are_forgotten() {
+        let throttle = LoginThrottle::new(policy(), policy());
+        let now = 1_000;
+
+        for _ in 0..3 {
+            throttle.record_failure("test_user", None, now);
+        }
+        throttle.record_success("test_user");
+        assert_eq!(throttle.check("test_user", None, now), Ok(()));
+
+        for _ in 0..3 {
+            throttle.record_failure("test_user", None, now);
+        }
+        // After a quiet hour the next failure starts counting from scratch
+        throttle.record_failure("test_user", None, now + 3600);
+        assert_eq!(throttle.check("test_user", None, now + 3600), Ok(()));
+    }
+}
@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use async_trait::async_trait;
+    use data_encoding::BASE32_NOPAD;
+    use mockall::mock;
+    use mockall::predicate::*;
+    use crate::dao::mfa_store::MfaStore;
+    use crate::models::authentication::mfa::MfaState;
+    use crate::services::authentication::mfa_service::{MfaPolicy, MfaService};
+    use crate::services::authentication::opaque_token::hash_opaque_token;
+    use crate::services::authentication::totp::hotp;
+
+    mock! {
+        pub MfaStoreMock {}
+
+        #[async_trait]
+        impl MfaStore for MfaStoreMock {
+            async fn get_mfa_state(&self, username: &str) -> Result<Option<MfaState>, String>;
+            async fn set_pending_secret(&self, username: &str, secret: &str) -> Result<(), String>;
+            async fn enable_mfa(&self, username: &str, recovery_code_hashes: &[String]) -> Result<(), String>;
+            async fn mark_step_used(&self, username: &str, step: i64) -> Result<bool, String>;
+            async fn consume_recovery_code(&self, username: &str, code_hash: &str) -> Result<bool, String>;
+            async fn disable_mfa(&self, username: &str) -> Result<(), String>;
+        }
+    }
+
+    const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    fn current_code() -> String {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let secret = BASE32_NOPAD.decode(SECRET.as_bytes()).unwrap();
+        format!("{:06}", hotp(&secret, now / 30))
+    }
+
+    fn state(enabled: bool) -> MfaState {
+        MfaState {
+            totp_secret: Some(SECRET.to_string()),
+            enabled,
+            last_used_step: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_enrollment() {
+        let mut mock_store = MockMfaStoreMock::new();
+        mock_store.expect_get_mfa_state()
+            .with(eq("test_user"))
+            .returning(|_| Ok(Some(state(false))));
+        mock_store.expect_set_pending_secret()
+            .with(eq("test_user"), always())
+            .times(1)
+            .returning(|_, _| Ok(()));
+        mock_store.expect_mark_step_used()
+            .returning(|_, _| Ok(true));
+        mock_store.expect_enable_mfa()
+            .withf(|username, hashes| username == "test_user" && hashes.len() == 10)
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let mfa_service = MfaService::new(mock_store);
+
+        let enrollment = mfa_service.start_enrollment("test_user", "File Server").await.unwrap();
+        assert!(enrollment.provisioning_uri.contains(&enrollment.secret));
+
+        // The pending secret is only enabled with a valid code
+        assert_eq!(mfa_service.confirm_enrollment("test_user", "000000x").await.unwrap_err().0, 401);
+        let recovery_codes = mfa_service.confirm_enrollment("test_user", &current_code()).await.unwrap();
+        assert_eq!(recovery_codes.len(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_enrollment_when_already_enabled() {
+        let mut mock_store = MockMfaStoreMock::new();
+        mock_store.expect_get_mfa_state()
+            .returning(|_| Ok(Some(state(true))));
+        mock_store.expect_set_pending_secret().never();
+
+        let mfa_service = MfaService::new(mock_store);
+        assert_eq!(mfa_service.start_enrollment("test_user", "File Server").await.unwrap_err().0, 409);
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_replayed_code() {
+        let mut mock_store = MockMfaStoreMock::new();
+        mock_store.expect_get_mfa_state()
+            .returning(|_| Ok(Some(state(true))));
+        mock_store.expect_mark_step_used()
+            .times(1)
+            .returning(|_, _| Ok(true));
+        mock_store.expect_mark_step_used()
+            .returning(|_, _| Ok(false));
+
+        let mfa_service = MfaService::new(mock_store);
+        let code = current_code();
+
+        assert!(mfa_service.verify("test_user", Some(&code), None).await.is_ok());
+        assert_eq!(mfa_service.verify("test_user", Some(&code), None).await.unwrap_err().0, 401);
+        assert_eq!(mfa_service.verify("test_user", None, None).await.unwrap_err().0, 400);
+    }
+
+    #[tokio::test]
+    async fn test_verify_with_recovery_code() {
+        let mut mock_store = MockMfaStoreMock::new();
+        mock_store.expect_get_mfa_state()
+            .returning(|_| Ok(Some(state(true))));
+        mock_store.expect_consume_recovery_code()
+            .with(eq("test_user"), eq(hash_opaque_token("a1b2c3d4e5")))
+            .times(1)
+            .returning(|_, _| Ok(true));
+        mock_store.expect_consume_recovery_code()
+            .returning(|_, _| Ok(false));
+
+        let mfa_service = MfaService::new(mock_store);
+
+        assert!(mfa_service.verify("test_user", None, Some("a1b2c3d4e5")).await.is_ok());
+        assert_eq!(mfa_service.verify("test_user", None, Some("a1b2c3d4e5")).await.unwrap_err().0, 401);
+    }
+
+    #[test]
+    fn test_policy() {
+        let policy = MfaPolicy { required_min_privilege: Some(999), ..MfaPolicy::default() };
+        assert!(policy.requires_mfa(999));
+        assert!(!policy.requires_mfa(1));
+        assert!(!MfaPolicy::default().requires_mfa(999));
+    }
+}
@@ -1,3 +1,21 @@
 pub mod directory_service_tests;
 pub mod file_service_tests;
-mod rename_service_tests;
\ No newline at end of file
+mod rename_service_tests;
+mod refresh_token_service_tests;
+mod authentication_service_tests;
+mod key_ring_tests;
+mod account_service_tests;
+mod login_throttle_tests;
+mod totp_tests;
+mod mfa_service_tests;
+mod api_key_service_tests;
+mod ldap_credential_store_tests;
+mod oidc_client_tests;
+mod oidc_account_service_tests;
+mod group_service_tests;
+
+mod acl_service_tests;
+mod share_service_tests;
+mod share_link_service_tests;
+mod tus_service_tests;
+mod archive_service_tests;
\ No newline at end of file
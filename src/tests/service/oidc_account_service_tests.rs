@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use mockall::mock;
+    use mockall::predicate::*;
+    use tempfile::tempdir;
+    use crate::dao::oidc_identity_store::OidcIdentityStore;
+    use crate::services::authentication::account_service::EXTERNAL_PASSWORD_HASH;
+    use crate::services::authentication::oidc_account_service::OidcAccountService;
+    use crate::services::authentication::oidc_client::{OidcIdentity, OidcSettings};
+
+    mock! {
+        pub OidcIdentityStoreMock {}
+
+        #[async_trait]
+        impl OidcIdentityStore for OidcIdentityStoreMock {
+            async fn find_linked_user(&self, issuer: &str, subject: &str) -> Result<Option<(String, bool)>, String>;
+            async fn get_user_disabled(&self, username: &str) -> Result<Option<bool>, String>;
+            async fn link_identity(&self, issuer: &str, subject: &str, username: &str, linked_at: i64) -> Result<(), String>;
+            async fn create_linked_user(
+                &self,
+                issuer: &str,
+                subject: &str,
+                username: &str,
+                password_hash: &str,
+                role: &str,
+                linked_at: i64
+            ) -> Result<bool, String>;
+        }
+    }
+
+    fn settings() -> OidcSettings {
+        OidcSettings {
+            issuer: "https://idp.example.com".to_string(),
+            client_id: "file-server".to_string(),
+            client_secret: None,
+            redirect_uri: "http://localhost:8080/login/oidc/callback".to_string(),
+            scopes: "openid".to_string(),
+            username_claim: "preferred_username".to_string(),
+            auto_provision: false,
+            default_role: "user".to_string(),
+            link_existing_users: false,
+        }
+    }
+
+    fn identity(username: Option<&str>) -> OidcIdentity {
+        OidcIdentity {
+            issuer: "https://idp.example.com".to_string(),
+            subject: "subject-1".to_string(),
+            username: username.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_linked_identity() {
+        let root = tempdir().unwrap();
+        let mut mock_store = MockOidcIdentityStoreMock::new();
+        mock_store.expect_find_linked_user()
+            .with(eq("https://idp.example.com"), eq("subject-1"))
+            .returning(|_, _| Ok(Some(("alice".to_string(), false))));
+        mock_store.expect_get_user_disabled().never();
+
+        let service = OidcAccountService::new(mock_store, root.path().to_str().unwrap().to_string());
+
+        // The link wins over the username claim, which the user may be able to change at the provider
+        let username = service.resolve_user(&identity(Some("mallory")), &settings()).await.unwrap();
+        assert_eq!(username, "alice");
+    }
+
+    #[tokio::test]
+    async fn test_disabled_user_is_refused() {
+        let root = tempdir().unwrap();
+        let mut mock_store = MockOidcIdentityStoreMock::new();
+        mock_store.expect_find_linked_user().returning(|_, _| Ok(Some(("alice".to_string(), true))));
+
+        let service = OidcAccountService::new(mock_store, root.path().to_str().unwrap().to_string());
+
+        assert_eq!(service.resolve_user(&identity(Some("alice")), &settings()).await.unwrap_err().0, 403);
+    }
+
+    #[tokio::test]
+    async fn test_existing_user_is_only_linked_when_allowed() {
+        let root = tempdir().unwrap();
+        let mut mock_store = MockOidcIdentityStoreMock::new();
+        mock_store.expect_find_linked_user().returning(|_, _| Ok(None));
+        mock_store.expect_get_user_disabled()
+            .with(eq("alice"))
+            .returning(|_| Ok(Some(false)));
+        mock_store.expect_link_identity()
+            .with(eq("https://idp.example.com"), eq("subject-1"), eq("alice"), always())
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+
+        let service = OidcAccountService::new(mock_store, root.path().to_str().unwrap().to_string());
+        let mut settings = settings();
+
+        assert_eq!(service.resolve_user(&identity(Some("alice")), &settings).await.unwrap_err().0, 403);
+
+        settings.link_existing_users = true;
+        assert_eq!(service.resolve_user(&identity(Some("alice")), &settings).await.unwrap(), "alice");
+    }
+
+    #[tokio::test]
+    async fn test_auto_provisioning() {
+        let root = tempdir().unwrap();
+        let mut mock_store = MockOidcIdentityStoreMock::new();
+        mock_store.expect_find_linked_user().returning(|_, _| Ok(None));
+        mock_store.expect_get_user_disabled().returning(|_| Ok(None));
+        mock_store.expect_create_linked_user()
+            .with(eq("https://idp.example.com"), eq("subject-1"), eq("bob"), eq(EXTERNAL_PASSWORD_HASH), eq("user"), always())
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok(true));
+
+        let service = OidcAccountService::new(mock_store, root.path().to_str().unwrap().to_string());
+        let mut settings = settings();
+
+        assert_eq!(service.resolve_user(&identity(Some("bob")), &settings).await.unwrap_err().0, 403);
+
+        settings.auto_provision = true;
+        assert_eq!(service.resolve_user(&identity(Some("bob")), &settings).await.unwrap(), "bob");
+        assert!(root.path().join("bob").is_dir());
+
+        // The claim has to be usable as a directory name
+        assert_eq!(service.resolve_user(&identity(Some("../bob")), &settings).await.unwrap_err().0, 403);
+        assert_eq!(service.resolve_user(&identity(None), &settings).await.unwrap_err().0, 403);
+    }
+}
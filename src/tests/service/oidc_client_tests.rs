@@ -0,0 +1,203 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use jsonwebtoken::jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, OctetKeyPairParameters, OctetKeyPairType,
+    };
+    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+    use reqwest::Url;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+    use sha2::{Digest, Sha256};
+    use crate::services::authentication::oidc_client::{OidcClient, OidcSettings};
+
+    const CLIENT_ID: &str = "file-server";
+    const REDIRECT_URI: &str = "http://localhost:8080/login/oidc/callback";
+    const VALID_CODE: &str = "valid-code";
+
+    /// A minimal provider, just enough of the spec for the authorization code flow
+    struct MockIdp {
+        issuer: String,
+        encoding_key: EncodingKey,
+        jwks: JwkSet,
+        /// The code challenge and nonce of the login in progress, taken from the authorization URL
+        login: Mutex<Option<(String, String)>>,
+        audience: Mutex<String>,
+    }
+
+    #[get("/.well-known/openid-configuration")]
+    async fn discovery(idp: web::Data<MockIdp>) -> impl Responder {
+        HttpResponse::Ok().json(serde_json::json!({
+            "issuer": idp.issuer,
+            "authorization_endpoint": format!("{}/authorize", idp.issuer),
+            "token_endpoint": format!("{}/token", idp.issuer),
+            "jwks_uri": format!("{}/jwks", idp.issuer),
+        }))
+    }
+
+    #[get("/jwks")]
+    async fn jwks(idp: web::Data<MockIdp>) -> impl Responder {
+        HttpResponse::Ok().json(&idp.jwks)
+    }
+
+    #[post("/token")]
+    async fn token(form: web::Form<HashMap<String, String>>, idp: web::Data<MockIdp>) -> impl Responder {
+        let Some((challenge, nonce)) = idp.login.lock().unwrap().clone() else {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": "invalid_grant" }));
+        };
+        let verifier = form.get("code_verifier").cloned().unwrap_or_default();
+        let valid = form.get("grant_type").map(String::as_str) == Some("authorization_code")
+            && form.get("code").map(String::as_str) == Some(VALID_CODE)
+            && form.get("client_id").map(String::as_str) == Some(CLIENT_ID)
+            && form.get("redirect_uri").map(String::as_str) == Some(REDIRECT_URI)
+            && URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())) == challenge;
+        if !valid {
+            return HttpResponse::BadRequest().json(serde_json::json!({ "error": "invalid_grant" }));
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let claims = serde_json::json!({
+            "iss": idp.issuer,
+            "aud": idp.audience.lock().unwrap().clone(),
+            "sub": "idp-subject-1",
+            "preferred_username": "alice",
+            "nonce": nonce,
+            "iat": now,
+            "exp": now + 300,
+        });
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some("idp-key".to_string());
+        let id_token = encode(&header, &claims, &idp.encoding_key).unwrap();
+
+        HttpResponse::Ok().json(serde_json::json!({
+            "access_token": "opaque",
+            "token_type": "Bearer",
+            "id_token": id_token,
+        }))
+    }
+
+    /// Starts the provider on a free local port and returns a client configured for it
+    async fn start_idp() -> (web::Data<MockIdp>, OidcClient) {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let jwk = Jwk {
+            common: CommonParameters {
+                key_id: Some("idp-key".to_string()),
+                ..CommonParameters::default()
+            },
+            algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+            }),
+        };
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let idp = web::Data::new(MockIdp {
+            issuer: issuer.clone(),
+            encoding_key: EncodingKey::from_ed_der(pkcs8.as_ref()),
+            jwks: JwkSet { keys: vec![jwk] },
+            login: Mutex::new(None),
+            audience: Mutex::new(CLIENT_ID.to_string()),
+        });
+
+        let app_data = idp.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_data.clone())
+                .service(discovery)
+                .service(jwks)
+                .service(token)
+        })
+            .workers(1)
+            .listen(listener)
+            .unwrap()
+            .run();
+        actix_web::rt::spawn(server);
+
+        let client = OidcClient::new(OidcSettings {
+            issuer,
+            client_id: CLIENT_ID.to_string(),
+            client_secret: None,
+            redirect_uri: REDIRECT_URI.to_string(),
+            scopes: "openid profile".to_string(),
+            username_claim: "preferred_username".to_string(),
+            auto_provision: false,
+            default_role: "user".to_string(),
+            link_existing_users: false,
+        });
+        (idp, client)
+    }
+
+    fn now() -> i64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+    }
+
+    /// Starts a login like a browser would and tells the provider about it. Returns the state.
+    async fn begin_login(idp: &MockIdp, client: &OidcClient) -> String {
+        let (url, state) = client.start(now()).await.unwrap();
+        let url = Url::parse(&url).unwrap();
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+        assert!(url.as_str().starts_with(&format!("{}/authorize?", idp.issuer)));
+        assert_eq!(params["response_type"], "code");
+        assert_eq!(params["client_id"], CLIENT_ID);
+        assert_eq!(params["redirect_uri"], REDIRECT_URI);
+        assert_eq!(params["code_challenge_method"], "S256");
+        assert_eq!(params["state"], state);
+
+        *idp.login.lock().unwrap() = Some((params["code_challenge"].clone(), params["nonce"].clone()));
+        state
+    }
+
+    #[actix_web::test]
+    async fn test_authorization_code_flow() {
+        let (idp, client) = start_idp().await;
+        let state = begin_login(&idp, &client).await;
+
+        let identity = client.complete(VALID_CODE, &state, now()).await.unwrap();
+        assert_eq!(identity.issuer, idp.issuer);
+        assert_eq!(identity.subject, "idp-subject-1");
+        assert_eq!(identity.username, Some("alice".to_string()));
+
+        // The state can't be replayed
+        assert_eq!(client.complete(VALID_CODE, &state, now()).await.unwrap_err().0, 400);
+    }
+
+    #[actix_web::test]
+    async fn test_rejected_logins() {
+        let (idp, client) = start_idp().await;
+
+        assert_eq!(client.complete(VALID_CODE, "unknown-state", now()).await.unwrap_err().0, 400);
+
+        let state = begin_login(&idp, &client).await;
+        assert_eq!(client.complete("wrong-code", &state, now()).await.unwrap_err().0, 401);
+
+        // Logins expire after 10 minutes
+        let state = begin_login(&idp, &client).await;
+        assert_eq!(client.complete(VALID_CODE, &state, now() + 601).await.unwrap_err().0, 400);
+
+        // ID tokens issued to another client are refused
+        *idp.audience.lock().unwrap() = "another-client".to_string();
+        let state = begin_login(&idp, &client).await;
+        assert_eq!(client.complete(VALID_CODE, &state, now()).await.unwrap_err().0, 401);
+    }
+
+    #[actix_web::test]
+    async fn test_nonce_must_match() {
+        let (idp, client) = start_idp().await;
+        let state = begin_login(&idp, &client).await;
+        let challenge = idp.login.lock().unwrap().clone().unwrap().0;
+        *idp.login.lock().unwrap() = Some((challenge, "another-nonce".to_string()));
+
+        let (code, msg) = client.complete(VALID_CODE, &state, now()).await.unwrap_err();
+        assert_eq!(code, 401);
+        assert!(msg.contains("nonce"));
+    }
+}
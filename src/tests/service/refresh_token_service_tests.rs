@@ -0,0 +1,131 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use async_trait::async_trait;
+    use mockall::mock;
+    use mockall::predicate::*;
+    use crate::dao::refresh_token_store::RefreshTokenStore;
+    use crate::models::authentication::refresh_token::RefreshTokenRecord;
+    use crate::services::authentication::opaque_token::hash_opaque_token;
+    use crate::services::authentication::refresh_token_service::RefreshTokenService;
+
+    mock! {
+        pub RefreshTokenStoreMock {}
+
+        #[async_trait]
+        impl RefreshTokenStore for RefreshTokenStoreMock {
+            async fn insert_refresh_token(&self, record: &RefreshTokenRecord) -> Result<(), String>;
+            async fn find_refresh_token(&self, token_hash: &str) -> Result<Option<RefreshTokenRecord>, String>;
+            async fn mark_refresh_token_used(&self, token_hash: &str) -> Result<bool, String>;
+            async fn revoke_token_family(&self, family_id: &str) -> Result<(), String>;
+            async fn revoke_user_tokens(&self, username: &str) -> Result<(), String>;
+        }
+    }
+
+    fn now() -> i64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+    }
+
+    fn record(token: &str, used: bool, expires_at: i64) -> RefreshTokenRecord {
+        RefreshTokenRecord {
+            token_hash: hash_opaque_token(token),
+            family_id: "family".to_string(),
+            username: "test_user".to_string(),
+            expires_at,
+            used,
+            revoked: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_issue_stores_only_the_hash() {
+        let mut mock_store = MockRefreshTokenStoreMock::new();
+        mock_store.expect_insert_refresh_token()
+            .withf(|r| r.username == "test_user" && !r.used && !r.revoked)
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let service = RefreshTokenService::new(mock_store);
+        let token = service.issue("test_user").await.unwrap();
+
+        assert_eq!(token.len(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_returns_new_token_in_same_family() {
+        let mut mock_store = MockRefreshTokenStoreMock::new();
+        let old = record("old_token", false, now() + 100);
+        mock_store.expect_find_refresh_token()
+            .with(eq(hash_opaque_token("old_token")))
+            .returning(move |_| Ok(Some(old.clone())));
+        mock_store.expect_mark_refresh_token_used()
+            .times(1)
+            .returning(|_| Ok(true));
+        mock_store.expect_insert_refresh_token()
+            .withf(|r| r.family_id == "family" && r.token_hash != hash_opaque_token("old_token"))
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_store.expect_revoke_token_family().never();
+
+        let service = RefreshTokenService::new(mock_store);
+        let (username, new_token) = service.rotate("old_token").await.unwrap();
+
+        assert_eq!(username, "test_user");
+        assert_ne!(new_token, "old_token");
+    }
+
+    #[tokio::test]
+    async fn test_rotate_reused_token_revokes_family() {
+        let mut mock_store = MockRefreshTokenStoreMock::new();
+        let used = record("used_token", true, now() + 100);
+        mock_store.expect_find_refresh_token()
+            .returning(move |_| Ok(Some(used.clone())));
+        mock_store.expect_revoke_token_family()
+            .with(eq("family"))
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_store.expect_insert_refresh_token().never();
+
+        let service = RefreshTokenService::new(mock_store);
+        let (code, _) = service.rotate("used_token").await.unwrap_err();
+
+        assert_eq!(code, 401);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_lost_race_revokes_family() {
+        let mut mock_store = MockRefreshTokenStoreMock::new();
+        let fresh = record("raced_token", false, now() + 100);
+        mock_store.expect_find_refresh_token()
+            .returning(move |_| Ok(Some(fresh.clone())));
+        mock_store.expect_mark_refresh_token_used()
+            .returning(|_| Ok(false));
+        mock_store.expect_revoke_token_family()
+            .times(1)
+            .returning(|_| Ok(()));
+        mock_store.expect_insert_refresh_token().never();
+
+        let service = RefreshTokenService::new(mock_store);
+        let (code, _) = service.rotate("raced_token").await.unwrap_err();
+
+        assert_eq!(code, 401);
+    }
+
+    #[tokio::test]
+    async fn test_rotate_expired_or_unknown_token() {
+        let mut mock_store = MockRefreshTokenStoreMock::new();
+        let expired = record("expired_token", false, now() - 1);
+        mock_store.expect_find_refresh_token()
+            .with(eq(hash_opaque_token("expired_token")))
+            .returning(move |_| Ok(Some(expired.clone())));
+        mock_store.expect_find_refresh_token()
+            .with(eq(hash_opaque_token("unknown_token")))
+            .returning(|_| Ok(None));
+        mock_store.expect_mark_refresh_token_used().never();
+
+        let service = RefreshTokenService::new(mock_store);
+
+        assert_eq!(service.rotate("expired_token").await.unwrap_err().0, 401);
+        assert_eq!(service.rotate("unknown_token").await.unwrap_err().0, 401);
+    }
+}
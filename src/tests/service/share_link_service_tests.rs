@@ -0,0 +1,257 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use async_trait::async_trait;
+    use futures_util::stream;
+    use tempfile::tempdir;
+    use crate::dao::share_link_store::ShareLinkStore;
+    use crate::models::shares::share_link::{ShareLink, ShareLinkKind};
+    use crate::services::authentication::opaque_token::hash_opaque_token;
+    use crate::services::file_structure::file_service::{FileService, StagedFile};
+    use crate::services::file_structure::share_link_service::ShareLinkService;
+    use crate::services::locking::directory_locking_manager::DirectoryLockManager;
+
+    // In the future, so links expiring after it can be created
+    const NOW: i64 = 4_000_000_000;
+
+    /// Keeps the links in memory together with the hashes of their tokens
+    #[derive(Default)]
+    struct MemoryShareLinkStore {
+        links: Mutex<Vec<(String, ShareLink)>>,
+    }
+
+    #[async_trait]
+    impl ShareLinkStore for MemoryShareLinkStore {
+        async fn insert_link(&self, link: &ShareLink, token_hash: &str) -> Result<(), String> {
+            self.links.lock().unwrap().push((token_hash.to_string(), link.clone()));
+            Ok(())
+        }
+
+        async fn find_link(&self, token_hash: &str) -> Result<Option<ShareLink>, String> {
+            Ok(self.links.lock().unwrap().iter().find(|(hash, _)| hash == token_hash).map(|(_, link)| link.clone()))
+        }
+
+        async fn list_links(&self, owner: &str) -> Result<Vec<ShareLink>, String> {
+            Ok(self.links.lock().unwrap().iter().filter(|(_, link)| link.owner == owner).map(|(_, link)| link.clone()).collect())
+        }
+
+        async fn revoke_link(&self, owner: &str, id: &str) -> Result<bool, String> {
+            let mut links = self.links.lock().unwrap();
+            match links.iter_mut().find(|(_, link)| link.owner == owner && link.id == id) {
+                Some((_, link)) => {
+                    link.revoked = true;
+                    Ok(true)
+                },
+                None => Ok(false),
+            }
+        }
+
+        async fn move_sources(&self, owner: &str, old_path: &str, new_path: &str) -> Result<(), String> {
+            for (_, link) in self.links.lock().unwrap().iter_mut().filter(|(_, link)| link.owner == owner) {
+                if link.path == old_path {
+                    link.path = new_path.to_string();
+                } else if let Some(rest) = link.path.strip_prefix(&format!("{}/", old_path)) {
+                    link.path = format!("{}/{}", new_path, rest);
+                }
+            }
+            Ok(())
+        }
+
+        async fn revoke_sources(&self, owner: &str, path: &str) -> Result<(), String> {
+            for (_, link) in self.links.lock().unwrap().iter_mut().filter(|(_, link)| link.owner == owner) {
+                if link.path == path || link.path.starts_with(&format!("{}/", path)) {
+                    link.revoked = true;
+                }
+            }
+            Ok(())
+        }
+
+        async fn record_upload(&self, id: &str, bytes: i64, _now: i64) -> Result<bool, String> {
+            let mut links = self.links.lock().unwrap();
+            let Some((_, link)) = links.iter_mut().find(|(_, link)| link.id == id) else {
+                return Ok(false);
+            };
+            if link.max_files.is_some_and(|max| link.upload_count >= max)
+                || link.max_bytes.is_some_and(|max| link.uploaded_bytes + bytes > max) {
+                return Ok(false);
+            }
+            link.upload_count += 1;
+            link.uploaded_bytes += bytes;
+            Ok(true)
+        }
+
+        async fn undo_upload(&self, id: &str, bytes: i64) -> Result<(), String> {
+            if let Some((_, link)) = self.links.lock().unwrap().iter_mut().find(|(_, link)| link.id == id) {
+                link.upload_count -= 1;
+                link.uploaded_bytes -= bytes;
+            }
+            Ok(())
+        }
+
+        async fn record_download(&self, id: &str, _now: i64) -> Result<bool, String> {
+            let mut links = self.links.lock().unwrap();
+            let Some((_, link)) = links.iter_mut().find(|(_, link)| link.id == id) else {
+                return Ok(false);
+            };
+            if link.max_downloads.is_some_and(|max| link.download_count >= max) {
+                return Ok(false);
+            }
+            link.download_count += 1;
+            Ok(true)
+        }
+    }
+
+    async fn staged(root: &Path, content: &[u8]) -> StagedFile {
+        let file_service = FileService::new(root.to_str().unwrap().to_string(), DirectoryLockManager::new());
+        let chunks = stream::iter([Ok::<_, std::io::Error>(content.to_vec())]);
+        file_service.stage_upload(chunks, None).await.unwrap()
+    }
+
+    fn setup(root: &Path) -> (Arc<MemoryShareLinkStore>, ShareLinkService) {
+        let holiday = root.join("alice").join("pictures").join("holiday");
+        fs::create_dir_all(&holiday).unwrap();
+        fs::write(holiday.join("beach.png"), b"sand").unwrap();
+        fs::write(root.join("alice").join("notes.txt"), b"notes").unwrap();
+
+        let store = Arc::new(MemoryShareLinkStore::default());
+        let service = ShareLinkService::new(store.clone(), root.to_str().unwrap().to_string());
+        (store, service)
+    }
+
+    #[tokio::test]
+    async fn test_create_link() {
+        let root = tempdir().unwrap();
+        let (store, service) = setup(root.path());
+
+        let (token, link) = service.create_link("alice", "/pictures/holiday/", None, None, None).await.unwrap();
+        assert_eq!(link.path, "pictures/holiday");
+        assert!(link.is_dir);
+        assert!(!link.has_password);
+        // Only the hash of the token is stored
+        assert!(store.find_link(&token).await.unwrap().is_none());
+        assert!(store.find_link(&hash_opaque_token(&token)).await.unwrap().is_some());
+
+        let (_, link) = service.create_link("alice", "notes.txt", Some(i64::MAX), Some("secret"), Some(3)).await.unwrap();
+        assert!(link.has_password);
+        assert_ne!(link.password_hash.as_deref(), Some("secret"));
+
+        assert_eq!(service.create_link("alice", "", None, None, None).await.unwrap_err().0, 400);
+        assert_eq!(service.create_link("alice", "../bob", None, None, None).await.unwrap_err().0, 400);
+        assert_eq!(service.create_link("alice", "missing.txt", None, None, None).await.unwrap_err().0, 404);
+        assert_eq!(service.create_link("alice", "notes.txt", Some(1), None, None).await.unwrap_err().0, 400);
+        assert_eq!(service.create_link("alice", "notes.txt", None, Some(""), None).await.unwrap_err().0, 400);
+        assert_eq!(service.create_link("alice", "notes.txt", None, None, Some(0)).await.unwrap_err().0, 400);
+        assert_eq!(service.list("alice").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_open_link() {
+        let root = tempdir().unwrap();
+        let (_, service) = setup(root.path());
+
+        let (token, _) = service.create_link("alice", "notes.txt", Some(NOW + 60), Some("secret"), Some(2)).await.unwrap();
+        assert_eq!(service.open("unknown", None, NOW).await.unwrap_err().0, 404);
+        assert_eq!(service.open(&token, None, NOW).await.unwrap_err().0, 401);
+        assert_eq!(service.open(&token, Some("guess"), NOW).await.unwrap_err().0, 401);
+        assert_eq!(service.open(&token, Some("secret"), NOW + 60).await.unwrap_err().0, 410);
+
+        let link = service.open(&token, Some("secret"), NOW).await.unwrap();
+        assert_eq!(link.path, "notes.txt");
+        // Opening alone doesn't count
+        assert_eq!(link.download_count, 0);
+        assert_eq!(service.record_download(link, NOW).await.unwrap().download_count, 1);
+        let link = service.open(&token, Some("secret"), NOW).await.unwrap();
+        assert!(service.record_download(link.clone(), NOW).await.is_ok());
+        // Used up
+        assert_eq!(service.open(&token, Some("secret"), NOW).await.unwrap_err().0, 410);
+        assert_eq!(service.record_download(link, NOW).await.unwrap_err().0, 410);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_link() {
+        let root = tempdir().unwrap();
+        let (_, service) = setup(root.path());
+        let (token, link) = service.create_link("alice", "notes.txt", None, None, None).await.unwrap();
+
+        assert_eq!(service.revoke("bob", &link.id).await.unwrap_err().0, 404);
+        assert!(service.revoke("alice", &link.id).await.is_ok());
+        assert_eq!(service.open(&token, None, NOW).await.unwrap_err().0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_file_drop() {
+        let root = tempdir().unwrap();
+        let (_, service) = setup(root.path());
+        let holiday = root.path().join("alice").join("pictures").join("holiday");
+
+        assert_eq!(service.create_drop("alice", "notes.txt", None, None, None).await.unwrap_err().0, 400);
+        assert_eq!(service.create_drop("alice", "pictures", None, Some(0), None).await.unwrap_err().0, 400);
+        let (token, link) = service.create_drop("alice", "pictures/holiday", Some(NOW + 60), Some(10), Some(3)).await.unwrap();
+        assert_eq!(link.kind, ShareLinkKind::Drop);
+
+        // Never downloads
+        assert_eq!(service.open(&token, None, NOW).await.unwrap_err().0, 404);
+
+        // Existing files are kept
+        assert_eq!(service.receive_file(&token, "beach.png", staged(root.path(), b"waves").await, NOW).await.unwrap(), "beach (1).png");
+        assert_eq!(fs::read(holiday.join("beach.png")).unwrap(), b"sand");
+        assert_eq!(fs::read(holiday.join("beach (1).png")).unwrap(), b"waves");
+
+        assert_eq!(service.receive_file(&token, "..", staged(root.path(), b"").await, NOW).await.unwrap_err().0, 400);
+        assert_eq!(service.receive_file(&token, "large.bin", staged(root.path(), b"too large").await, NOW).await.unwrap_err().0, 413);
+        assert_eq!(service.receive_file(&token, "a.txt", staged(root.path(), b"a").await, NOW + 60).await.unwrap_err().0, 410);
+        assert!(service.receive_file(&token, "a.txt", staged(root.path(), b"a").await, NOW).await.is_ok());
+        assert!(service.receive_file(&token, "b.txt", staged(root.path(), b"b").await, NOW).await.is_ok());
+        // Three files
+        assert_eq!(service.receive_file(&token, "c.txt", staged(root.path(), b"c").await, NOW).await.unwrap_err().0, 410);
+        assert!(!holiday.join("c.txt").exists());
+
+        // Download links don't take uploads
+        let (token, _) = service.create_link("alice", "pictures/holiday", None, None, None).await.unwrap();
+        assert_eq!(service.receive_file(&token, "a.txt", staged(root.path(), b"a").await, NOW).await.unwrap_err().0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_file_drop_failures() {
+        let root = tempdir().unwrap();
+        let (store, service) = setup(root.path());
+        let holiday = root.path().join("alice").join("pictures").join("holiday");
+        let (token, link) = service.create_drop("alice", "pictures/holiday", None, Some(10), Some(1)).await.unwrap();
+
+        // A file that can't be stored doesn't count
+        let vanished = StagedFile::from_path(root.path().join("vanished"), 4).unwrap();
+        assert_eq!(service.receive_file(&token, "a.txt", vanished, NOW).await.unwrap_err().0, 500);
+        assert!(!holiday.join("a.txt").exists());
+        let counted = store.list_links("alice").await.unwrap().into_iter().find(|l| l.id == link.id).unwrap();
+        assert_eq!((counted.upload_count, counted.uploaded_bytes), (0, 0));
+
+        // Nothing is stored once the folder is gone, even if one of the same name appears
+        fs::rename(&holiday, root.path().join("alice").join("holiday")).unwrap();
+        assert_eq!(service.open_drop(&token, NOW).await.unwrap_err().0, 404);
+        assert_eq!(service.receive_file(&token, "a.txt", staged(root.path(), b"a").await, NOW).await.unwrap_err().0, 404);
+        fs::write(root.path().join("alice").join("pictures").join("holiday"), b"not a folder").unwrap();
+        assert_eq!(service.receive_file(&token, "a.txt", staged(root.path(), b"a").await, NOW).await.unwrap_err().0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_link_follows_renames() {
+        let root = tempdir().unwrap();
+        let (_, service) = setup(root.path());
+        let (token, _) = service.create_link("alice", "pictures/holiday", None, None, None).await.unwrap();
+
+        let alice = root.path().join("alice");
+        fs::rename(alice.join("pictures").join("holiday"), alice.join("holiday_12_2022")).unwrap();
+        assert_eq!(service.open(&token, None, NOW).await.unwrap_err().0, 404);
+        service.move_path("alice", "pictures/holiday", "holiday_12_2022").await.unwrap();
+        assert_eq!(service.open(&token, None, NOW).await.unwrap().path, "holiday_12_2022");
+
+        // A new directory at the place of a deleted one is not exposed
+        fs::remove_dir_all(alice.join("holiday_12_2022")).unwrap();
+        service.delete_path("alice", "holiday_12_2022").await.unwrap();
+        fs::create_dir(alice.join("holiday_12_2022")).unwrap();
+        assert_eq!(service.open(&token, None, NOW).await.unwrap_err().0, 404);
+        assert!(service.list("alice").await.unwrap()[0].revoked);
+    }
+}
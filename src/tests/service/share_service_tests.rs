@@ -0,0 +1,204 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+    use async_trait::async_trait;
+    use tempfile::tempdir;
+    use crate::dao::share_store::ShareStore;
+    use crate::models::shares::share::Share;
+    use crate::services::file_structure::directory_service::DirectoryService;
+    use crate::services::file_structure::share_service::{is_reserved_path, strip_shared_prefix, ShareService, SHARED_WITH_ME};
+    use crate::services::locking::directory_locking_manager::DirectoryLockManager;
+
+    /// Keeps the shares in memory, so moves recorded by the service can be checked
+    #[derive(Default)]
+    struct MemoryShareStore {
+        shares: Mutex<Vec<Share>>,
+    }
+
+    #[async_trait]
+    impl ShareStore for MemoryShareStore {
+        async fn create_share(&self, share: &Share) -> Result<Option<i64>, String> {
+            if share.recipient == "ghost" {
+                return Err("User not found".to_string());
+            }
+            let mut shares = self.shares.lock().unwrap();
+            if shares.iter().any(|s| s.recipient == share.recipient && s.name == share.name) {
+                return Ok(None);
+            }
+            let id = shares.len() as i64 + 1;
+            shares.push(Share { id, ..share.clone() });
+            Ok(Some(id))
+        }
+
+        async fn list_shares(&self, username: &str, incoming: bool) -> Result<Vec<Share>, String> {
+            Ok(self.shares.lock().unwrap()
+                .iter()
+                .filter(|s| if incoming { s.recipient == username } else { s.owner == username })
+                .cloned()
+                .collect())
+        }
+
+        async fn find_share(&self, recipient: &str, name: &str) -> Result<Option<Share>, String> {
+            Ok(self.shares.lock().unwrap().iter().find(|s| s.recipient == recipient && s.name == name).cloned())
+        }
+
+        async fn move_sources(&self, owner: &str, old_path: &str, new_path: &str) -> Result<(), String> {
+            for share in self.shares.lock().unwrap().iter_mut().filter(|s| s.owner == owner) {
+                if share.path == old_path {
+                    share.path = new_path.to_string();
+                } else if let Some(rest) = share.path.strip_prefix(&format!("{}/", old_path)) {
+                    share.path = format!("{}/{}", new_path, rest);
+                }
+            }
+            Ok(())
+        }
+
+        async fn refresh_sources(&self, owner: &str, path: &str, file_id: &str) -> Result<(), String> {
+            for share in self.shares.lock().unwrap().iter_mut().filter(|s| s.owner == owner && s.path == path && !s.is_dir) {
+                share.file_id = file_id.to_string();
+            }
+            Ok(())
+        }
+
+        async fn delete_sources(&self, owner: &str, path: &str) -> Result<(), String> {
+            self.shares.lock().unwrap().retain(|s| {
+                s.owner != owner || (s.path != path && !s.path.starts_with(&format!("{}/", path)))
+            });
+            Ok(())
+        }
+
+        async fn delete_share(&self, id: i64, username: &str) -> Result<bool, String> {
+            let mut shares = self.shares.lock().unwrap();
+            let before = shares.len();
+            shares.retain(|s| !(s.id == id && (s.owner == username || s.recipient == username)));
+            Ok(shares.len() < before)
+        }
+    }
+
+    fn setup(root: &Path) -> (Arc<MemoryShareStore>, ShareService) {
+        let holiday = root.join("alice").join("pictures").join("holiday");
+        fs::create_dir_all(&holiday).unwrap();
+        fs::write(holiday.join("beach.png"), b"sand").unwrap();
+        fs::write(root.join("alice").join("notes.txt"), b"notes").unwrap();
+
+        let store = Arc::new(MemoryShareStore::default());
+        let service = ShareService::new(store.clone(), root.to_str().unwrap().to_string());
+        (store, service)
+    }
+
+    #[tokio::test]
+    async fn test_create_share() {
+        let root = tempdir().unwrap();
+        let (_, service) = setup(root.path());
+
+        let share = service.create_share("alice", "/pictures/holiday/", "bob", None, false).await.unwrap();
+        assert_eq!(share.name, "holiday");
+        assert_eq!(share.path, "pictures/holiday");
+        assert!(share.is_dir);
+
+        assert_eq!(service.create_share("alice", "notes.txt", "bob", Some("holiday"), false).await.unwrap_err().0, 409);
+        assert!(service.create_share("alice", "notes.txt", "bob", Some("alice's notes"), true).await.is_ok());
+
+        assert_eq!(service.create_share("alice", "", "bob", None, false).await.unwrap_err().0, 400);
+        assert_eq!(service.create_share("alice", "notes.txt", "alice", None, false).await.unwrap_err().0, 400);
+        assert_eq!(service.create_share("alice", "notes.txt", "carol", Some("a/b"), false).await.unwrap_err().0, 400);
+        assert_eq!(service.create_share("alice", "missing.txt", "carol", None, false).await.unwrap_err().0, 404);
+        assert_eq!(service.create_share("alice", "../bob", "carol", None, false).await.unwrap_err().0, 400);
+        assert_eq!(service.create_share("alice", "notes.txt", "ghost", None, false).await.unwrap_err().0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_share_follows_renames() {
+        let root = tempdir().unwrap();
+        let (store, service) = setup(root.path());
+        service.create_share("alice", "pictures/holiday", "bob", None, false).await.unwrap();
+        service.create_share("alice", "pictures/holiday/beach.png", "bob", None, false).await.unwrap();
+        service.create_share("alice", "notes.txt", "bob", None, false).await.unwrap();
+
+        let alice = root.path().join("alice");
+        fs::create_dir(alice.join("archive")).unwrap();
+        fs::rename(alice.join("pictures").join("holiday"), alice.join("archive").join("holiday_12_2022")).unwrap();
+        // Until the rename is recorded, the source is missing
+        assert_eq!(service.resolve("bob", "holiday").await.unwrap_err().0, 404);
+
+        service.move_path("alice", "pictures/holiday", "/archive/holiday_12_2022").await.unwrap();
+        assert_eq!(service.resolve("bob", "holiday").await.unwrap().path, "archive/holiday_12_2022");
+        assert_eq!(service.resolve("bob", "beach.png").await.unwrap().path, "archive/holiday_12_2022/beach.png");
+
+        // Another file put in place of the source isn't shared, unless the server stored it there
+        fs::write(alice.join("new_notes.txt"), b"new notes").unwrap();
+        fs::rename(alice.join("new_notes.txt"), alice.join("notes.txt")).unwrap();
+        assert_eq!(service.resolve("bob", "notes.txt").await.unwrap_err().0, 404);
+        service.replace_file("alice", "/notes.txt").await.unwrap();
+        assert_eq!(service.resolve("bob", "notes.txt").await.unwrap().path, "notes.txt");
+        assert_eq!(service.replace_file("alice", "missing.txt").await.unwrap_err().0, 404);
+
+        // Sources that are gone are left out, but still listed for the owner
+        fs::remove_file(alice.join("notes.txt")).unwrap();
+        assert_eq!(service.resolve("bob", "notes.txt").await.unwrap_err().0, 404);
+        assert_eq!(service.list_incoming("bob").await.unwrap().len(), 2);
+        assert_eq!(service.list_outgoing("alice").await.unwrap().len(), 3);
+
+        // Once the deletion is recorded, a new file at the same place is not shared
+        service.delete_path("alice", "notes.txt").await.unwrap();
+        service.delete_path("alice", "archive").await.unwrap();
+        fs::write(alice.join("notes.txt"), b"other notes").unwrap();
+        assert_eq!(service.resolve("bob", "notes.txt").await.unwrap_err().0, 404);
+        assert!(store.list_shares("alice", false).await.unwrap().is_empty());
+
+        assert_eq!(service.resolve("carol", "holiday").await.unwrap_err().0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_revoke_share() {
+        let root = tempdir().unwrap();
+        let (_, service) = setup(root.path());
+        let by_owner = service.create_share("alice", "pictures/holiday", "bob", None, false).await.unwrap();
+        let by_recipient = service.create_share("alice", "notes.txt", "bob", None, false).await.unwrap();
+
+        assert_eq!(service.revoke("carol", by_owner.id).await.unwrap_err().0, 404);
+        assert!(service.revoke("alice", by_owner.id).await.is_ok());
+        assert!(service.revoke("bob", by_recipient.id).await.is_ok());
+        assert!(service.list_incoming("bob").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_shared_with_me_node() {
+        let root = tempdir().unwrap();
+        let (_, service) = setup(root.path());
+        fs::create_dir_all(root.path().join("bob").join("documents")).unwrap();
+        service.create_share("alice", "pictures/holiday", "bob", None, false).await.unwrap();
+        service.create_share("alice", "notes.txt", "bob", None, false).await.unwrap();
+
+        let directory_service = DirectoryService::new(
+            root.path().to_str().unwrap().to_string(),
+            DirectoryLockManager::new()
+        ).with_shared(service.list_incoming("bob").await.unwrap());
+
+        let tree = directory_service.build_dir_tree(&"bob".to_string(), Path::new("")).unwrap();
+        let shared = tree.dirs.iter().find(|dir| dir.name == SHARED_WITH_ME).expect("no shared node");
+        assert_eq!(shared.files, vec!["notes.txt".to_string()]);
+        assert_eq!(shared.dirs.len(), 1);
+        assert_eq!(shared.dirs[0].name, "holiday");
+        assert_eq!(shared.dirs[0].files, vec!["beach.png".to_string()]);
+
+        // Only the root shows the node
+        let documents = directory_service.build_dir_tree(&"bob".to_string(), Path::new("documents")).unwrap();
+        assert!(documents.dirs.is_empty());
+    }
+
+    #[test]
+    fn test_strip_shared_prefix() {
+        assert_eq!(strip_shared_prefix("Shared with me"), Some(""));
+        assert_eq!(strip_shared_prefix("Shared with me/holiday/day1"), Some("holiday/day1"));
+        assert_eq!(strip_shared_prefix("Shared with meat"), None);
+        assert_eq!(strip_shared_prefix("pictures/Shared with me"), None);
+
+        assert!(is_reserved_path("/Shared with me"));
+        assert!(is_reserved_path("Shared with me/holiday"));
+        assert!(!is_reserved_path("pictures/Shared with me"));
+        assert!(!is_reserved_path("../Shared with me"));
+    }
+}
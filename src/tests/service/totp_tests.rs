@@ -0,0 +1,42 @@
+#[cfg(test)]
+mod tests {
+    use data_encoding::BASE32_NOPAD;
+    use crate::services::authentication::totp::{generate_totp_secret, hotp, provisioning_uri, verify_totp};
+
+    // Secret of the test vectors in RFC 4226 and RFC 6238
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_hotp_matches_rfc_vectors() {
+        assert_eq!(hotp(RFC_SECRET, 0), 755224);
+        assert_eq!(hotp(RFC_SECRET, 1), 287082);
+        assert_eq!(hotp(RFC_SECRET, 9), 520489);
+        // RFC 6238 values, truncated to 6 digits
+        assert_eq!(hotp(RFC_SECRET, 1111111109 / 30), 81804);
+        assert_eq!(hotp(RFC_SECRET, 1234567890 / 30), 5924);
+    }
+
+    #[test]
+    fn test_verify_totp_allows_one_step_of_drift() {
+        let secret = BASE32_NOPAD.encode(RFC_SECRET);
+        let now = 1234567890;
+
+        assert_eq!(verify_totp(&secret, "005924", now), Some(now / 30));
+        assert_eq!(verify_totp(&secret, "005924", now + 30), Some(now / 30));
+        assert_eq!(verify_totp(&secret, "005924", now + 90), None);
+        assert_eq!(verify_totp(&secret, "5924", now), None);
+        assert_eq!(verify_totp(&secret, "abcdef", now), None);
+    }
+
+    #[test]
+    fn test_generated_secret_and_uri() {
+        let secret = generate_totp_secret();
+        assert_eq!(BASE32_NOPAD.decode(secret.as_bytes()).unwrap().len(), 20);
+
+        let uri = provisioning_uri("File Server", "test_user", &secret);
+        assert_eq!(
+            uri,
+            format!("otpauth://totp/File%20Server:test_user?secret={}&issuer=File%20Server&algorithm=SHA1&digits=6&period=30", secret)
+        );
+    }
+}
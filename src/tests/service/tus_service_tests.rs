@@ -0,0 +1,122 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io;
+    use futures_util::stream;
+    use tempfile::tempdir;
+    use crate::services::file_structure::tus_service::{parse_upload_metadata, TusService, TusSettings};
+    use crate::services::locking::directory_locking_manager::DirectoryLockManager;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn metadata(filename: &str) -> HashMap<String, String> {
+        HashMap::from([
+            ("filename".to_string(), filename.to_string()),
+            ("path".to_string(), "videos".to_string()),
+        ])
+    }
+
+    fn chunks(chunks: &[&'static [u8]]) -> impl futures_util::Stream<Item = Result<&'static [u8], io::Error>> + Unpin {
+        stream::iter(chunks.iter().map(|chunk| Ok(*chunk)).collect::<Vec<_>>())
+    }
+
+    fn tus_service(root: &std::path::Path) -> TusService {
+        TusService::new(
+            root.to_str().unwrap().to_string(),
+            DirectoryLockManager::new(),
+            TusSettings { max_size: 100, expiration_seconds: 60, max_open_uploads: 3 }
+        )
+    }
+
+    #[tokio::test]
+    async fn test_resumable_upload() {
+        let root = tempdir().unwrap();
+        let service = tus_service(root.path());
+
+        for invalid in ["", "/", "..", "../holiday.mp4", "videos/holiday.mp4", "C:holiday.mp4", "holiday\n.mp4"] {
+            assert_eq!(service.create("alice", 10, &metadata(invalid), NOW).await.unwrap_err().0, 400);
+        }
+
+        let upload = service.create("alice", 10, &metadata("holiday.mp4"), NOW).await.unwrap();
+        assert_eq!(upload.filename, "holiday.mp4");
+        assert_eq!(upload.path, "videos");
+        assert_eq!(upload.expires_at, NOW + 60);
+        assert_eq!(service.offset("alice", &upload.id, NOW).await.unwrap().1, 0);
+
+        let (_, offset, completed) = service.append("alice", &upload.id, 0, chunks(&[b"0123"]), NOW).await.unwrap();
+        assert_eq!(offset, 4);
+        assert!(completed.is_none());
+
+        // The connection drops, what arrived before is kept
+        let broken = stream::iter([Ok(&b"45"[..]), Err(io::Error::from(io::ErrorKind::ConnectionReset))]);
+        assert_eq!(service.append("alice", &upload.id, 4, broken, NOW).await.unwrap_err().0, 400);
+        assert_eq!(service.offset("alice", &upload.id, NOW).await.unwrap().1, 6);
+
+        // Only the user who started the upload can continue it, at the current offset
+        assert_eq!(service.offset("bob", &upload.id, NOW).await.unwrap_err().0, 404);
+        assert_eq!(service.append("alice", &upload.id, 4, chunks(&[b"45"]), NOW).await.unwrap_err().0, 409);
+        assert_eq!(service.append("alice", &upload.id, 6, chunks(&[b"6789", b"x"]), NOW).await.unwrap_err().0, 400);
+
+        let (upload, offset, completed) = service.append("alice", &upload.id, 10, chunks(&[]), NOW).await.unwrap();
+        assert_eq!(offset, 10);
+        let staged = completed.unwrap();
+        assert_eq!(staged.size, 10);
+        // The upload is handed over once, later requests don't find it
+        assert_eq!(service.append("alice", &upload.id, 10, chunks(&[]), NOW).await.unwrap_err().0, 404);
+        let target = root.path().join("holiday.mp4");
+        staged.persist(&target).await.unwrap();
+        assert_eq!(std::fs::read(target).unwrap(), b"0123456789");
+        assert_eq!(service.offset("alice", &upload.id, NOW).await.unwrap_err().0, 404);
+    }
+
+    #[tokio::test]
+    async fn test_refused_uploads() {
+        let root = tempdir().unwrap();
+        let service = tus_service(root.path());
+
+        assert_eq!(service.create("alice", 101, &metadata("big.iso"), NOW).await.unwrap_err().0, 413);
+        assert_eq!(service.create("alice", 1, &HashMap::new(), NOW).await.unwrap_err().0, 400);
+        assert_eq!(service.create("alice", 1, &metadata("/"), NOW).await.unwrap_err().0, 400);
+        assert_eq!(service.offset("alice", "../../alice/notes", NOW).await.unwrap_err().0, 404);
+
+        // Three unfinished uploads per user
+        for filename in ["a.bin", "b.bin", "c.bin"] {
+            service.create("alice", 10, &metadata(filename), NOW).await.unwrap();
+        }
+        assert_eq!(service.create("alice", 10, &metadata("d.bin"), NOW).await.unwrap_err().0, 429);
+        assert!(service.create("bob", 10, &metadata("d.bin"), NOW).await.is_ok());
+        // Expired ones don't count
+        assert!(service.create("alice", 10, &metadata("d.bin"), NOW + 60).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_terminate_and_expire() {
+        let root = tempdir().unwrap();
+        let service = tus_service(root.path());
+
+        let terminated = service.create("alice", 10, &metadata("a.bin"), NOW).await.unwrap();
+        assert_eq!(service.terminate("bob", &terminated.id, NOW).await.unwrap_err().0, 404);
+        service.terminate("alice", &terminated.id, NOW).await.unwrap();
+        assert_eq!(service.offset("alice", &terminated.id, NOW).await.unwrap_err().0, 404);
+
+        let expired = service.create("alice", 10, &metadata("b.bin"), NOW).await.unwrap();
+        assert_eq!(service.offset("alice", &expired.id, NOW + 60).await.unwrap_err().0, 410);
+        assert_eq!(service.offset("alice", &expired.id, NOW + 60).await.unwrap_err().0, 404);
+
+        // Creating uploads cleans up the ones nobody asked about anymore
+        let forgotten = service.create("alice", 10, &metadata("c.bin"), NOW).await.unwrap();
+        service.create("alice", 10, &metadata("d.bin"), NOW + 60).await.unwrap();
+        assert_eq!(service.offset("alice", &forgotten.id, NOW).await.unwrap_err().0, 404);
+        assert_eq!(service.remove_expired(NOW + 120).await, 1);
+    }
+
+    #[test]
+    fn test_parse_upload_metadata() {
+        let metadata = parse_upload_metadata("filename aG9saWRheS5tcDQ=, path dmlkZW9zLzIwMjQ=,is_confidential").unwrap();
+        assert_eq!(metadata["filename"], "holiday.mp4");
+        assert_eq!(metadata["path"], "videos/2024");
+        assert_eq!(metadata["is_confidential"], "");
+        assert!(parse_upload_metadata("").unwrap().is_empty());
+        assert_eq!(parse_upload_metadata("filename not-base64!").unwrap_err().0, 400);
+    }
+}
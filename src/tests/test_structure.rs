@@ -3,7 +3,26 @@ use std::fs::{create_dir, File};
 use std::io;
 use std::io::Write;
 use std::path::Path;
+use std::sync::Arc;
+use async_trait::async_trait;
 use tempfile::{tempdir, TempDir};
+use crate::app_config::AppConfig;
+use crate::dao::acl_store::AclStore;
+use crate::dao::db_credential_store::DbCredentialStore;
+use crate::dao::share_link_store::ShareLinkStore;
+use crate::dao::share_store::ShareStore;
+use crate::dao::token_revocation_store::TokenRevocationStore;
+use crate::models::acl::acl_entry::{AclEntry, AclPrincipal};
+use crate::models::shares::share::Share;
+use crate::models::shares::share_link::ShareLink;
+use crate::services::authentication::authentication_service::{AuthenticationService, TokenSettings};
+use crate::services::authentication::key_ring::KeyRing;
+use crate::services::authentication::token_revocation_service::TokenRevocationService;
+use crate::services::authentication::login_throttle::LoginThrottle;
+use crate::services::authentication::mfa_service::MfaPolicy;
+use crate::services::file_structure::archive_service::ArchiveLimits;
+use crate::services::file_structure::tus_service::TusSettings;
+use crate::services::locking::directory_locking_manager::DirectoryLockManager;
 
 pub struct TestEnv {
     pub root_dir: TempDir,
@@ -48,4 +67,167 @@ pub fn create_test_structure(root: &Path, user: &String) -> io::Result<()> {
 pub async fn get_global_test_env() -> TestEnv {
     env::set_var("JWT_TOKEN_SECRET", "some_secret_token");
     TestEnv::new()
-}
\ No newline at end of file
+}
+
+/// Revocation store that keeps nothing, the service's in-memory cache is enough for tests
+pub struct NoopTokenRevocationStore;
+
+#[async_trait]
+impl TokenRevocationStore for NoopTokenRevocationStore {
+    async fn revoke_token(&self, _jti: &str, _expires_at: i64) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn revoke_all_user_tokens(&self, _username: &str, _issued_before: i64) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn load_revoked_tokens(&self, _now: i64) -> Result<Vec<(String, i64)>, String> {
+        Ok(Vec::new())
+    }
+
+    async fn load_user_cutoffs(&self) -> Result<Vec<(String, i64)>, String> {
+        Ok(Vec::new())
+    }
+}
+
+/// ACL store without entries, file requests in tests stay inside the user's own tree
+pub struct NoopAclStore;
+
+#[async_trait]
+impl AclStore for NoopAclStore {
+    async fn get_entries(&self, _tree: &str, _paths: &[String]) -> Result<Vec<AclEntry>, String> {
+        Ok(Vec::new())
+    }
+
+    async fn get_user_groups(&self, _username: &str) -> Result<Vec<String>, String> {
+        Ok(Vec::new())
+    }
+
+    async fn set_entry(&self, _tree: &str, _entry: &AclEntry) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn remove_entry(&self, _tree: &str, _path: &str, _principal: &AclPrincipal) -> Result<bool, String> {
+        Ok(false)
+    }
+
+    async fn move_entries(&self, _tree: &str, _old_path: &str, _new_path: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn delete_entries(&self, _tree: &str, _path: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Share store without shares
+pub struct NoopShareStore;
+
+#[async_trait]
+impl ShareStore for NoopShareStore {
+    async fn create_share(&self, _share: &Share) -> Result<Option<i64>, String> {
+        Err("User not found".to_string())
+    }
+
+    async fn list_shares(&self, _username: &str, _incoming: bool) -> Result<Vec<Share>, String> {
+        Ok(Vec::new())
+    }
+
+    async fn find_share(&self, _recipient: &str, _name: &str) -> Result<Option<Share>, String> {
+        Ok(None)
+    }
+
+    async fn move_sources(&self, _owner: &str, _old_path: &str, _new_path: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn refresh_sources(&self, _owner: &str, _path: &str, _file_id: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn delete_sources(&self, _owner: &str, _path: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn delete_share(&self, _id: i64, _username: &str) -> Result<bool, String> {
+        Ok(false)
+    }
+}
+
+pub struct NoopShareLinkStore;
+
+#[async_trait]
+impl ShareLinkStore for NoopShareLinkStore {
+    async fn insert_link(&self, _link: &ShareLink, _token_hash: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn find_link(&self, _token_hash: &str) -> Result<Option<ShareLink>, String> {
+        Ok(None)
+    }
+
+    async fn list_links(&self, _owner: &str) -> Result<Vec<ShareLink>, String> {
+        Ok(Vec::new())
+    }
+
+    async fn revoke_link(&self, _owner: &str, _id: &str) -> Result<bool, String> {
+        Ok(false)
+    }
+
+    async fn move_sources(&self, _owner: &str, _old_path: &str, _new_path: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn revoke_sources(&self, _owner: &str, _path: &str) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn record_upload(&self, _id: &str, _bytes: i64, _now: i64) -> Result<bool, String> {
+        Ok(false)
+    }
+
+    async fn undo_upload(&self, _id: &str, _bytes: i64) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn record_download(&self, _id: &str, _now: i64) -> Result<bool, String> {
+        Ok(false)
+    }
+}
+
+// Every test service shares the same secret, so tokens issued by one are accepted by all
+pub fn test_authentication_service() -> AuthenticationService {
+    AuthenticationService::with_keys(
+        Some(b"some_secret_token"),
+        KeyRing::default(),
+        None,
+        TokenSettings::default(),
+        TokenRevocationService::new(Arc::new(NoopTokenRevocationStore))
+    )
+}
+
+// Builds an app config rooted at the given test directory
+pub fn test_app_config(root: &Path) -> AppConfig {
+    AppConfig {
+        root_dir: Arc::new(root.to_str().unwrap().to_string()),
+        directory_lock_manager: DirectoryLockManager::new(),
+        authentication_service: test_authentication_service(),
+        credential_store: Arc::new(DbCredentialStore),
+        acl_store: Arc::new(NoopAclStore),
+        share_store: Arc::new(NoopShareStore),
+        share_link_store: Arc::new(NoopShareLinkStore),
+        login_throttle: LoginThrottle::default(),
+        mfa_policy: MfaPolicy::default(),
+        tus_settings: TusSettings::default(),
+        archive_limits: ArchiveLimits::default(),
+        oidc_client: None
+    }
+}
+
+// Generates a valid token for a regular user
+pub fn generate_test_token(username: &str) -> String {
+    test_authentication_service()
+        .generate_token(username.to_string(), "user".to_string(), 1)
+        .expect("failed to generate token")
+}